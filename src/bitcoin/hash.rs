@@ -5,6 +5,7 @@ use crate::MtHasher;
 const HASH_CHAR_LEN: usize = 64;
 
 #[derive(Clone, PartialEq, Eq)]
+#[repr(transparent)]
 pub struct Hash {
     hash: [u8; 32]
 }
@@ -120,6 +121,21 @@ impl FromStr for Hash {
 }
 
 
+// SAFETY: `Hash` is transparent wrapper over `[u8; 32]`
+#[cfg(feature = "mmap")]
+unsafe impl crate::MtHashPod for Hash {}
+
+impl crate::MtHashBytes for Hash {
+    const LEN: usize = 32;
+
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.hash);
+    }
+    fn read_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(Self { hash: bytes.try_into().ok()? })
+    }
+}
+
 // ↑↑↑↑ Hash ↑↑↑↑
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 // ↓↓↓ Hasher ↓↓↓
@@ -160,6 +176,20 @@ impl MtHasher<Hash> for BitcoinHasher {
     }
 }
 
+impl crate::MtHasherId for BitcoinHasher {
+    const ALGORITHM: &'static str = "bitcoin-dsha256";
+}
+
+#[cfg(feature = "serde")]
+impl crate::MtHasherDescriptor for BitcoinHasher {
+    type Params = ();
+
+    fn params(&self) -> Self::Params {}
+    fn from_params(_: Self::Params) -> Self {
+        Self::new()
+    }
+}
+
 impl<Data: AsRef<[u8]>> crate::MtDataHasherStatic<Hash, Data> for BitcoinHasher {
     fn hash_data_static(data: Data) -> Hash {
         let mut hasher = Self::new();
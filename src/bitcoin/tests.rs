@@ -58,14 +58,14 @@ async fn big_bitcoin_block_test() -> Result<(), Box<dyn std::error::Error>> {
 
         // test proof validity
         let proof = tree.proof_owned(crate::LeafId::new(index_proof));
-        assert!(proof.verify(hash_valid_proof.clone(), &mut super::BitcoinHasher::new()));
-        assert!(!proof.verify(hash_invalid_proof.clone(), &mut super::BitcoinHasher::new()));
+        assert!(proof.verify_with_root(tree.root_ref(), hash_valid_proof.clone(), &mut super::BitcoinHasher::new()));
+        assert!(!proof.verify_with_root(tree.root_ref(), hash_invalid_proof.clone(), &mut super::BitcoinHasher::new()));
         
         // test proof serde
         let proof = serde_json::to_string_pretty(&proof)?;
         let proof: MtProof<Hash, { MerkleTreeBitcoin::ARITY }> = serde_json::from_str(&proof)?;
-        assert!(proof.verify(hash_valid_proof, &mut super::BitcoinHasher::new()));
-        assert!(!proof.verify(hash_invalid_proof, &mut super::BitcoinHasher::new()));
+        assert!(proof.verify_with_root(tree.root_ref(), hash_valid_proof, &mut super::BitcoinHasher::new()));
+        assert!(!proof.verify_with_root(tree.root_ref(), hash_invalid_proof, &mut super::BitcoinHasher::new()));
         
         // test tree serialize / deserialize
         let tree_serde = serde_json::to_string(&tree)?;
@@ -90,6 +90,15 @@ async fn bitcoin_block_100000_test() -> Result<(), Box<dyn std::error::Error>> {
 
     let expected_root: Hash = "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766".parse()?;
     assert_eq!(expected_root, tree.root());
+
+    let envelope = tree.proof_envelope(crate::LeafId::new(2));
+    assert_eq!(envelope.algorithm(), "bitcoin-dsha256");
+    assert!(envelope.verify(&expected_root, Hash::from_str(tx2)?, &mut super::BitcoinHasher::new()));
+
+    let proof = tree.proof_owned(crate::LeafId::new(2));
+    let bytes = proof.to_bytes();
+    assert_eq!(bytes.len(), 7 + 32 * 5);
+    assert_eq!(MtProof::from_bytes(&bytes)?, proof);
     Ok(())
 }
 
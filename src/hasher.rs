@@ -84,6 +84,34 @@ impl<Hasher: MtHasher<Hash>, Hash, const ARITY: usize> MtArityHasher<Hash, ARITY
     }
 }
 
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Identifier of the hash algorithm. 
+/// It's written into [`crate::MtEnvelope`], so verifier can reject proofs made by another hasher.
+pub trait MtHasherId {
+    /// Stable name of the algorithm, for example `"bitcoin-dsha256"`.
+    /// 
+    /// Hashers with different results **must** have different names.
+    const ALGORITHM: &'static str;
+}
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Descriptor of the hasher configuration: algorithm id & parameters that change 
+/// the result of the hash function (key, prefix, ...).\
+/// It can be recorded into [`crate::MtSerde`] (see [`crate::MtSerde::with_hasher`]), so the tree is loaded 
+/// only with the same configuration & the hasher itself is restored from the parameters.
+/// 
+/// Two hashers with equal `params` **must** be [`MtHasher::is_the_same`].
+#[cfg(feature = "serde")]
+pub trait MtHasherDescriptor: MtHasherId + Sized {
+    /// `()` for hashers without parameters.
+    type Params: serde::Serialize + serde::de::DeserializeOwned + PartialEq;
+
+    fn params(&self) -> Self::Params;
+    fn from_params(params: Self::Params) -> Self;
+}
+
 /// `Mt` stands for `MerkleTree`
 pub trait MtDataHasher<Hash, Data> {
     fn hash_data(&mut self, data: Data) -> Hash;
@@ -137,6 +165,21 @@ impl MtHasher<u64> for UnsecureHasher {
     }
 }
 
+#[cfg(any(feature = "unsecure", test))]
+impl MtHasherId for UnsecureHasher {
+    const ALGORITHM: &'static str = "unsecure-std-default";
+}
+
+#[cfg(all(any(feature = "unsecure", test), feature = "serde"))]
+impl MtHasherDescriptor for UnsecureHasher {
+    type Params = ();
+
+    fn params(&self) -> Self::Params {}
+    fn from_params(_: Self::Params) -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(any(feature = "unsecure", test))]
 impl<Data: std::hash::Hash> MtDataHasherStatic<u64, Data> for UnsecureHasher {
     fn hash_data_static(data: Data) -> u64 {
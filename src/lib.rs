@@ -10,19 +10,32 @@ mod tests;
 pub mod bitcoin;
 
 pub use merkle_tree::{MtLvl, LeafId, NodeId};
-pub use merkle_tree::{MtProofRef, MtProof};
+pub use merkle_tree::{MtProofRef, MtProof, MtDetachedProof, MtCompactProof, MtRangeProof, MtNodeProof, MtConsistencyProof, ProofError};
+pub use merkle_tree::{MtProofsIter, MtProofSet, MtBatchVerifier, MtChange};
+pub use merkle_tree::{MtHashBytes, MtDecodeError, MtEncodeError};
+pub use merkle_tree::{MtNodeStore, MtMemStore, MtFileStore, StoredMerkleTree, MtStoreError, MtStreamBuilder};
+pub use merkle_tree::{LightMerkleTree, MtLightStore};
+pub use merkle_tree::{MerkleLog, MtLogConfig, MtLogError};
+pub use merkle_tree::MtObserver;
+pub use merkle_tree::{MtOp, MtJournal, MtJournalEntry, MtCheckpoint, MtJournalError};
+pub use merkle_tree::ConcurrentMerkleTree;
+pub use merkle_tree::{MtSyncRequest, MtSyncResponse, MtSyncTransport, MtSyncError, MtSyncReport, MtSyncChannel, MtSyncChannelServer, MtSyncChannelClosed};
+#[cfg(feature = "mmap")]
+pub use merkle_tree::{MappedMerkleTree, MtHashPod, MtMapError};
 pub use merkle_tree::{MerkleTree, MerkleBinTree, MerkleTrinaryTree};
 #[cfg(feature = "serde")]
-pub use merkle_tree::{MtSerde, MerkleTreeSerdeError};
+pub use merkle_tree::{MtSerde, MtSerdeSeed, MtSerdeLimits, MtHasherConfig, MerkleTreeSerdeError, MtEnvelope, MtEnvelopeError};
 
-pub use hasher::{MtHasher, MtArityHasher, MtDataHasher, MtDataHasherStatic};
+pub use hasher::{MtHasher, MtArityHasher, MtDataHasher, MtDataHasherStatic, MtHasherId};
+#[cfg(feature = "serde")]
+pub use hasher::MtHasherDescriptor;
 
 #[cfg(any(feature = "unsecure", test))]
 pub use hasher::UnsecureHasher;
 
 pub mod prelude {
     pub use crate::{MerkleTree, MerkleBinTree, MerkleTrinaryTree};
-    pub use crate::MtProofRef;
+    pub use crate::{MtProofRef, MtDetachedProof};
     pub use crate::{LeafId, NodeId};
     pub use crate::{MtArityHasher, MtDataHasher};
 
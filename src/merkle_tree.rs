@@ -1,8 +1,37 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::ops::Range;
 use crate::utility::{get_pad_index, length_in_base};
 use crate::MtArityHasher as ArityHasher;
 use crate::MtDataHasher as DataHasher;
 use crate::MtDataHasherStatic as StaticDataHasher;
+#[cfg(feature = "serde")]
+use crate::MtHasherId as HasherId;
+#[cfg(feature = "serde")]
+use crate::MtHasherDescriptor as HasherDescriptor;
+
+mod codec;
+pub use codec::{MtHashBytes, MtDecodeError, MtEncodeError};
+mod store;
+pub use store::{MtNodeStore, MtMemStore, MtFileStore, StoredMerkleTree, MtStoreError, MtStreamBuilder};
+mod light;
+pub use light::{LightMerkleTree, MtLightStore};
+mod log;
+pub use log::{MerkleLog, MtLogConfig, MtLogError};
+mod observer;
+pub use observer::MtObserver;
+mod journal;
+pub use journal::{MtOp, MtJournal, MtJournalEntry, MtCheckpoint, MtJournalError};
+mod concurrent;
+pub use concurrent::ConcurrentMerkleTree;
+mod sync;
+pub use sync::{MtSyncRequest, MtSyncResponse, MtSyncTransport, MtSyncError, MtSyncReport, MtSyncChannel, MtSyncChannelServer, MtSyncChannelClosed};
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "mmap")]
+mod mapped;
+#[cfg(feature = "mmap")]
+pub use mapped::{MappedMerkleTree, MtHashPod, MtMapError};
 
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 // [+] Merkle Tree Level
@@ -60,6 +89,10 @@ impl<'mt_ref, Hash, const ARITY: usize> MtLvl<'mt_ref, Hash, ARITY> {
     pub fn to_vec(&self) -> &Vec<Hash> {
         self.lvl.unwrap()
     }
+    /// The same as [`Self::to_vec`], but the empty level is an empty slice
+    pub fn as_slice(&self) -> &'mt_ref [Hash] {
+        self.lvl.map(Vec::as_slice).unwrap_or(&[])
+    }
 }
 impl<'mt_ref, Hash: Clone, const ARITY: usize> MtLvl<'mt_ref, Hash, ARITY> {
     pub fn continuation(&self) -> Option<Vec<Hash>> {
@@ -182,7 +215,7 @@ impl<'mt_ref, Hash: Eq, const ARITY: usize> PartialEq for MtLvl<'mt_ref, Hash, A
 // [-] Merkle Tree Level
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct LeafId(usize);
 impl LeafId {
     #[inline(always)]
@@ -196,7 +229,8 @@ impl LeafId {
 }
 
 /// You can get NodeId by [MerkleTree::node_id_by_parent_of_leaf]
-#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct NodeId {
     pub lvl: usize,
     pub index: usize,
@@ -205,19 +239,20 @@ pub struct NodeId {
 pub type MerkleBinTree<Hash, Hasher> = MerkleTree<Hash, Hasher, 2>; 
 pub type MerkleTrinaryTree<Hash, Hasher> = MerkleTree<Hash, Hasher, 3>; 
 
+/// `Observer` is called on each change of the nodes, see [`MtObserver`] (`()` observes nothing).
 #[derive(Debug, Clone)]
-pub struct MerkleTree<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> {
+pub struct MerkleTree<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer = ()> {
     tree_lvls: Vec<Vec<Hash>>,
     hasher: Box<Hasher>,
+    observer: Observer,
 
-    add_lvl_sz: usize,
     new_lvl_cap: usize,
 }
 // TODO: diff
 // TODO: trait with tree types
 // TODO: extend / continuation to lvl & calc root (of n-th lvl)
 
-impl<Hash: Eq, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY> {
+impl<Hash: Eq, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
     pub const ARITY: usize = ARITY;
 
     /// Test equality of two trees by comparing only equality of height and root.\
@@ -269,7 +304,7 @@ impl<Hash: Eq, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<
         !self.eq_full(other)
     }
 }
-impl<Hash: Clone, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY> {
+impl<Hash: Clone, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
     /// # panic
     /// * if `self.is_empty()`
     #[inline]
@@ -284,7 +319,7 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         Self {
             tree_lvls: vec![vec![]],
             hasher: Box::new(hasher),
-            add_lvl_sz: 1,
+            observer: (),
             new_lvl_cap: 1,
         }
     }
@@ -304,7 +339,8 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         tree.push_batched_data(leafs_iter);
         tree
     }
-    
+}
+impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
     pub fn node_id_by_parent_of_leaf(&self, leaf: LeafId, lvl: usize) -> NodeId {
         let index = leaf.0 / ARITY.pow(lvl as u32);
         NodeId {
@@ -327,6 +363,10 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         &self.tree_lvls[self.height() - 1][0]
     }
     #[inline]
+    pub fn hasher(&self) -> &Hasher {
+        &self.hasher
+    }
+    #[inline]
     pub fn is_empty(&self) -> bool {
         self.tree_lvls[0].is_empty()
     }
@@ -368,8 +408,73 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
     pub fn is_valid_leaf_id(&self, id: LeafId) -> bool {
         id.0 < self.leaf_count()
     }
+
+    /// You can get NodeId by [Self::node_id_by_parent_of_leaf]
+    /// 
+    /// # Panic
+    /// * if `!self.is_valid_node_id`
+    #[inline]
+    pub fn get_node_ref(&self, node_id: NodeId) -> &Hash {
+        &self.tree_lvls[node_id.lvl][node_id.index]
+    }
 }
-impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY> {
+/// Hash group of `1..=ARITY` nodes.\
+/// Uneven group (with less than `ARITY` nodes) is padded by its last node.
+/// 
+/// ```txt
+/// ARITY = 5: 
+/// group: A B C --> hash(A B C C C)
+/// ```
+/// 
+/// # panic
+/// * if `group` is empty
+fn hash_padded_group<'a, Hash: 'a, Hasher, const ARITY: usize>(hasher: &mut Hasher, group: impl IntoIterator<Item = &'a Hash>) -> Hash
+where Hasher: ArityHasher<Hash, ARITY>
+{
+    let mut len = 0;
+    let mut last = None;
+    for hash in group {
+        hasher.hash_arity_one_ref(hash);
+        last = Some(hash);
+        len += 1;
+    }
+    let last = last.expect("group must contain at least one node");
+    for _ in len..ARITY {
+        hasher.hash_arity_one_ref(last);
+    }
+    hasher.finish_arity()
+}
+
+/// Hash each group of `ARITY` nodes of the `children` (the last group can be uneven),
+/// so it's the nodes of the upper level.
+fn hash_lvl<Hash, Hasher, const ARITY: usize>(hasher: &mut Hasher, children: &[Hash]) -> Vec<Hash>
+where Hasher: ArityHasher<Hash, ARITY>
+{
+    children.chunks(ARITY).map(|group| hash_padded_group::<_, _, ARITY>(hasher, group)).collect()
+}
+
+/// See [`MerkleTree::audit`]
+fn audit_lvls<'tree, Hash: Eq + 'tree, Hasher, const ARITY: usize>(
+    lvl_count: usize,
+    tree_lvl: impl Fn(usize) -> &'tree [Hash],
+    hasher: &mut Hasher,
+) -> Result<(), NodeId>
+where Hasher: ArityHasher<Hash, ARITY>
+{
+    for lvl in 1..lvl_count {
+        let children = tree_lvl(lvl - 1);
+        for (index, node) in tree_lvl(lvl).iter().enumerate() {
+            let start = index * ARITY;
+            let end = (start + ARITY).min(children.len());
+            if &hash_padded_group::<_, _, ARITY>(hasher, &children[start..end]) != node {
+                return Err(NodeId { lvl, index })
+            }
+        }
+    }
+    Ok(())
+}
+
+impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer: MtObserver<Hash>> MerkleTree<Hash, Hasher, ARITY, Observer> {
     #[inline]
     fn lvl_must(&self) -> usize {
         if self.is_empty() {
@@ -394,19 +499,13 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         let (from, to) = (pre_lvl_range.start, pre_lvl_range.end);
         let last_is_even = to % ARITY == 0;
 
-        // in next range all `tree_lvls[lvl - 1]` is valid
-        for elem_index in (from / ARITY)..(to / ARITY) {
-            for win_index in 0..ARITY {
-                self.hasher.hash_arity_one_ref(&self.tree_lvls[lvl - 1][elem_index * ARITY + win_index]);
-            }
-            let new_hash = self.hasher.finish_arity();
-            self.set_or_push(elem_index, lvl, new_hash);
-        }
-
-        if !last_is_even {
-            let new_hash = self.calc_possibly_uneven_group_hash(to - 1, lvl);
-            let elem_index = to / ARITY;
-            self.set_or_push(elem_index, lvl, new_hash);
+        // groups of the range, the last one can be uneven
+        let start = from / ARITY;
+        let pre_lvl = &self.tree_lvls[lvl - 1];
+        let children = &pre_lvl[start * ARITY..(to.div_ceil(ARITY) * ARITY).min(pre_lvl.len())];
+        let new_hashes = hash_lvl::<_, _, ARITY>(&mut *self.hasher, children);
+        for (offset, new_hash) in new_hashes.into_iter().enumerate() {
+            self.set_or_push(start + offset, lvl, new_hash);
         }
 
         last_is_even
@@ -426,25 +525,29 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
     /// * if `lvl` is `0` 
     fn calc_possibly_uneven_group_hash(&mut self, elem_n_from_group: usize, lvl: usize) -> Hash {
         let group_from = elem_n_from_group - (elem_n_from_group % ARITY);
-        for i in 0..ARITY {
-            if let Some(hash) = self.tree_lvls[lvl - 1].get(group_from + i) {
-                self.hasher.hash_arity_one_ref(hash);
-            } else {
-                for _ in i..ARITY {
-                    let hash = self.tree_lvls[lvl - 1].get(group_from + i - 1).unwrap();
-                    self.hasher.hash_arity_one_ref(hash);
-                }
-                break;
-            }
-        }
-        self.hasher.finish_arity()
+        let pre_lvl = &self.tree_lvls[lvl - 1];
+        let group_to = (group_from + ARITY).min(pre_lvl.len());
+        hash_padded_group::<_, _, ARITY>(&mut *self.hasher, &pre_lvl[group_from..group_to])
     }
 
     fn set_or_push(&mut self, index: usize, lvl: usize, new_hash: Hash) {
+        let node = NodeId { lvl, index };
         if let Some(prev_hash) = self.tree_lvls[lvl].get_mut(index) {
-            *prev_hash = new_hash;
+            let old = std::mem::replace(prev_hash, new_hash);
+            self.observer.on_node(node, Some(&old), Some(prev_hash));
         } else {
             self.tree_lvls[lvl].push(new_hash);
+            self.observer.on_node(node, None, self.tree_lvls[lvl].last());
+        }
+    }
+
+    /// Add `nodes` to the end of the level `lvl`.
+    fn extend_lvl(&mut self, lvl: usize, nodes: impl IntoIterator<Item = Hash>) {
+        let start = self.lvl_len(lvl);
+        self.tree_lvls[lvl].extend(nodes);
+        if !Observer::ENABLED { return }
+        for (index, hash) in self.tree_lvls[lvl].iter().enumerate().skip(start) {
+            self.observer.on_node(NodeId { lvl, index }, None, Some(hash));
         }
     }
 
@@ -465,13 +568,11 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
     /// [`Self::push_batched`] & [`Self::push_batched_data`] 
     /// they are faster.
     pub fn push(&mut self, hash: Hash) -> LeafId {
-        if self.leaf_count() == self.add_lvl_sz {
-            self.add_lvl_sz *= ARITY;
-            self.tree_lvls.push(Vec::with_capacity(self.new_lvl_cap));
-        }
-
         let elem_n = self.leaf_count();
         self.tree_lvls[0].push(hash);
+        self.observer.on_node(NodeId { lvl: 0, index: elem_n }, None, self.tree_lvls[0].last());
+        // the tree became higher (if leaf amount before push was `pow(ARITY, height - 1)`)
+        self.make_lvl_valid(self.lvl_must() - 1, 1);
         self.recalc_elem_hashes(elem_n);
 
         LeafId::new(self.leaf_count() - 1)
@@ -493,6 +594,7 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
     pub fn replace(&mut self, mut hash: Hash, id: LeafId) -> Hash {
         let elem_n = id.0;
         std::mem::swap(&mut hash, &mut self.tree_lvls[0][elem_n]);
+        self.observer.on_node(NodeId { lvl: 0, index: elem_n }, Some(&hash), Some(&self.tree_lvls[0][elem_n]));
         self.recalc_elem_hashes(elem_n);
         hash
     }
@@ -541,6 +643,13 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
     /// # panic
     /// * if `start_id` > `last_leaf_id`
     pub fn replace_batched(&mut self, batch: impl IntoIterator<Item = Hash>, start_id: LeafId) -> std::ops::Range<LeafId> {
+        let range = self.replace_leafs(batch, start_id);
+        self.recalc_upper_lvls(range.start.0, range.end.0, |tree, pre_lvl_range, lvl| tree.calc_lvl_hashes(pre_lvl_range, lvl));
+        range
+    }
+
+    /// Leaf level of [`Self::replace_batched`], upper levels are not updated.
+    fn replace_leafs(&mut self, batch: impl IntoIterator<Item = Hash>, start_id: LeafId) -> std::ops::Range<LeafId> {
         if start_id > self.next_leaf_id() {
             panic!("invalid `start_id`")
         }
@@ -558,34 +667,33 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
                 to = index;
                 break
             };
-            self.tree_lvls[0][index] = next_hash;
+            let old = std::mem::replace(&mut self.tree_lvls[0][index], next_hash);
+            self.observer.on_node(NodeId { lvl: 0, index }, Some(&old), Some(&self.tree_lvls[0][index]));
         }
 
         // if batch not ended during replacing -- add rest hashes to the end of leaf level
         if !ended {
-            self.tree_lvls[0].extend(batch);
+            self.extend_lvl(0, batch);
             to = self.leaf_count();
         }
-        let to = LeafId::new(to);
+        from..LeafId::new(to)
+    }
 
-        // update hashes on next levels:
-        if from != to {
-            let mut from = from.0;
-            let mut to = to.0;
-            let mut lvl = 1;
-            let lvl_must = self.lvl_must();
+    /// Update hashes on levels above the changed leafs `from..to`,
+    /// `calc_lvl` is [`Self::calc_lvl_hashes`] or an equal one.
+    fn recalc_upper_lvls(&mut self, mut from: usize, mut to: usize, mut calc_lvl: impl FnMut(&mut Self, Range<usize>, usize) -> bool) {
+        if from == to { return }
+        let mut lvl = 1;
+        let lvl_must = self.lvl_must();
 
-            while lvl != lvl_must {
-                self.make_lvl_valid(lvl, (to / ARITY - from / ARITY) + 1);
-                let last_is_even = self.calc_lvl_hashes(from..to, lvl);
-                
-                lvl += 1;
-                from /= ARITY;
-                to = (to / ARITY) + (!last_is_even) as usize;
-            }
+        while lvl != lvl_must {
+            self.make_lvl_valid(lvl, (to / ARITY - from / ARITY) + 1);
+            let last_is_even = calc_lvl(self, from..to, lvl);
+            
+            lvl += 1;
+            from /= ARITY;
+            to = (to / ARITY) + (!last_is_even) as usize;
         }
-
-        from..to
     }
 
     /// It is effective if leaf amount is `pow(ARITY, exp)` & all `MerkleTree`s have the same len.\
@@ -610,11 +718,12 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
     /// place big trees that satisfy the two previous conditions. 
     /// 
     /// It can be used for parallelism (accept only trees with some leaf amount, or last trees, if it is time to create tree).
+    /// For a single big batch there are `*_par` methods with the feature `rayon` (for example `new_from_leafs_par`).
     /// 
     /// # returns
     /// * [`None`] if `iter` is empty 
     /// * [`Some`] of merged tree otherwise
-    pub fn merge(&mut self, iter: impl IntoIterator<Item = Self>) {
+    pub fn merge<Other>(&mut self, iter: impl IntoIterator<Item = MerkleTree<Hash, Hasher, ARITY, Other>>) {
         for other in iter {
             if other.leaf_count() == 0 { continue }
             let other_height = other.height();
@@ -629,7 +738,7 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
                     recalc_index = Some(from_index / ARITY);
                 } else {
                     let left_len = self.lvl_len(lvl);
-                    self.tree_lvls[lvl].extend(tree_lvl);
+                    self.extend_lvl(lvl, tree_lvl);
                     if left_len % ARITY != 0 {
                         recalc_index = Some(left_len);
                     }
@@ -649,17 +758,8 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
             }
         }
     }
-
-    /// You can get NodeId by [Self::node_id_by_parent_of_leaf]
-    /// 
-    /// # Panic
-    /// * if `!self.is_valid_node_id`
-    #[inline]
-    pub fn get_node_ref(&self, node_id: NodeId) -> &Hash {
-        &self.tree_lvls[node_id.lvl][node_id.index]
-    }
 }
-impl<Hash: Clone, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY> {
+impl<Hash: Clone, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
     /// You can get NodeId by [Self::node_id_by_parent_of_leaf]
     /// 
     /// # Panic
@@ -701,7 +801,7 @@ impl<Hash: Clone, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTr
         hasher.finish_arity()
     }
 }
-impl<Hash: Clone + Eq, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY> {
+impl<Hash: Clone + Eq, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
     /// Verify if node `node_id` have correct hash.
     /// 
     /// You can get NodeId by [Self::node_id_by_parent_of_leaf]
@@ -711,20 +811,32 @@ impl<Hash: Clone + Eq, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> Mer
     pub fn verify_node(&self, node_id: NodeId, hasher: &mut Hasher) -> bool {
         self.recalc_node(node_id, hasher) == self.tree_lvls[node_id.lvl][node_id.index]
     }
+
+    /// Verify hashes of all the nodes (level by level, from the leafs to the root).
+    ///
+    /// Needs for trees that were loaded without rehashing,
+    /// see [`MtSerde::to_merkle_tree_lazy`].
+    ///
+    /// # Errors
+    /// * first node that is not equal to the hash of its children
+    pub fn audit(&mut self) -> Result<(), NodeId> {
+        let tree_lvls = &self.tree_lvls;
+        audit_lvls::<_, _, ARITY>(tree_lvls.len(), |lvl| &tree_lvls[lvl], self.hasher.as_mut())
+    }
 }
-impl<Hash: Clone, Hasher: Clone + ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY> {
+impl<Hash: Clone, Hasher: Clone + ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
     /// Last tree can have less height than `lvl`.
     /// 
     /// # painc
     /// * if `lvl >= self.height()`    
-    pub fn split(&self, lvl: usize) -> Vec<Self> {
+    pub fn split(&self, lvl: usize) -> Vec<MerkleTree<Hash, Hasher, ARITY>> {
+        let hasher =self.hasher.as_ref();
         if lvl == 0 && self.is_empty() {
-            return vec![self.clone()]
+            return vec![MerkleTree::new_minimal(hasher.clone())]
         }
 
         let len = self.lvl_len(lvl);
-        let hasher =self.hasher.as_ref();
-        let mut trees: Vec<Self> = (0..len).into_iter().map(|_|Self::new_minimal(hasher.clone())).collect();
+        let mut trees: Vec<MerkleTree<Hash, Hasher, ARITY>> = (0..len).into_iter().map(|_|MerkleTree::new_minimal(hasher.clone())).collect();
         trees.iter_mut().for_each(|tree|tree.tree_lvls.clear());
 
         for cur_lvl in 0..=lvl {
@@ -746,7 +858,7 @@ impl<Hash: Clone, Hasher: Clone + ArityHasher<Hash, ARITY>, const ARITY: usize>
         trees
     }
 }
-impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY>
+impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer: MtObserver<Hash>> MerkleTree<Hash, Hasher, ARITY, Observer>
 {
     pub fn hash_data_static<Data>(data: Data) -> Hash
     where Hasher: StaticDataHasher<Hash, Data>
@@ -798,17 +910,24 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         let Some(removed) = self.tree_lvls[0].pop() else {
             return None
         };
+        self.observer.on_node(NodeId { lvl: 0, index: self.leaf_count() }, Some(&removed), None);
         
         let height = self.lvl_must();
-        let truncate_to = height.max(1);
-        self.tree_lvls.truncate(truncate_to);
+        let truncate_to = height.max(1).min(self.tree_lvls.len());
+        for (offset, removed_lvl) in self.tree_lvls.drain(truncate_to..).enumerate() {
+            for (index, hash) in removed_lvl.iter().enumerate() {
+                self.observer.on_node(NodeId { lvl: truncate_to + offset, index }, Some(hash), None);
+            }
+        }
 
         let mut pre_len = self.leaf_count();
         let mut pop_stage = true;
 
         for lvl in 1..height {
             if pop_stage && pre_len % ARITY == 0 {
-                self.tree_lvls[lvl].pop();
+                if let Some(old) = self.tree_lvls[lvl].pop() {
+                    self.observer.on_node(NodeId { lvl, index: self.lvl_len(lvl) }, Some(&old), None);
+                }
                 pre_len = self.lvl_len(lvl);
             } else {
                 pop_stage = false;
@@ -851,33 +970,32 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         self.replace(hash_b, id_a);
     }
 }
-impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY> {
+impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
     /// # panic 
     /// * if `!self.is_valid_leaf_id(id)`
     pub fn proof_ref(&self, id: LeafId) -> MtProofRef<'_, Hash, ARITY> {
         assert!(self.height() != 0);
+        self.proof_ref_from_node(NodeId { lvl: 0, index: id.0 })
+    }
 
-        let mut index = id.0;
-        let mut lvl = 0;
-        let mut tree_lvl_nodes = vec![];
-        let mut tree_lvl_path = vec![];
-
-        while lvl + 1 < self.height() {
-            let tree_lvl = &self.tree_lvls[lvl];
+    /// Proof from node `node` up to the root.
+    fn proof_ref_from_node(&self, node: NodeId) -> MtProofRef<'_, Hash, ARITY> {
+        lvls_proof_ref(&self.tree_lvls, node)
+    }
 
-            let next_index = index / ARITY;
-            let index_start = next_index * ARITY;
-            let index_end = (index_start + ARITY).min(tree_lvl.len());
-            tree_lvl_nodes.push(&tree_lvl[index_start..index_end]);
-            tree_lvl_path.push(index % ARITY);
-            index = next_index;
-            lvl += 1;
-        }
+    /// Proofs of all leafs (in order of leafs), see [`MtProofsIter`].
+    /// 
+    /// It's much faster than [`Self::proof_owned`] for each leaf.
+    pub fn proofs_iter(&self) -> MtProofsIter<'_, Hash, ARITY> {
+        MtProofsIter::new(&self.tree_lvls)
+    }
 
-        return MtProofRef {
-            tree_lvl_nodes,
-            tree_lvl_path,
-            root: self.root_ref(),
+    /// Proofs of all leafs that share nodes of the tree, see [`MtProofSet`].
+    pub fn all_proofs(&self) -> MtProofSet<Hash, ARITY>
+    where Hash: Clone
+    {
+        MtProofSet {
+            tree_lvls: self.tree_lvls[..self.height().max(1)].to_vec(),
         }
     }
 
@@ -893,6 +1011,119 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         self.proof_ref(id).to_owned()
     }
 
+    /// Proof without embedded root, see [`MtDetachedProof`].
+    /// 
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn proof_detached(&self, id: LeafId) -> MtDetachedProof<Hash, ARITY>
+    where Hash: Clone
+    {
+        self.proof_owned(id).detach()
+    }
+
+    /// Proof that contains only true siblings, see [`MtCompactProof`].
+    /// 
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn proof_compact(&self, id: LeafId) -> MtCompactProof<Hash, ARITY>
+    where Hash: Clone
+    {
+        self.proof_ref(id).to_compact()
+    }
+
+    /// Proof that node `node` (for example root of a subtree) is in the tree, see [`MtNodeProof`].
+    /// 
+    /// # panic
+    /// * if `node` is not in the tree
+    pub fn node_proof(&self, node: NodeId) -> MtNodeProof<Hash, ARITY>
+    where Hash: Clone
+    {
+        assert!(node.lvl < self.height(), "level of the node must be in the tree");
+        assert!(node.index < self.lvl_len(node.lvl), "node must be in the tree");
+        MtNodeProof {
+            node,
+            proof: self.proof_ref_from_node(node).to_owned(),
+        }
+    }
+
+    /// Current hashes of all nodes that depend on leafs `changed`, see [`MtChange`].
+    /// 
+    /// # panic
+    /// * if `changed` is empty
+    /// * if `changed` is not in the tree 
+    pub fn change_record(&self, changed: Range<LeafId>) -> MtChange<Hash, ARITY>
+    where Hash: Clone
+    {
+        let (mut start, mut end) = (changed.start.0, changed.end.0);
+        assert!(start < end, "range of changed leafs must not be empty");
+        assert!(end <= self.leaf_count(), "changed leafs must be in the tree");
+
+        let lvls = self.height() - 1;
+        let mut tree_lvl_changes = Vec::with_capacity(lvls);
+        for tree_lvl in &self.tree_lvls[..lvls] {
+            tree_lvl_changes.push((start, tree_lvl[start..end].to_vec()));
+            start /= ARITY;
+            end = end.div_ceil(ARITY);
+        }
+
+        MtChange {
+            tree_lvl_changes,
+            root: self.root(),
+            tree_size: self.leaf_count(),
+        }
+    }
+
+    /// Proof that the tree with the first `old_size` leafs is a prefix of this tree, see [`MtConsistencyProof`].
+    /// 
+    /// # panic
+    /// * if `old_size == 0`
+    /// * if `old_size > self.leaf_count()`
+    pub fn consistency_proof(&self, old_size: usize) -> MtConsistencyProof<Hash, ARITY>
+    where Hash: Clone
+    {
+        assert!(old_size > 0, "old tree must be non-empty");
+        assert!(old_size <= self.leaf_count(), "old tree must be a prefix of the tree");
+        MtConsistencyProof {
+            old_size,
+            proof: self.proof_detached(LeafId(old_size - 1)),
+        }
+    }
+
+    /// Proof of contiguous range of leafs, see [`MtRangeProof`].
+    /// 
+    /// # panic
+    /// * if `range` is empty
+    /// * if `range` is not in the tree 
+    pub fn range_proof(&self, range: Range<LeafId>) -> MtRangeProof<Hash, ARITY>
+    where Hash: Clone
+    {
+        let (mut start, mut end) = (range.start.0, range.end.0);
+        assert!(start < end, "range of leafs must not be empty");
+        assert!(end <= self.leaf_count(), "range of leafs must be in the tree");
+
+        let lvls = self.height() - 1;
+        let mut left = Vec::with_capacity(lvls);
+        let mut right = Vec::with_capacity(lvls);
+        for tree_lvl in &self.tree_lvls[..lvls] {
+            let group_start = start - start % ARITY;
+            let group_end = (end.div_ceil(ARITY) * ARITY).min(tree_lvl.len());
+            left.push(tree_lvl[group_start..start].to_vec());
+            right.push(tree_lvl[end..group_end].to_vec());
+
+            start /= ARITY;
+            end = end.div_ceil(ARITY);
+        }
+
+        MtRangeProof {
+            start: range.start.0,
+            end: range.end.0,
+            left,
+            right,
+            root: self.root(),
+            tree_size: self.leaf_count(),
+        }
+    }
+
     #[cfg(feature = "serde")]
     pub fn serializable(&self) -> MtSerde<Hash, ARITY>
     where Hash: Clone
@@ -900,11 +1131,43 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         MtSerde::from_merkle_tree(&self)
     }
 
-    pub fn subtree_by_height(&self, hasher: Hasher, child: LeafId, height: usize) -> Self
+    /// Serializable form with all the levels, see [`MtSerde::from_merkle_tree_full`].
+    #[cfg(feature = "serde")]
+    pub fn serializable_full(&self) -> MtSerde<Hash, ARITY>
+    where Hash: Clone
+    {
+        MtSerde::from_merkle_tree_full(self)
+    }
+
+    /// Serializable form with the hasher configuration, see [`MtSerde::with_hasher`].
+    #[cfg(feature = "serde")]
+    pub fn serializable_with_hasher(&self) -> MtSerde<Hash, ARITY, Hasher::Params>
+    where 
+        Hash: Clone,
+        Hasher: HasherDescriptor,
+    {
+        MtSerde::from_merkle_tree(self).with_hasher(self.hasher.as_ref())
+    }
+
+    /// Proof wrapped into self-describing envelope, see [`MtEnvelope`].
+    /// 
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    #[cfg(feature = "serde")]
+    pub fn proof_envelope(&self, id: LeafId) -> MtEnvelope<MtProof<Hash, ARITY>>
+    where 
+        Hash: Clone,
+        Hasher: HasherId,
+    {
+        let proof = self.proof_owned(id);
+        MtEnvelope::new::<Hasher, ARITY>(Some(id.0), self.leaf_count(), proof)
+    }
+
+    pub fn subtree_by_height(&self, hasher: Hasher, child: LeafId, height: usize) -> MerkleTree<Hash, Hasher, ARITY>
     where Hash: Clone
     {
         if height == 0 {
-            return Self::new_minimal(hasher);
+            return MerkleTree::new_minimal(hasher);
         }
 
         let child_amount = ARITY.pow((height - 1) as u32);
@@ -913,13 +1176,13 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         self.subtree_inner(hasher, child_min, child_max, height)
     }
 
-    pub fn subtree_by_leaf(&self, hasher: Hasher, leafs: impl IntoIterator<Item = LeafId>) -> Self
+    pub fn subtree_by_leaf(&self, hasher: Hasher, leafs: impl IntoIterator<Item = LeafId>) -> MerkleTree<Hash, Hasher, ARITY>
     where Hash: Clone
     {
         let mut leafs = leafs.into_iter();
 
         let Some(first) = leafs.next() else {
-            return Self::new_minimal(hasher);
+            return MerkleTree::new_minimal(hasher);
         };
 
         let mut child_min = first.index();
@@ -936,7 +1199,7 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         }
 
         if self.leaf_count() <= child_min {
-            return Self::new_minimal(hasher);
+            return MerkleTree::new_minimal(hasher);
         }
 
         let mut height = 1;
@@ -954,14 +1217,14 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
         self.subtree_inner(hasher, child_min, child_max, height)
     }
     
-    pub fn subtree_inner(&self, hasher: Hasher, mut child_min: usize, mut child_max: usize, height: usize) -> Self
+    pub fn subtree_inner(&self, hasher: Hasher, mut child_min: usize, mut child_max: usize, height: usize) -> MerkleTree<Hash, Hasher, ARITY>
     where Hash: Clone
     {
         if height >= self.height() {
-            return Self {
+            return MerkleTree {
                 tree_lvls: self.tree_lvls.clone(),
                 hasher: Box::new(hasher),
-                add_lvl_sz: self.add_lvl_sz,
+                observer: (),
                 new_lvl_cap: self.new_lvl_cap,
             }
         }
@@ -980,10 +1243,10 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
             child_amount /= ARITY;
         }
         
-        Self {
+        MerkleTree {
             tree_lvls,
             hasher: Box::new(hasher),
-            add_lvl_sz: self.add_lvl_sz,
+            observer: (),
             new_lvl_cap: self.new_lvl_cap,
         }
     }
@@ -992,53 +1255,225 @@ impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> MerkleTree<Hash
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 // [+] MerkleTree Serde
 
+/// The hasher configuration is recorded, see [`MtSerde::with_hasher`].
 #[cfg(feature = "serde")]
 impl<
     Hash: serde::Serialize, 
-    Hasher: ArityHasher<Hash, ARITY>, 
+    Hasher: ArityHasher<Hash, ARITY> + HasherDescriptor, 
     const ARITY: usize
 > serde::Serialize for MerkleTree<Hash, Hasher, ARITY> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where S: serde::Serializer
     {
-        MtSerdeRef::from_merkle_tree(self).serialize(serializer)
+        MtSerdeRef::from_merkle_tree(self).with_hasher(self.hasher.as_ref()).serialize(serializer)
     }
 }
+/// The hasher is restored from the recorded configuration (input made by another algorithm is rejected),
+/// [`Default`] hasher is used for input without configuration.
 #[cfg(feature = "serde")]
 impl<'de, 
     Hash: serde::Deserialize<'de> + Eq + Clone + std::fmt::Debug, 
-    Hasher: ArityHasher<Hash, ARITY> + Default, 
+    Hasher: ArityHasher<Hash, ARITY> + HasherDescriptor + Default, 
     const ARITY: usize
 > serde::Deserialize<'de> for MerkleTree<Hash, Hasher, ARITY> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where D: serde::Deserializer<'de>
     {
-        let mt_serde = MtSerde::deserialize(deserializer)?;
-        mt_serde.to_merkle_tree(Hasher::default()).map_err(serde::de::Error::custom)
+        let mt_serde = MtSerde::<Hash, ARITY, Hasher::Params>::deserialize(deserializer)?;
+        let tree = match mt_serde.hasher_config() {
+            Some(_) => mt_serde.to_merkle_tree_restored(),
+            None => mt_serde.to_merkle_tree(Hasher::default()),
+        };
+        tree.map_err(serde::de::Error::custom)
     }
 }
 
 #[cfg(feature = "serde")]
 #[derive(thiserror::Error, Debug)]
 pub enum MerkleTreeSerdeError<Hash> {
-    #[error("Invalid arity. Expected arity {0}, but it was {1}. Just use correct arity for the tree.")]
+    #[error("Invalid arity. Expected arity {0}, but the input has arity {1}. The input was made for another tree (or it was tampered with).")]
     InvalidArity(usize, usize),
-    #[error("Wrong root(expected: {0:?}; was: {1:?}). There is potentially an error in the algorithm. Please report this.")]
+    #[error("Wrong root (input: {0:?}; calculated: {1:?}). The input was tampered with (or it was made by another hasher).")]
     WrongRoot(Hash, Hash),
-    #[error("Tree must be empty, but it's not?!")]
+    #[error("Input must have a root if and only if it has leafs. The input was tampered with.")]
     ExpectedEmptyTree,
+    #[error("Input has {actual} levels above the leafs, but {expected} levels are expected for its leafs. The input was tampered with.")]
+    WrongLvlCount { expected: usize, actual: usize },
+    #[error("Input level {lvl} has {actual} nodes, but {expected} nodes are expected. The input was tampered with.")]
+    WrongLvlLen { lvl: usize, expected: usize, actual: usize },
+    #[error("Node {0:?} of the input is not equal to the hash of its children. The input was tampered with.")]
+    TamperedNode(NodeId),
+    #[error("Input has more than {max} {what}. The input exceeds the limit (or it was tampered with).")]
+    LimitExceeded { what: &'static str, max: usize },
+    #[error("Input has no hasher configuration, so it can't be tested that it was made by the same hasher.")]
+    MissingHasher,
+    #[error("Input was made by hash algorithm {actual:?}, but it's loaded with {expected:?}.")]
+    HasherMismatch { expected: String, actual: String },
+    #[error("Input was made by hash algorithm {0:?} with other parameters (or it was tampered with).")]
+    HasherParamsMismatch(String),
+}
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Hasher configuration recorded into [`MtSerde`], see [`MtHasherDescriptor`](crate::MtHasherDescriptor).
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MtHasherConfig<Params> {
+    algorithm: String,
+    params: Params,
+}
+#[cfg(feature = "serde")]
+impl<Params> MtHasherConfig<Params> {
+    pub fn from_hasher<Hasher: HasherDescriptor<Params = Params>>(hasher: &Hasher) -> Self {
+        Self {
+            algorithm: Hasher::ALGORITHM.to_string(),
+            params: hasher.params(),
+        }
+    }
+
+    pub fn algorithm(&self) -> &str {
+        &self.algorithm
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Test that the config is made by `Hasher` with the same parameters as `hasher`.
+    pub fn check<Hash, Hasher>(&self, hasher: &Hasher) -> Result<(), MerkleTreeSerdeError<Hash>>
+    where 
+        Hasher: HasherDescriptor<Params = Params>,
+        Params: PartialEq,
+    {
+        self.check_algorithm::<Hash, Hasher>()?;
+        if self.params != hasher.params() {
+            return Err(MerkleTreeSerdeError::HasherParamsMismatch(self.algorithm.clone()))
+        }
+        Ok(())
+    }
+
+    /// Restore the hasher from the parameters if the config is made by `Hasher`.
+    pub fn restore<Hash, Hasher>(self) -> Result<Hasher, MerkleTreeSerdeError<Hash>>
+    where Hasher: HasherDescriptor<Params = Params>,
+    {
+        self.check_algorithm::<Hash, Hasher>()?;
+        Ok(Hasher::from_params(self.params))
+    }
+
+    fn check_algorithm<Hash, Hasher: HasherId>(&self) -> Result<(), MerkleTreeSerdeError<Hash>> {
+        if self.algorithm != Hasher::ALGORITHM {
+            let expected = Hasher::ALGORITHM.to_string();
+            return Err(MerkleTreeSerdeError::HasherMismatch { expected, actual: self.algorithm.clone() })
+        }
+        Ok(())
+    }
+}
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Limits that are applied while [`MtSerde`] is deserialized by [`MtSerdeSeed`].\
+/// [`serde::Deserialize`] of [`MtSerde`] & [`MerkleTree`] applies [`MtSerdeLimits::default`],
+/// so use [`MtSerdeSeed`] with [`MtSerdeLimits::unlimited`] for bigger trusted input.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MtSerdeLimits {
+    /// Max amount of leafs.\
+    /// All the levels above the leafs (if any) together can't have more than `2 * max_leafs` nodes.
+    /// 
+    /// Deserialization stops as soon as the limit is exceeded, so memory is never allocated for more.
+    pub max_leafs: usize,
+    /// Reject the input with another arity as soon as `arity` is read.\
+    /// `arity` is serialized before the levels, so the levels of such input are never read.
+    /// 
+    /// If `false` then the arity is tested only by [`MtSerde::to_merkle_tree`].
+    pub early_arity_check: bool,
+}
+#[cfg(feature = "serde")]
+impl MtSerdeLimits {
+    /// `2^24` leafs
+    pub const DEFAULT_MAX_LEAFS: usize = 1 << 24;
+
+    /// Limits for trusted input
+    pub fn unlimited() -> Self {
+        Self {
+            max_leafs: usize::MAX,
+            early_arity_check: true,
+        }
+    }
+}
+#[cfg(feature = "serde")]
+impl Default for MtSerdeLimits {
+    fn default() -> Self {
+        Self {
+            max_leafs: Self::DEFAULT_MAX_LEAFS,
+            early_arity_check: true,
+        }
+    }
 }
+
 #[cfg(feature = "serde")]
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize)]
 /// `Mt` stands for `MerkleTree`
-pub struct MtSerde<Hash, const ARITY: usize> {
+/// 
+/// Serializable form of [`MerkleTree`]: the leafs and the root, 
+/// optionally with all the levels above the leafs (see [`Self::from_merkle_tree_full`])
+/// & the hasher configuration (see [`Self::with_hasher`]).
+pub struct MtSerde<Hash, const ARITY: usize, Params = ()> {
     leafs: Vec<Hash>,
     root: Option<Hash>,
     arity: usize,
+    /// Levels above the leafs (the last one is the root level)
+    lvls: Option<Vec<Vec<Hash>>>,
+    hasher: Option<MtHasherConfig<Params>>,
 }
 #[cfg(feature = "serde")]
-impl<Hash, const ARITY: usize> MtSerde<Hash, ARITY> {
+impl<Hash, const ARITY: usize, Params> MtSerde<Hash, ARITY, Params> {
+    /// Load the tree and verify it eagerly: 
+    /// * the recorded hasher configuration (if any) is tested, see [`Self::check_hasher`]
+    /// * without levels in the input the tree is rebuilt from the leafs
+    /// * with levels in the input every node is tested, see [`MerkleTree::audit`]
     pub fn to_merkle_tree<Hasher>(self, hasher: Hasher) -> Result<MerkleTree<Hash, Hasher, ARITY>, MerkleTreeSerdeError<Hash>>
+    where
+        Hash: Eq + Clone,
+        Hasher: ArityHasher<Hash, ARITY> + HasherDescriptor<Params = Params>,
+        Params: PartialEq,
+    {
+        self.check_recorded_hasher(&hasher)?;
+        self.to_merkle_tree_unchecked(hasher)
+    }
+
+    /// The same as [`Self::to_merkle_tree`], but the recorded hasher configuration is ignored,
+    /// so it's for hashers without [`MtHasherDescriptor`](crate::MtHasherDescriptor).
+    pub fn to_merkle_tree_unchecked<Hasher>(self, hasher: Hasher) -> Result<MerkleTree<Hash, Hasher, ARITY>, MerkleTreeSerdeError<Hash>>
+    where
+        Hash: Eq + Clone,
+        Hasher: ArityHasher<Hash, ARITY>
+    {
+        let has_lvls = self.lvls.is_some();
+        let mut tree = self.load_lazy(hasher)?;
+        if has_lvls {
+            tree.audit().map_err(MerkleTreeSerdeError::TamperedNode)?;
+        }
+        Ok(tree)
+    }
+
+    /// Load the tree without rehashing if the input has all the levels.\
+    /// Only the recorded hasher configuration (if any), the shape of the levels and the root are tested, 
+    /// so call [`MerkleTree::audit`] before the tree is trusted.
+    /// 
+    /// Without levels in the input it's the same as [`Self::to_merkle_tree`].
+    pub fn to_merkle_tree_lazy<Hasher>(self, hasher: Hasher) -> Result<MerkleTree<Hash, Hasher, ARITY>, MerkleTreeSerdeError<Hash>>
+    where
+        Hash: Eq + Clone,
+        Hasher: ArityHasher<Hash, ARITY> + HasherDescriptor<Params = Params>,
+        Params: PartialEq,
+    {
+        self.check_recorded_hasher(&hasher)?;
+        self.load_lazy(hasher)
+    }
+
+    /// See [`Self::to_merkle_tree_lazy`]
+    fn load_lazy<Hasher>(self, hasher: Hasher) -> Result<MerkleTree<Hash, Hasher, ARITY>, MerkleTreeSerdeError<Hash>>
     where
         Hash: Eq + Clone,
         Hasher: ArityHasher<Hash, ARITY>
@@ -1046,71 +1481,428 @@ impl<Hash, const ARITY: usize> MtSerde<Hash, ARITY> {
         if self.arity != ARITY {
             return Err(MerkleTreeSerdeError::InvalidArity(ARITY, self.arity));
         }
+        if self.leafs.is_empty() != self.root.is_none() {
+            return Err(MerkleTreeSerdeError::ExpectedEmptyTree);
+        }
 
-        let tree = MerkleTree::new_from_leafs(hasher, self.leafs);
-        if let Some(root) = self.root {
-            if tree.root_ref() != &root {
+        let Some(lvls) = self.lvls else {
+            let tree = MerkleTree::new_from_leafs(hasher, self.leafs);
+            if let Some(root) = self.root && tree.root_ref() != &root {
                 return Err(MerkleTreeSerdeError::WrongRoot(root, tree.root()));
             }
-        } else {
-            if !tree.is_empty() {
-                return Err(MerkleTreeSerdeError::ExpectedEmptyTree);
+            return Ok(tree)
+        };
+
+        let mut lvl_lens = vec![];
+        let mut len = self.leafs.len();
+        while len > 1 {
+            len = len.div_ceil(ARITY);
+            lvl_lens.push(len);
+        }
+        if lvls.len() != lvl_lens.len() {
+            return Err(MerkleTreeSerdeError::WrongLvlCount { expected: lvl_lens.len(), actual: lvls.len() });
+        }
+        for (lvl, (nodes, &expected)) in lvls.iter().zip(&lvl_lens).enumerate() {
+            if nodes.len() != expected {
+                let lvl = lvl + 1;
+                return Err(MerkleTreeSerdeError::WrongLvlLen { lvl, expected, actual: nodes.len() });
             }
         }
 
+        let mut tree = MerkleTree::new_minimal(hasher);
+        tree.tree_lvls = std::iter::once(self.leafs).chain(lvls).collect();
+        if let Some(root) = self.root && tree.root_ref() != &root {
+            return Err(MerkleTreeSerdeError::WrongRoot(root, tree.root()));
+        }
         Ok(tree)
     }
-    pub fn from_merkle_tree<Hasher>(mt: &MerkleTree<Hash, Hasher, ARITY>) -> Self
-    where
-        Hash: Clone,
-        Hasher: ArityHasher<Hash, ARITY>
+
+    /// `true` if all the levels are in the serialized form.
+    pub fn has_lvls(&self) -> bool {
+        self.lvls.is_some()
+    }
+
+    /// [`None`] if the hasher configuration wasn't recorded.
+    pub fn hasher_config(&self) -> Option<&MtHasherConfig<Params>> {
+        self.hasher.as_ref()
+    }
+
+    /// Test that the input was made by the hasher with the same configuration as `hasher`.
+    pub fn check_hasher<Hasher>(&self, hasher: &Hasher) -> Result<(), MerkleTreeSerdeError<Hash>>
+    where 
+        Hasher: HasherDescriptor<Params = Params>,
+        Params: PartialEq,
     {
-        let leafs = mt.tree_lvls[0].clone();
-        if mt.is_empty() {
-            Self {
-                leafs,
-                root: None,
-                arity: ARITY,
-            }
-        } else {
-            Self {
-                leafs,
-                root: Some(mt.root()),
-                arity: ARITY,
-            }
-        }
+        self.hasher.as_ref().ok_or(MerkleTreeSerdeError::MissingHasher)?.check(hasher)
     }
-}
 
-#[cfg(feature = "serde")]
-#[derive(serde::Serialize)]
-/// `Mt` stands for `MerkleTree`
-pub struct MtSerdeRef<'tree, Hash, const ARITY: usize> {
-    leafs: &'tree Vec<Hash>,
-    root: Option<&'tree Hash>,
-    arity: usize,
-}
-#[cfg(feature = "serde")]
-impl<'tree, Hash, const ARITY: usize> MtSerdeRef<'tree, Hash, ARITY> {
-    pub fn from_merkle_tree<Hasher>(mt: &'tree MerkleTree<Hash, Hasher, ARITY>) -> Self
-    where Hasher: ArityHasher<Hash, ARITY>
+    /// The same as [`Self::check_hasher`], but the input without the hasher configuration is accepted.
+    fn check_recorded_hasher<Hasher>(&self, hasher: &Hasher) -> Result<(), MerkleTreeSerdeError<Hash>>
+    where 
+        Hasher: HasherDescriptor<Params = Params>,
+        Params: PartialEq,
     {
-        let leafs = &mt.tree_lvls[0];
-        if mt.is_empty() {
-            Self {
-                leafs,
-                root: None,
-                arity: ARITY,
-            }
-        } else {
-            Self {
-                leafs,
-                root: Some(mt.root_ref()),
-                arity: ARITY,
-            }
+        match &self.hasher {
+            Some(config) => config.check(hasher),
+            None => Ok(()),
         }
     }
-}
+
+    /// The same as [`Self::to_merkle_tree`], but the hasher configuration is tested first.\
+    /// It's strict: the input without the hasher configuration is rejected.
+    pub fn to_merkle_tree_checked<Hasher>(self, hasher: Hasher) -> Result<MerkleTree<Hash, Hasher, ARITY>, MerkleTreeSerdeError<Hash>>
+    where
+        Hash: Eq + Clone,
+        Hasher: ArityHasher<Hash, ARITY> + HasherDescriptor<Params = Params>,
+        Params: PartialEq,
+    {
+        self.check_hasher(&hasher)?;
+        self.to_merkle_tree_unchecked(hasher)
+    }
+
+    /// The same as [`Self::to_merkle_tree`], but the hasher is restored from the recorded configuration.
+    pub fn to_merkle_tree_restored<Hasher>(mut self) -> Result<MerkleTree<Hash, Hasher, ARITY>, MerkleTreeSerdeError<Hash>>
+    where
+        Hash: Eq + Clone,
+        Hasher: ArityHasher<Hash, ARITY> + HasherDescriptor<Params = Params>
+    {
+        let config = self.hasher.take().ok_or(MerkleTreeSerdeError::MissingHasher)?;
+        let hasher = config.restore()?;
+        self.to_merkle_tree_unchecked(hasher)
+    }
+}
+#[cfg(feature = "serde")]
+impl<Hash, const ARITY: usize> MtSerde<Hash, ARITY> {
+    pub fn from_merkle_tree<Hasher, Observer>(mt: &MerkleTree<Hash, Hasher, ARITY, Observer>) -> Self
+    where
+        Hash: Clone,
+        Hasher: ArityHasher<Hash, ARITY>
+    {
+        Self {
+            leafs: mt.tree_lvls[0].clone(),
+            root: (!mt.is_empty()).then(|| mt.root()),
+            arity: ARITY,
+            lvls: None,
+            hasher: None,
+        }
+    }
+
+    /// Serializable form with all the levels, so loading can skip rehashing 
+    /// (see [`Self::to_merkle_tree_lazy`]).
+    pub fn from_merkle_tree_full<Hasher, Observer>(mt: &MerkleTree<Hash, Hasher, ARITY, Observer>) -> Self
+    where
+        Hash: Clone,
+        Hasher: ArityHasher<Hash, ARITY>
+    {
+        Self {
+            lvls: Some(mt.tree_lvls[1..].to_vec()),
+            ..Self::from_merkle_tree(mt)
+        }
+    }
+
+    /// Record configuration of the `hasher` that made the tree.
+    pub fn with_hasher<Hasher: HasherDescriptor>(self, hasher: &Hasher) -> MtSerde<Hash, ARITY, Hasher::Params> {
+        MtSerde {
+            leafs: self.leafs,
+            root: self.root,
+            arity: self.arity,
+            lvls: self.lvls,
+            hasher: Some(MtHasherConfig::from_hasher(hasher)),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, Hash, const ARITY: usize, Params> serde::Deserialize<'de> for MtSerde<Hash, ARITY, Params>
+where 
+    Hash: serde::Deserialize<'de>,
+    Params: serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: serde::Deserializer<'de>
+    {
+        serde::de::DeserializeSeed::deserialize(MtSerdeSeed::new(MtSerdeLimits::default()), deserializer)
+    }
+}
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Deserialize [`MtSerde`] with the given [`MtSerdeLimits`]:
+/// ```ignore
+/// let mut de = serde_json::Deserializer::from_str(&json);
+/// let tree: MtSerde<_, 2> = MtSerdeSeed::new(limits).deserialize(&mut de)?;
+/// ```
+#[cfg(feature = "serde")]
+pub struct MtSerdeSeed<Hash, const ARITY: usize, Params = ()> {
+    limits: MtSerdeLimits,
+    _hash: std::marker::PhantomData<fn() -> (Hash, Params)>,
+}
+#[cfg(feature = "serde")]
+impl<Hash, const ARITY: usize, Params> MtSerdeSeed<Hash, ARITY, Params> {
+    pub fn new(limits: MtSerdeLimits) -> Self {
+        Self { limits, _hash: std::marker::PhantomData }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_seed {
+    use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+    use super::{MerkleTreeSerdeError, MtSerde, MtSerdeSeed};
+
+    const FIELDS: &[&str] = &["leafs", "root", "arity", "lvls", "hasher"];
+    const LVLS_MAX: usize = usize::BITS as usize;
+
+    fn limit_exceeded<E: de::Error>(what: &'static str, max: usize) -> E {
+        E::custom(MerkleTreeSerdeError::<()>::LimitExceeded { what, max })
+    }
+
+    fn check_arity<E: de::Error, const ARITY: usize>(arity: usize, early: bool) -> Result<usize, E> {
+        if early && arity != ARITY {
+            return Err(E::custom(MerkleTreeSerdeError::<()>::InvalidArity(ARITY, arity)))
+        }
+        Ok(arity)
+    }
+
+    enum Field { Leafs, Root, Arity, Lvls, Hasher, Ignore }
+
+    impl<'de> de::Deserialize<'de> for Field {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct FieldVisitor;
+            impl Visitor<'_> for FieldVisitor {
+                type Value = Field;
+                fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    f.write_str("field identifier")
+                }
+                fn visit_u64<E: de::Error>(self, v: u64) -> Result<Field, E> {
+                    Ok(match v {
+                        0 => Field::Leafs,
+                        1 => Field::Root,
+                        2 => Field::Arity,
+                        3 => Field::Lvls,
+                        4 => Field::Hasher,
+                        _ => Field::Ignore,
+                    })
+                }
+                fn visit_str<E: de::Error>(self, v: &str) -> Result<Field, E> {
+                    self.visit_bytes(v.as_bytes())
+                }
+                fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Field, E> {
+                    Ok(match v {
+                        b"arity" => Field::Arity,
+                        b"hasher" => Field::Hasher,
+                        b"leafs" => Field::Leafs,
+                        b"root" => Field::Root,
+                        b"lvls" => Field::Lvls,
+                        _ => Field::Ignore,
+                    })
+                }
+            }
+            deserializer.deserialize_identifier(FieldVisitor)
+        }
+    }
+
+    /// Sequence of hashes with at most `max` elements
+    struct LimitedVec<Hash> {
+        max: usize,
+        what: &'static str,
+        _hash: std::marker::PhantomData<fn() -> Hash>,
+    }
+    impl<Hash> LimitedVec<Hash> {
+        fn new(max: usize, what: &'static str) -> Self {
+            Self { max, what, _hash: std::marker::PhantomData }
+        }
+    }
+    impl<'de, Hash: de::Deserialize<'de>> DeserializeSeed<'de> for LimitedVec<Hash> {
+        type Value = Vec<Hash>;
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_seq(self)
+        }
+    }
+    impl<'de, Hash: de::Deserialize<'de>> Visitor<'de> for LimitedVec<Hash> {
+        type Value = Vec<Hash>;
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a sequence of at most {} {}", self.max, self.what)
+        }
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let hint = seq.size_hint().unwrap_or(0);
+            if hint > self.max {
+                return Err(limit_exceeded(self.what, self.max))
+            }
+            let mut vec = Vec::with_capacity(hint.min(4096));
+            while let Some(hash) = seq.next_element()? {
+                if vec.len() == self.max {
+                    return Err(limit_exceeded(self.what, self.max))
+                }
+                vec.push(hash);
+            }
+            Ok(vec)
+        }
+    }
+
+    /// Optional levels with at most `budget` nodes at all
+    struct LimitedLvls<Hash> {
+        budget: usize,
+        _hash: std::marker::PhantomData<fn() -> Hash>,
+    }
+    impl<'de, Hash: de::Deserialize<'de>> DeserializeSeed<'de> for LimitedLvls<Hash> {
+        type Value = Option<Vec<Vec<Hash>>>;
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_option(self)
+        }
+    }
+    impl<'de, Hash: de::Deserialize<'de>> Visitor<'de> for LimitedLvls<Hash> {
+        type Value = Option<Vec<Vec<Hash>>>;
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("optional sequence of levels")
+        }
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+        fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_seq(self)
+        }
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut budget = self.budget;
+            let mut lvls = vec![];
+            while let Some(lvl) = seq.next_element_seed(LimitedVec::<Hash>::new(budget, "nodes in the levels"))? {
+                if lvls.len() == LVLS_MAX {
+                    return Err(limit_exceeded("levels", LVLS_MAX))
+                }
+                budget -= lvl.len();
+                lvls.push(lvl);
+            }
+            Ok(Some(lvls))
+        }
+    }
+
+    impl<Hash, const ARITY: usize, Params> MtSerdeSeed<Hash, ARITY, Params> {
+        fn leafs_seed(&self) -> LimitedVec<Hash> {
+            LimitedVec::new(self.limits.max_leafs, "leafs")
+        }
+        fn lvls_seed(&self) -> LimitedLvls<Hash> {
+            LimitedLvls { 
+                budget: self.limits.max_leafs.saturating_mul(2), 
+                _hash: std::marker::PhantomData,
+            }
+        }
+    }
+
+    impl<'de, Hash, const ARITY: usize, Params> DeserializeSeed<'de> for MtSerdeSeed<Hash, ARITY, Params>
+    where 
+        Hash: de::Deserialize<'de>,
+        Params: de::Deserialize<'de>,
+    {
+        type Value = MtSerde<Hash, ARITY, Params>;
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_struct("MtSerde", FIELDS, self)
+        }
+    }
+
+    impl<'de, Hash, const ARITY: usize, Params> Visitor<'de> for MtSerdeSeed<Hash, ARITY, Params>
+    where 
+        Hash: de::Deserialize<'de>,
+        Params: de::Deserialize<'de>,
+    {
+        type Value = MtSerde<Hash, ARITY, Params>;
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("struct MtSerde")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let early = self.limits.early_arity_check;
+            let leafs = seq.next_element_seed(self.leafs_seed())?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            let root = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            let arity = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+            let arity = check_arity::<_, ARITY>(arity, early)?;
+            // `lvls` & `hasher` are optional, so the sequences of 3 fields are accepted
+            let lvls = seq.next_element_seed(self.lvls_seed())?.flatten();
+            let hasher = seq.next_element()?.flatten();
+            Ok(MtSerde { leafs, root, arity, lvls, hasher })
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let early = self.limits.early_arity_check;
+            let mut leafs = None;
+            let mut root = None;
+            let mut arity = None;
+            let mut lvls = None;
+            let mut hasher = None;
+            while let Some(field) = map.next_key()? {
+                match field {
+                    Field::Leafs => {
+                        if leafs.is_some() { return Err(de::Error::duplicate_field("leafs")) }
+                        leafs = Some(map.next_value_seed(self.leafs_seed())?);
+                    }
+                    Field::Root => {
+                        if root.is_some() { return Err(de::Error::duplicate_field("root")) }
+                        root = Some(map.next_value()?);
+                    }
+                    Field::Arity => {
+                        if arity.is_some() { return Err(de::Error::duplicate_field("arity")) }
+                        arity = Some(check_arity::<_, ARITY>(map.next_value()?, early)?);
+                    }
+                    Field::Lvls => {
+                        if lvls.is_some() { return Err(de::Error::duplicate_field("lvls")) }
+                        lvls = Some(map.next_value_seed(self.lvls_seed())?);
+                    }
+                    Field::Hasher => {
+                        if hasher.is_some() { return Err(de::Error::duplicate_field("hasher")) }
+                        hasher = Some(map.next_value()?);
+                    }
+                    Field::Ignore => {
+                        map.next_value::<de::IgnoredAny>()?;
+                    }
+                }
+            }
+            Ok(MtSerde {
+                leafs: leafs.ok_or_else(|| de::Error::missing_field("leafs"))?,
+                root: root.flatten(),
+                arity: arity.ok_or_else(|| de::Error::missing_field("arity"))?,
+                lvls: lvls.flatten(),
+                hasher: hasher.flatten(),
+            })
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+/// `Mt` stands for `MerkleTree`
+pub struct MtSerdeRef<'tree, Hash, const ARITY: usize, Params> {
+    leafs: &'tree Vec<Hash>,
+    root: Option<&'tree Hash>,
+    arity: usize,
+    lvls: Option<&'tree [Vec<Hash>]>,
+    hasher: Option<MtHasherConfig<Params>>,
+}
+#[cfg(feature = "serde")]
+impl<'tree, Hash, const ARITY: usize> MtSerdeRef<'tree, Hash, ARITY, ()> {
+    pub fn from_merkle_tree<Hasher, Observer>(mt: &'tree MerkleTree<Hash, Hasher, ARITY, Observer>) -> Self
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        Self {
+            leafs: &mt.tree_lvls[0],
+            root: (!mt.is_empty()).then(|| mt.root_ref()),
+            arity: ARITY,
+            lvls: None,
+            hasher: None,
+        }
+    }
+
+    /// See [`MtSerde::with_hasher`]
+    pub fn with_hasher<Hasher: HasherDescriptor>(self, hasher: &Hasher) -> MtSerdeRef<'tree, Hash, ARITY, Hasher::Params> {
+        MtSerdeRef {
+            leafs: self.leafs,
+            root: self.root,
+            arity: self.arity,
+            lvls: self.lvls,
+            hasher: Some(MtHasherConfig::from_hasher(hasher)),
+        }
+    }
+}
 
 // [-] MerkleTree Serde
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
@@ -1118,28 +1910,176 @@ impl<'tree, Hash, const ARITY: usize> MtSerdeRef<'tree, Hash, ARITY> {
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
 // [+] MerkleTree Proof
 
+/// Reason why a proof was rejected.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ProofError {
+    #[error("Wrong node on level {0}. It's not the node that was proven (or calculated from the previous level).")]
+    WrongNode(usize),
+    #[error("Calculated root is not equal to the root of the proof.")]
+    RootMismatch,
+    #[error("Path index {index} on level {lvl} is out of group. Group has only {len} nodes.")]
+    PathIndexOutOfRange { lvl: usize, index: usize, len: usize },
+    #[error("Wrong amount of nodes. Expected {expected}, but it was {actual}.")]
+    WrongNodeCount { expected: usize, actual: usize },
+    #[error("Proof is truncated. Path has {path_lvls} levels, but there are nodes only for {node_lvls} levels.")]
+    Truncated { path_lvls: usize, node_lvls: usize },
+    #[error("Leaf {index} is out of the tree with {tree_size} leafs.")]
+    LeafOutOfTree { index: usize, tree_size: usize },
+    #[error("Proof was made for the tree with {actual} leafs, but expected tree size is {expected}.")]
+    TreeSizeMismatch { expected: usize, actual: usize },
+    #[error("Wrong amount of levels. The tree with claimed size has {expected} levels in proof, but it was {actual}.")]
+    WrongLvlCount { expected: usize, actual: usize },
+    #[error("Path leads to leaf {actual:?}, but expected leaf is {expected}.")]
+    LeafIndexMismatch { expected: usize, actual: Option<usize> },
+    #[error("Group on level {lvl} has {actual} nodes, but the tree with claimed size has {expected} nodes there.")]
+    WrongGroupLen { lvl: usize, expected: usize, actual: usize },
+    #[error("Range of proven leafs is empty.")]
+    EmptyRange,
+    #[error("Proof is made for leafs {actual:?}, but expected leafs are {expected:?}.")]
+    RangeMismatch { expected: Range<usize>, actual: Range<usize> },
+    #[error("Proof is made for node {actual:?}, but expected node is {expected:?}.")]
+    NodeMismatch { expected: NodeId, actual: NodeId },
+    #[error("Node {index} on level {lvl} is out of the tree. The tree with claimed size has {lvl_len} nodes on the level.")]
+    NodeOutOfTree { lvl: usize, index: usize, lvl_len: usize },
+    #[error("Subtree with height {height} can't be a subtree of node on level {lvl}.")]
+    SubtreeTooHigh { lvl: usize, height: usize },
+    #[error("Proof doesn't contain tree size.")]
+    UnknownTreeSize,
+    #[error("Change record doesn't contain node {index} on level {lvl} that is needed to update the proof.")]
+    ChangeMissingNode { lvl: usize, index: usize },
+    #[error("Consistency proof is made from the tree with {actual} leafs, but expected old tree size is {expected}.")]
+    OldSizeMismatch { expected: usize, actual: usize },
+    #[error("Old tree of consistency proof must be non-empty.")]
+    EmptyOldTree,
+    #[error("Calculated old root is not equal to the trusted old root.")]
+    OldRootMismatch,
+}
+
 /// `Mt` stands for `MerkleTree`
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MtProof<Hash, const ARITY: usize> {
     /// SHOULD have len: `ARITY * LVLs` 
     tree_lvl_nodes: Vec<Hash>,
     tree_lvl_path: Vec<usize>,
     root: Hash,
+    /// [`None`] for proofs that were made without tree size 
+    tree_size: Option<usize>,
 }
-impl<Hash: Eq, const ARITY: usize> MtProof<Hash, ARITY> {
-    pub fn verify<Hasher>(&self, mut hash: Hash, hasher: &mut Hasher) -> bool
-    where Hasher: ArityHasher<Hash, ARITY>
+impl<Hash, const ARITY: usize> MtProof<Hash, ARITY> {
+    fn check_well_formed(&self) -> Result<(), ProofError> {
+        check_aligned_proof::<ARITY>(self.tree_lvl_nodes.len(), &self.tree_lvl_path)
+    }
+
+    /// Drop embedded root. See [`MtDetachedProof`].
+    pub fn detach(self) -> MtDetachedProof<Hash, ARITY> {
+        MtDetachedProof {
+            tree_lvl_nodes: self.tree_lvl_nodes,
+            tree_lvl_path: self.tree_lvl_path,
+            tree_size: self.tree_size,
+        }
+    }
+
+    /// Index of proven leaf, calculated from the path.
+    /// 
+    /// # Return
+    /// * [`None`] if path is malformed (index in group is out of `ARITY` or index doesn't fit into `usize`)
+    /// * [`Some`] of leaf index otherwise
+    pub fn leaf_index(&self) -> Option<LeafId> {
+        pack_digits::<ARITY>(&self.tree_lvl_path).map(LeafId::new)
+    }
+
+    /// Amount of leafs in the tree from which the proof was made.
+    /// 
+    /// Tree size is not committed by the root, 
+    /// so it can be trusted only after [`Self::verify_position`] with trusted tree size.
+    /// 
+    /// [`None`] if the proof was made without tree size.
+    pub fn tree_size(&self) -> Option<usize> {
+        self.tree_size
+    }
+
+    /// Make proof from parts received from elsewhere.
+    /// 
+    /// # Input
+    /// * `lvls`: group of nodes for each level (from leafs to root).
+    ///   Group contains proven node & its siblings. 
+    ///   Group that has less than `ARITY` nodes is padded by its last node.
+    /// * `path`: index of proven node in the group for each level
+    /// * `root`: root of the tree
+    /// 
+    /// # Return
+    /// * [`Err`] if amount of groups isn't equal to amount of path indexes, 
+    ///   if some group is empty or has more than `ARITY` nodes, 
+    ///   or if some path index is out of its group.
+    /// * [`Ok`] of proof without tree size otherwise
+    pub fn from_parts(lvls: Vec<Vec<Hash>>, path: Vec<usize>, root: Hash) -> Result<Self, ProofError>
+    where Hash: Clone
     {
-        for (cur_lvl, path_index) in self.tree_lvl_path.iter().copied().enumerate() {
-            let is_valid = self.tree_lvl_nodes[cur_lvl * ARITY + path_index] == hash;
-            if !is_valid { return false }
+        if lvls.len() < path.len() {
+            return Err(ProofError::Truncated { path_lvls: path.len(), node_lvls: lvls.len() })
+        }
+        if lvls.len() != path.len() {
+            return Err(ProofError::WrongNodeCount { expected: path.len(), actual: lvls.len() })
+        }
 
-            for index in 0..ARITY {
-                hasher.hash_arity_one_ref(&self.tree_lvl_nodes[cur_lvl * ARITY + index]);
+        let mut tree_lvl_nodes = Vec::with_capacity(lvls.len() * ARITY);
+        for (lvl, (mut group, index)) in lvls.into_iter().zip(path.iter().copied()).enumerate() {
+            let Some(last) = group.last().cloned() else {
+                return Err(ProofError::WrongNodeCount { expected: ARITY, actual: 0 })
+            };
+            if group.len() > ARITY {
+                return Err(ProofError::WrongNodeCount { expected: ARITY, actual: group.len() })
             }
-            hash = hasher.finish_arity();
+            if index >= group.len() {
+                return Err(ProofError::PathIndexOutOfRange { lvl, index, len: group.len() })
+            }
+
+            group.resize(ARITY, last);
+            tree_lvl_nodes.extend(group);
         }
-        hash == self.root
+
+        Ok(Self {
+            tree_lvl_nodes,
+            tree_lvl_path: path,
+            root,
+            tree_size: None,
+        })
+    }
+
+    /// Root embedded into the proof.
+    pub fn root(&self) -> &Hash {
+        &self.root
+    }
+
+    /// Index of proven node in its group for each level (from leafs to root).
+    pub fn path(&self) -> &[usize] {
+        &self.tree_lvl_path
+    }
+
+    /// Amount of levels in the proof (it's `height - 1` of the tree).
+    pub fn lvl_count(&self) -> usize {
+        self.tree_lvl_path.len()
+    }
+
+    /// Group of `ARITY` nodes (proven node, its siblings & padding) on level `lvl`. 
+    pub fn lvl_nodes(&self, lvl: usize) -> Option<&[Hash]> {
+        self.tree_lvl_nodes.chunks(ARITY).nth(lvl)
+    }
+
+    /// Iterator over `(lvl, position, group)` (from leafs to root),
+    /// where `group` is proven node with its siblings & `position` is index of proven node in the group.
+    pub fn lvls(&self) -> impl Iterator<Item = (usize, usize, &[Hash])> {
+        let groups = self.tree_lvl_nodes.chunks(ARITY);
+        groups.zip(self.tree_lvl_path.iter().copied()).enumerate().map(|(lvl, (group, index))|(lvl, index, group))
+    }
+}
+impl<Hash: Eq, const ARITY: usize> MtProof<Hash, ARITY> {
+    /// The same as [`Self::verify_detailed`] but without reason of rejection.
+    pub fn verify<Hasher>(&self, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_detailed(hash, hasher).is_ok()
     }
 
     pub fn verify_data<Data, Hasher>(&self, data: Data, hasher: &mut Hasher) -> bool
@@ -1148,77 +2088,2086 @@ impl<Hash: Eq, const ARITY: usize> MtProof<Hash, ARITY> {
         let hash = hasher.hash_data(data);
         self.verify(hash, hasher)
     }
-}
 
-/// `Mt` stands for `MerkleTree`
-pub struct MtProofRef<'tree, Hash, const ARITY: usize> {
-    tree_lvl_nodes: Vec<&'tree [Hash]>,
-    tree_lvl_path: Vec<usize>,
-    root: &'tree Hash,
-}
-impl<'tree, Hash, const ARITY: usize> Clone for MtProofRef<'tree, Hash, ARITY> {
-    fn clone(&self) -> Self {
-        Self { 
-            tree_lvl_nodes: self.tree_lvl_nodes.clone(), 
-            tree_lvl_path: self.tree_lvl_path.clone(), 
-            root: self.root,
-        }
-    }
-}
-impl<'tree, Hash: Eq, const ARITY: usize> MtProofRef<'tree, Hash, ARITY> {
-    pub fn verify<Hasher>(&self, mut hash: Hash, hasher: &mut Hasher) -> bool
+    /// Verify the proof against its own embedded root & tell why it was rejected.
+    /// 
+    /// ⚠️ The embedded root is chosen by the proof creator. 
+    /// A forged proof with a consistent self-chosen root passes this check,
+    /// so the root must be compared with a trusted one separately. 
+    /// Better use [`Self::verify_with_root_detailed`] or [`MtDetachedProof`].
+    /// It's the same for all proofs with embedded root.
+    /// 
+    /// Never panics, so it can be used on untrusted (for example deserialized) proofs.
+    pub fn verify_detailed<Hasher>(&self, hash: Hash, hasher: &mut Hasher) -> Result<(), ProofError>
     where Hasher: ArityHasher<Hash, ARITY>
     {
-        for (cur_lvl, path_index) in self.tree_lvl_path.iter().copied().enumerate() {
-            let is_valid = self.tree_lvl_nodes[cur_lvl][path_index] == hash;
-            if !is_valid { return false }
-
-            let nodes_amount = self.tree_lvl_nodes[cur_lvl].len();
-            for hash in self.tree_lvl_nodes[cur_lvl] {
-                hasher.hash_arity_one_ref(hash);
-            }
+        self.verify_with_root_detailed(&self.root, hash, hasher)
+    }
 
-            // if we have unaligned amount of nodes on current lvl 
-            // => we need to hash last node few times more 
-            for _ in 0..(ARITY - nodes_amount) {
-                hasher.hash_arity_one_ref(&self.tree_lvl_nodes[cur_lvl][nodes_amount - 1]);
-            }
+    pub fn verify_data_detailed<Data, Hasher>(&self, data: Data, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_detailed(hash, hasher)
+    }
 
-            hash = hasher.finish_arity();
-        }
-        &hash == self.root
+    /// Verify the proof against externally trusted root (the embedded root is ignored).
+    pub fn verify_with_root<Hasher>(&self, trusted_root: &Hash, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_with_root_detailed(trusted_root, hash, hasher).is_ok()
     }
 
-    pub fn verify_data<Data, Hasher>(&self, data: Data, hasher: &mut Hasher) -> bool
+    pub fn verify_data_with_root<Data, Hasher>(&self, trusted_root: &Hash, data: Data, hasher: &mut Hasher) -> bool
     where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
     {
         let hash = hasher.hash_data(data);
-        self.verify(hash, hasher)
+        self.verify_with_root(trusted_root, hash, hasher)
     }
-}
-impl<'tree, Hash: Clone, const ARITY: usize> MtProofRef<'tree, Hash, ARITY> {
-    pub fn to_owned(self) -> MtProof<Hash, ARITY> {
-        let mut tree_lvl_nodes = Vec::with_capacity(self.tree_lvl_nodes.len() * ARITY);
-        for lvl_nodes in self.tree_lvl_nodes {
-            let len = lvl_nodes.len();
 
-            for index in 0..len {
-                tree_lvl_nodes.push(lvl_nodes[index].clone());
-            }
+    /// Verify the proof against externally trusted root & tell why it was rejected.
+    /// 
+    /// Never panics, so it can be used on untrusted (for example deserialized) proofs.
+    pub fn verify_with_root_detailed<Hasher>(&self, trusted_root: &Hash, hash: Hash, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        let root = calc_aligned_root::<_, _, ARITY>(&self.tree_lvl_nodes, &self.tree_lvl_path, hash, hasher)?;
+        if &root == trusted_root { Ok(()) } else { Err(ProofError::RootMismatch) }
+    }
 
-            // align it if unaligned:
-            for _ in len..ARITY {
-                tree_lvl_nodes.push(lvl_nodes[len - 1].clone());
-            }
-        }
+    pub fn verify_data_with_root_detailed<Data, Hasher>(&self, trusted_root: &Hash, data: Data, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_with_root_detailed(trusted_root, hash, hasher)
+    }
 
-        MtProof {
-            tree_lvl_nodes,
-            tree_lvl_path: self.tree_lvl_path,
-            root: self.root.clone(),
-        }
+    /// Verify the proof against trusted root, trusted tree size & expected leaf index.
+    /// 
+    /// Besides [`Self::verify_with_root`] it tests that the path leads exactly to leaf `id`
+    /// & that groups of the path have exactly as much nodes as the tree with `tree_size` leafs has.
+    /// So a proof through a padding duplicate can't be accepted as a proof for a non-existent leaf.
+    pub fn verify_position<Hasher>(&self, trusted_root: &Hash, id: LeafId, tree_size: usize, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_position_detailed(trusted_root, id, tree_size, hash, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify_position`] but tells why the proof was rejected.
+    pub fn verify_position_detailed<Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        id: LeafId, 
+        tree_size: usize, 
+        hash: Hash, 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        check_tree_size(self.tree_size, tree_size)?;
+        check_aligned_proof::<ARITY>(self.tree_lvl_nodes.len(), &self.tree_lvl_path)?;
+        let group_lens = check_leaf_position::<ARITY>(&self.tree_lvl_path, id, tree_size)?;
+        check_aligned_padding::<_, ARITY>(&self.tree_lvl_nodes, &group_lens)?;
+        self.verify_with_root_detailed(trusted_root, hash, hasher)
     }
 }
 
-// [-] MerkleTree Proof
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Proof without embedded root.
+/// 
+/// It can be verified only against a root that the verifier trusts, 
+/// so the trusted root check can't be skipped (unlike [`MtProof::verify`]).
+/// 
+/// It's the recommended way to send proofs: 
+/// publish root by trusted channel & send proofs by [`MerkleTree::proof_detached`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtDetachedProof<Hash, const ARITY: usize> {
+    /// SHOULD have len: `ARITY * LVLs` 
+    tree_lvl_nodes: Vec<Hash>,
+    tree_lvl_path: Vec<usize>,
+    /// [`None`] for proofs that were made without tree size 
+    tree_size: Option<usize>,
+}
+impl<Hash, const ARITY: usize> MtDetachedProof<Hash, ARITY> {
+    /// Embed `root` into the proof. 
+    pub fn attach(self, root: Hash) -> MtProof<Hash, ARITY> {
+        MtProof {
+            tree_lvl_nodes: self.tree_lvl_nodes,
+            tree_lvl_path: self.tree_lvl_path,
+            root,
+            tree_size: self.tree_size,
+        }
+    }
+
+    /// Index of proven leaf, see [`MtProof::leaf_index`].
+    pub fn leaf_index(&self) -> Option<LeafId> {
+        pack_digits::<ARITY>(&self.tree_lvl_path).map(LeafId::new)
+    }
+
+    /// Amount of leafs in the tree from which the proof was made, see [`MtProof::tree_size`].
+    pub fn tree_size(&self) -> Option<usize> {
+        self.tree_size
+    }
+
+    /// See [`MtProof::path`].
+    pub fn path(&self) -> &[usize] {
+        &self.tree_lvl_path
+    }
+
+    /// See [`MtProof::lvl_nodes`].
+    pub fn lvl_nodes(&self, lvl: usize) -> Option<&[Hash]> {
+        self.tree_lvl_nodes.chunks(ARITY).nth(lvl)
+    }
+
+    /// See [`MtProof::lvls`].
+    pub fn lvls(&self) -> impl Iterator<Item = (usize, usize, &[Hash])> {
+        let groups = self.tree_lvl_nodes.chunks(ARITY);
+        groups.zip(self.tree_lvl_path.iter().copied()).enumerate().map(|(lvl, (group, index))|(lvl, index, group))
+    }
+}
+impl<Hash: Eq, const ARITY: usize> MtDetachedProof<Hash, ARITY> {
+    pub fn verify_with_root<Hasher>(&self, trusted_root: &Hash, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_with_root_detailed(trusted_root, hash, hasher).is_ok()
+    }
+
+    pub fn verify_data_with_root<Data, Hasher>(&self, trusted_root: &Hash, data: Data, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_with_root(trusted_root, hash, hasher)
+    }
+
+    /// Verify the proof against trusted root & tell why it was rejected.
+    /// 
+    /// Never panics, so it can be used on untrusted (for example deserialized) proofs.
+    pub fn verify_with_root_detailed<Hasher>(&self, trusted_root: &Hash, hash: Hash, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        let root = calc_aligned_root::<_, _, ARITY>(&self.tree_lvl_nodes, &self.tree_lvl_path, hash, hasher)?;
+        if &root == trusted_root { Ok(()) } else { Err(ProofError::RootMismatch) }
+    }
+
+    pub fn verify_data_with_root_detailed<Data, Hasher>(&self, trusted_root: &Hash, data: Data, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_with_root_detailed(trusted_root, hash, hasher)
+    }
+
+    /// Verify the proof against trusted root, trusted tree size & expected leaf index,
+    /// see [`MtProof::verify_position`].
+    pub fn verify_position<Hasher>(&self, trusted_root: &Hash, id: LeafId, tree_size: usize, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_position_detailed(trusted_root, id, tree_size, hash, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify_position`] but tells why the proof was rejected.
+    pub fn verify_position_detailed<Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        id: LeafId, 
+        tree_size: usize, 
+        hash: Hash, 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        check_tree_size(self.tree_size, tree_size)?;
+        check_aligned_proof::<ARITY>(self.tree_lvl_nodes.len(), &self.tree_lvl_path)?;
+        let group_lens = check_leaf_position::<ARITY>(&self.tree_lvl_path, id, tree_size)?;
+        check_aligned_padding::<_, ARITY>(&self.tree_lvl_nodes, &group_lens)?;
+        self.verify_with_root_detailed(trusted_root, hash, hasher)
+    }
+}
+
+/// Test that amount of nodes & path of aligned proof (each level has `ARITY` nodes) 
+/// are agree with each other & `ARITY`.
+fn check_aligned_proof<const ARITY: usize>(nodes_len: usize, path: &[usize]) -> Result<(), ProofError> {
+    let path_lvls = path.len();
+    let expected = path_lvls.saturating_mul(ARITY);
+    let actual = nodes_len;
+
+    if actual.is_multiple_of(ARITY) && actual < expected {
+        return Err(ProofError::Truncated { path_lvls, node_lvls: actual / ARITY })
+    }
+    if actual != expected {
+        return Err(ProofError::WrongNodeCount { expected, actual })
+    }
+    for (lvl, index) in path.iter().copied().enumerate() {
+        if index >= ARITY {
+            return Err(ProofError::PathIndexOutOfRange { lvl, index, len: ARITY })
+        }
+    }
+    Ok(())
+}
+
+/// Calculate root of aligned proof (each level has `ARITY` nodes).
+fn calc_aligned_root<Hash: Eq, Hasher, const ARITY: usize>(
+    nodes: &[Hash], 
+    path: &[usize], 
+    mut hash: Hash, 
+    hasher: &mut Hasher
+) -> Result<Hash, ProofError>
+where Hasher: ArityHasher<Hash, ARITY>
+{
+    check_aligned_proof::<ARITY>(nodes.len(), path)?;
+
+    for (cur_lvl, (group, path_index)) in nodes.chunks(ARITY).zip(path.iter().copied()).enumerate() {
+        if group[path_index] != hash {
+            return Err(ProofError::WrongNode(cur_lvl))
+        }
+
+        for hash in group {
+            hasher.hash_arity_one_ref(hash);
+        }
+        hash = hasher.finish_arity();
+    }
+    Ok(hash)
+}
+
+/// Test that claimed tree size is agree with tree size of the proof (if the proof has it).
+fn check_tree_size(proof_tree_size: Option<usize>, tree_size: usize) -> Result<(), ProofError> {
+    match proof_tree_size {
+        Some(actual) if actual != tree_size => Err(ProofError::TreeSizeMismatch { expected: tree_size, actual }),
+        _ => Ok(()),
+    }
+}
+
+/// Test that `path` leads to leaf `id` in the tree with `tree_size` leafs.
+/// 
+/// # Return
+/// Amount of real (non-padding) nodes in each group of the path.
+fn check_leaf_position<const ARITY: usize>(path: &[usize], id: LeafId, tree_size: usize) -> Result<Vec<usize>, ProofError> {
+    if id.0 >= tree_size {
+        return Err(ProofError::LeafOutOfTree { index: id.0, tree_size })
+    }
+
+    let expected = length_in_base(tree_size - 1, ARITY) as usize;
+    if path.len() != expected {
+        return Err(ProofError::WrongLvlCount { expected, actual: path.len() })
+    }
+
+    let mut group_lens = Vec::with_capacity(path.len());
+    let mut index = id.0;
+    let mut lvl_len = tree_size;
+    for path_index in path.iter().copied() {
+        if path_index != index % ARITY {
+            let actual = pack_digits::<ARITY>(path);
+            return Err(ProofError::LeafIndexMismatch { expected: id.0, actual })
+        }
+
+        let group_start = index - index % ARITY;
+        group_lens.push(ARITY.min(lvl_len - group_start));
+
+        index /= ARITY;
+        lvl_len = lvl_len / ARITY + (!lvl_len.is_multiple_of(ARITY)) as usize;
+    }
+    Ok(group_lens)
+}
+
+/// Test that padding of aligned proof (each level has `ARITY` nodes) 
+/// is agree with real (non-padding) amount of nodes in each group.
+fn check_aligned_padding<Hash: Eq, const ARITY: usize>(nodes: &[Hash], group_lens: &[usize]) -> Result<(), ProofError> {
+    for (lvl, (group, len)) in nodes.chunks(ARITY).zip(group_lens.iter().copied()).enumerate() {
+        let last = &group[len - 1];
+        if let Some(pos) = group[len..].iter().position(|hash|hash != last) {
+            return Err(ProofError::WrongGroupLen { lvl, expected: len, actual: len + pos + 1 })
+        }
+    }
+    Ok(())
+}
+
+/// `Mt` stands for `MerkleTree`
+pub struct MtProofRef<'tree, Hash, const ARITY: usize> {
+    tree_lvl_nodes: Vec<&'tree [Hash]>,
+    tree_lvl_path: Vec<usize>,
+    root: &'tree Hash,
+    tree_size: usize,
+}
+impl<'tree, Hash, const ARITY: usize> Clone for MtProofRef<'tree, Hash, ARITY> {
+    fn clone(&self) -> Self {
+        Self { 
+            tree_lvl_nodes: self.tree_lvl_nodes.clone(), 
+            tree_lvl_path: self.tree_lvl_path.clone(), 
+            root: self.root,
+            tree_size: self.tree_size,
+        }
+    }
+}
+impl<'tree, Hash, const ARITY: usize> MtProofRef<'tree, Hash, ARITY> {
+    /// Index of proven leaf.
+    pub fn leaf_index(&self) -> LeafId {
+        LeafId::new(pack_digits::<ARITY>(&self.tree_lvl_path).expect("path of tree proof always fits into `usize`"))
+    }
+
+    /// Amount of leafs in the tree from which the proof was made.
+    pub fn tree_size(&self) -> usize {
+        self.tree_size
+    }
+
+    /// Root of the tree.
+    pub fn root(&self) -> &'tree Hash {
+        self.root
+    }
+
+    /// Index of proven node in its group for each level (from leafs to root).
+    pub fn path(&self) -> &[usize] {
+        &self.tree_lvl_path
+    }
+
+    /// Amount of levels in the proof (it's `height - 1` of the tree).
+    pub fn lvl_count(&self) -> usize {
+        self.tree_lvl_path.len()
+    }
+
+    /// Group of nodes (proven node & its siblings, without padding) on level `lvl`. 
+    pub fn lvl_nodes(&self, lvl: usize) -> Option<&'tree [Hash]> {
+        self.tree_lvl_nodes.get(lvl).copied()
+    }
+
+    /// Iterator over `(lvl, position, group)` (from leafs to root),
+    /// where `group` is proven node with its siblings (without padding) 
+    /// & `position` is index of proven node in the group.
+    pub fn lvls(&self) -> impl Iterator<Item = (usize, usize, &'tree [Hash])> + '_ {
+        let groups = self.tree_lvl_nodes.iter().copied();
+        groups.zip(self.tree_lvl_path.iter().copied()).enumerate().map(|(lvl, (group, index))|(lvl, index, group))
+    }
+
+    /// Test that groups of nodes & path are agree with each other & `ARITY`.
+    fn check_well_formed(&self) -> Result<(), ProofError> {
+        let path_lvls = self.tree_lvl_path.len();
+        let node_lvls = self.tree_lvl_nodes.len();
+
+        if node_lvls < path_lvls {
+            return Err(ProofError::Truncated { path_lvls, node_lvls })
+        }
+        if node_lvls != path_lvls {
+            return Err(ProofError::WrongNodeCount { expected: path_lvls, actual: node_lvls })
+        }
+        for (lvl, (group, index)) in self.tree_lvl_nodes.iter().zip(self.tree_lvl_path.iter().copied()).enumerate() {
+            if group.is_empty() || group.len() > ARITY {
+                return Err(ProofError::WrongNodeCount { expected: ARITY, actual: group.len() })
+            }
+            if index >= group.len() {
+                return Err(ProofError::PathIndexOutOfRange { lvl, index, len: group.len() })
+            }
+        }
+        Ok(())
+    }
+}
+impl<'tree, Hash: Eq, const ARITY: usize> MtProofRef<'tree, Hash, ARITY> {
+    /// The same as [`Self::verify_detailed`] but without reason of rejection.
+    pub fn verify<Hasher>(&self, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_detailed(hash, hasher).is_ok()
+    }
+
+    pub fn verify_data<Data, Hasher>(&self, data: Data, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify(hash, hasher)
+    }
+
+    /// Verify the proof against its own embedded root & tell why it was rejected.
+    /// 
+    /// ⚠️ The embedded root is chosen by the proof creator, see [`MtProof::verify_detailed`].
+    /// 
+    /// Never panics.
+    pub fn verify_detailed<Hasher>(&self, hash: Hash, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_with_root_detailed(self.root, hash, hasher)
+    }
+
+    pub fn verify_data_detailed<Data, Hasher>(&self, data: Data, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_detailed(hash, hasher)
+    }
+
+    /// Verify the proof against externally trusted root (the embedded root is ignored).
+    pub fn verify_with_root<Hasher>(&self, trusted_root: &Hash, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_with_root_detailed(trusted_root, hash, hasher).is_ok()
+    }
+
+    pub fn verify_data_with_root<Data, Hasher>(&self, trusted_root: &Hash, data: Data, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_with_root(trusted_root, hash, hasher)
+    }
+
+    pub fn verify_data_with_root_detailed<Data, Hasher>(&self, trusted_root: &Hash, data: Data, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_with_root_detailed(trusted_root, hash, hasher)
+    }
+
+    /// Verify the proof against externally trusted root & tell why it was rejected.
+    /// 
+    /// Never panics.
+    pub fn verify_with_root_detailed<Hasher>(&self, trusted_root: &Hash, mut hash: Hash, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.check_well_formed()?;
+
+        for (cur_lvl, path_index) in self.tree_lvl_path.iter().copied().enumerate() {
+            let group = self.tree_lvl_nodes[cur_lvl];
+            if group[path_index] != hash {
+                return Err(ProofError::WrongNode(cur_lvl))
+            }
+
+            for hash in group {
+                hasher.hash_arity_one_ref(hash);
+            }
+
+            // if we have unaligned amount of nodes on current lvl 
+            // => we need to hash last node few times more 
+            for _ in group.len()..ARITY {
+                hasher.hash_arity_one_ref(&group[group.len() - 1]);
+            }
+
+            hash = hasher.finish_arity();
+        }
+
+        if &hash == trusted_root { Ok(()) } else { Err(ProofError::RootMismatch) }
+    }
+
+    /// Verify the proof against trusted root, trusted tree size & expected leaf index,
+    /// see [`MtProof::verify_position`].
+    pub fn verify_position<Hasher>(&self, trusted_root: &Hash, id: LeafId, tree_size: usize, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_position_detailed(trusted_root, id, tree_size, hash, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify_position`] but tells why the proof was rejected.
+    pub fn verify_position_detailed<Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        id: LeafId, 
+        tree_size: usize, 
+        hash: Hash, 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        check_tree_size(Some(self.tree_size), tree_size)?;
+        self.check_well_formed()?;
+        let group_lens = check_leaf_position::<ARITY>(&self.tree_lvl_path, id, tree_size)?;
+        for (lvl, (group, expected)) in self.tree_lvl_nodes.iter().zip(group_lens).enumerate() {
+            if group.len() != expected {
+                return Err(ProofError::WrongGroupLen { lvl, expected, actual: group.len() })
+            }
+        }
+        self.verify_with_root_detailed(trusted_root, hash, hasher)
+    }
+}
+impl<'tree, Hash: Clone, const ARITY: usize> MtProofRef<'tree, Hash, ARITY> {
+    pub fn to_owned(self) -> MtProof<Hash, ARITY> {
+        let mut tree_lvl_nodes = Vec::with_capacity(self.tree_lvl_nodes.len() * ARITY);
+        for lvl_nodes in self.tree_lvl_nodes {
+            let len = lvl_nodes.len();
+
+            for index in 0..len {
+                tree_lvl_nodes.push(lvl_nodes[index].clone());
+            }
+
+            // align it if unaligned:
+            for _ in len..ARITY {
+                tree_lvl_nodes.push(lvl_nodes[len - 1].clone());
+            }
+        }
+
+        MtProof {
+            tree_lvl_nodes,
+            tree_lvl_path: self.tree_lvl_path,
+            root: self.root.clone(),
+            tree_size: Some(self.tree_size),
+        }
+    }
+
+    pub fn to_compact(&self) -> MtCompactProof<Hash, ARITY> {
+        let mut siblings = Vec::with_capacity(self.tree_lvl_nodes.len() * (ARITY - 1));
+        let mut paddings = Vec::with_capacity(self.tree_lvl_nodes.len());
+        for (lvl_nodes, path_index) in self.tree_lvl_nodes.iter().zip(self.tree_lvl_path.iter().copied()) {
+            for (index, hash) in lvl_nodes.iter().enumerate() {
+                if index != path_index {
+                    siblings.push(hash.clone());
+                }
+            }
+            paddings.push(ARITY - lvl_nodes.len());
+        }
+
+        // path is index of leaf & padding is less than `(ARITY - 1) * leaf_count`,
+        // so both of them fit into `usize` for any tree that fits into memory
+        MtCompactProof {
+            siblings,
+            path: pack_digits::<ARITY>(&self.tree_lvl_path).expect("path of tree proof always fits into `usize`"),
+            padding: pack_digits::<ARITY>(&paddings).expect("padding of tree proof always fits into `usize`"),
+            lvls: self.tree_lvl_path.len(),
+            root: self.root.clone(),
+            tree_size: Some(self.tree_size),
+        }
+    }
+}
+impl<Hash: Clone, const ARITY: usize> MtProof<Hash, ARITY> {
+    /// Convert into [`MtCompactProof`] (the same as [`MtProofRef::to_compact`] of the proof).
+    /// 
+    /// Group lengths (so the padding) are calculated from the tree size, 
+    /// so equal siblings are never taken for padding.
+    /// 
+    /// # Return
+    /// * [`None`] if proof has no tree size (see [`Self::tree_size`]) or it's malformed
+    ///   (wrong amount of nodes, path index out of `ARITY` or the path doesn't fit into the tree size)
+    /// * [`Some`] of compact proof otherwise 
+    pub fn to_compact(&self) -> Option<MtCompactProof<Hash, ARITY>> {
+        let tree_size = self.tree_size?;
+        self.check_well_formed().ok()?;
+        let lvls = self.tree_lvl_path.len();
+        let path = pack_digits::<ARITY>(&self.tree_lvl_path)?;
+        let group_lens = check_leaf_position::<ARITY>(&self.tree_lvl_path, LeafId::new(path), tree_size).ok()?;
+
+        let mut siblings = Vec::with_capacity(lvls * (ARITY - 1));
+        let mut paddings = Vec::with_capacity(lvls);
+        let groups = self.tree_lvl_nodes.chunks(ARITY).zip(self.tree_lvl_path.iter().copied());
+        for ((group, path_index), len) in groups.zip(group_lens) {
+            for (index, hash) in group[..len].iter().enumerate() {
+                if index != path_index {
+                    siblings.push(hash.clone());
+                }
+            }
+            paddings.push(ARITY - len);
+        }
+
+        Some(MtCompactProof {
+            siblings,
+            path,
+            padding: pack_digits::<ARITY>(&paddings)?,
+            lvls,
+            root: self.root.clone(),
+            tree_size: Some(tree_size),
+        })
+    }
+}
+
+// [-] MerkleTree Proof
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] MerkleTree Compact Proof
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Compact form of [`MtProof`]. It contains only true siblings of proven nodes:
+/// proven nodes themselves & padding duplicates are omitted.
+/// 
+/// Path & padding are packed into `ARITY`-base numbers where `k`-th digit describes level `k`:
+/// * `path`: index of proven node in its group (so it's equal to index of the leaf)
+/// * `padding`: amount of padding duplicates at the end of the group
+/// 
+/// ```txt
+/// ARITY = 3; proof of leaf `4` (marked by `*`): 
+/// lvl 1: || A B* _ ||          --> siblings: A;     path digit: 1; padding digit: 1
+/// lvl 0: || 0 1 2 | 3 4* _ ||  --> siblings: 3;     path digit: 1; padding digit: 1
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtCompactProof<Hash, const ARITY: usize> {
+    siblings: Vec<Hash>,
+    path: usize,
+    padding: usize,
+    lvls: usize,
+    root: Hash,
+    /// [`None`] for proofs that were made without tree size 
+    tree_size: Option<usize>,
+}
+impl<Hash, const ARITY: usize> MtCompactProof<Hash, ARITY> {
+    /// True siblings of proven nodes (from leafs to root).
+    pub fn siblings(&self) -> &[Hash] {
+        &self.siblings
+    }
+
+    /// Root embedded into the proof.
+    pub fn root(&self) -> &Hash {
+        &self.root
+    }
+
+    /// Index of proven leaf (it's just packed path).
+    pub fn leaf_index(&self) -> LeafId {
+        LeafId::new(self.path)
+    }
+
+    /// Amount of leafs in the tree from which the proof was made, see [`MtProof::tree_size`].
+    pub fn tree_size(&self) -> Option<usize> {
+        self.tree_size
+    }
+
+    /// Iterator over `(path_index, group_len)` of each level (from leafs to root). 
+    fn lvl_groups(&self) -> impl Iterator<Item = (usize, usize)> {
+        let mut path = self.path;
+        let mut padding = self.padding;
+        (0..self.lvls).map(move |_|{
+            let path_index = path % ARITY;
+            let group_len = ARITY - padding % ARITY;
+            path /= ARITY;
+            padding /= ARITY;
+            (path_index, group_len)
+        })
+    }
+
+    /// Test that path, padding & amount of siblings are agree with each other.
+    fn check_well_formed(&self) -> Result<(), ProofError> {
+        let lvls = u32::try_from(self.lvls).unwrap_or(u32::MAX);
+        let is_packed = |x: usize|ARITY.checked_pow(lvls).is_none_or(|max|x < max);
+        // packed path (or padding) has more levels than proof 
+        for packed in [self.path, self.padding] {
+            if !is_packed(packed) {
+                let path_lvls = length_in_base(packed, ARITY) as usize;
+                return Err(ProofError::Truncated { path_lvls, node_lvls: self.lvls })
+            }
+        }
+
+        let mut expected = 0;
+        for (lvl, (index, len)) in self.lvl_groups().enumerate() {
+            if index >= len {
+                return Err(ProofError::PathIndexOutOfRange { lvl, index, len })
+            }
+            expected += len - 1;
+        }
+
+        let actual = self.siblings.len();
+        if actual != expected {
+            return Err(ProofError::WrongNodeCount { expected, actual })
+        }
+        Ok(())
+    }
+
+    /// Hash `hash` with siblings up to the root.
+    /// 
+    /// `on_group` is called for each level with `(left_siblings, proven_node, right_siblings)`.  
+    /// 
+    /// # Return
+    /// * [`Err`] if proof is malformed
+    /// * [`Ok`] of calculated root otherwise
+    fn calc_root<Hasher>(&self, mut hash: Hash, hasher: &mut Hasher, mut on_group: impl FnMut(&[Hash], &Hash, &[Hash])) -> Result<Hash, ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.check_well_formed()?;
+
+        let mut offset = 0;
+        for (path_index, group_len) in self.lvl_groups() {
+            let left = &self.siblings[offset..offset + path_index];
+            offset += path_index;
+            let right = &self.siblings[offset..offset + group_len - path_index - 1];
+            offset += right.len();
+            on_group(left, &hash, right);
+
+            let last = right.last().unwrap_or(&hash);
+            for hash in left.iter().chain([&hash]).chain(right) {
+                hasher.hash_arity_one_ref(hash);
+            }
+            for _ in group_len..ARITY {
+                hasher.hash_arity_one_ref(last);
+            }
+            hash = hasher.finish_arity();
+        }
+        Ok(hash)
+    }
+}
+impl<Hash: Eq, const ARITY: usize> MtCompactProof<Hash, ARITY> {
+    /// The same as [`Self::verify_detailed`] but without reason of rejection.
+    pub fn verify<Hasher>(&self, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_detailed(hash, hasher).is_ok()
+    }
+
+    pub fn verify_data<Data, Hasher>(&self, data: Data, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify(hash, hasher)
+    }
+
+    /// Verify the proof against its own embedded root & tell why it was rejected.
+    /// 
+    /// ⚠️ The embedded root is chosen by the proof creator, see [`MtProof::verify_detailed`].
+    /// 
+    /// Never panics, so it can be used on untrusted (for example deserialized) proofs.
+    pub fn verify_detailed<Hasher>(&self, hash: Hash, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_with_root_detailed(&self.root, hash, hasher)
+    }
+
+    /// Verify the proof against externally trusted root (the embedded root is ignored).
+    pub fn verify_with_root<Hasher>(&self, trusted_root: &Hash, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_with_root_detailed(trusted_root, hash, hasher).is_ok()
+    }
+
+    pub fn verify_data_with_root<Data, Hasher>(&self, trusted_root: &Hash, data: Data, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_with_root(trusted_root, hash, hasher)
+    }
+
+    /// Verify the proof against externally trusted root & tell why it was rejected.
+    /// 
+    /// Never panics, so it can be used on untrusted (for example deserialized) proofs.
+    pub fn verify_with_root_detailed<Hasher>(&self, trusted_root: &Hash, hash: Hash, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        let root = self.calc_root(hash, hasher, |_, _, _|())?;
+        if &root == trusted_root { Ok(()) } else { Err(ProofError::RootMismatch) }
+    }
+
+    pub fn verify_data_with_root_detailed<Data, Hasher>(&self, trusted_root: &Hash, data: Data, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_with_root_detailed(trusted_root, hash, hasher)
+    }
+
+    /// Verify the proof against trusted root, trusted tree size & expected leaf index,
+    /// see [`MtProof::verify_position`].
+    pub fn verify_position<Hasher>(&self, trusted_root: &Hash, id: LeafId, tree_size: usize, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_position_detailed(trusted_root, id, tree_size, hash, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify_position`] but tells why the proof was rejected.
+    pub fn verify_position_detailed<Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        id: LeafId, 
+        tree_size: usize, 
+        hash: Hash, 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        check_tree_size(self.tree_size, tree_size)?;
+        self.check_well_formed()?;
+        let path: Vec<_> = self.lvl_groups().map(|(path_index, _)|path_index).collect();
+        let group_lens = check_leaf_position::<ARITY>(&path, id, tree_size)?;
+        for (lvl, ((_, actual), expected)) in self.lvl_groups().zip(group_lens).enumerate() {
+            if actual != expected {
+                return Err(ProofError::WrongGroupLen { lvl, expected, actual })
+            }
+        }
+        self.verify_with_root_detailed(trusted_root, hash, hasher)
+    }
+
+    pub fn verify_data_detailed<Data, Hasher>(&self, data: Data, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let hash = hasher.hash_data(data);
+        self.verify_detailed(hash, hasher)
+    }
+}
+impl<Hash: Clone, const ARITY: usize> MtCompactProof<Hash, ARITY> {
+    /// Convert into [`MtProof`].
+    /// 
+    /// Compact proof doesn't contain proven nodes, so they are recalculated from `hash` of the leaf.
+    /// 
+    /// # Return
+    /// * [`None`] if proof is malformed
+    /// * [`Some`] of proof otherwise. It is equal to the proof from which `self` was made 
+    ///   (if `hash` is the leaf of that proof)
+    pub fn to_proof<Hasher>(&self, hash: Hash, hasher: &mut Hasher) -> Option<MtProof<Hash, ARITY>>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        let mut tree_lvl_nodes = Vec::with_capacity(self.lvls * ARITY);
+        let on_group = |left: &[Hash], hash: &Hash, right: &[Hash]|{
+            tree_lvl_nodes.extend_from_slice(left);
+            tree_lvl_nodes.push(hash.clone());
+            tree_lvl_nodes.extend_from_slice(right);
+            let last = right.last().unwrap_or(hash);
+            for _ in (left.len() + 1 + right.len())..ARITY {
+                tree_lvl_nodes.push(last.clone());
+            }
+        };
+        self.calc_root(hash, hasher, on_group).ok()?;
+
+        Some(MtProof {
+            tree_lvl_nodes,
+            tree_lvl_path: self.lvl_groups().map(|(path_index, _)|path_index).collect(),
+            root: self.root.clone(),
+            tree_size: self.tree_size,
+        })
+    }
+}
+
+/// Pack `digits` (least significant first) into `ARITY`-base number.
+/// 
+/// # Return
+/// * [`None`] if some digit is not less than `ARITY` or result doesn't fit into `usize`
+fn pack_digits<const ARITY: usize>(digits: &[usize]) -> Option<usize> {
+    let mut packed: usize = 0;
+    for digit in digits.iter().rev().copied() {
+        if digit >= ARITY { return None }
+        packed = packed.checked_mul(ARITY)?.checked_add(digit)?;
+    }
+    Some(packed)
+}
+
+// [-] MerkleTree Compact Proof
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] MerkleTree Range Proof
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Proof of contiguous range of leafs. 
+/// 
+/// It contains only boundary siblings of each level: 
+/// * `left`: nodes of the first group that are before the range
+/// * `right`: real (non-padding) nodes of the last group that are after the range
+/// 
+/// All other nodes are recalculated by verifier from the leafs of the range.
+/// 
+/// ```txt
+/// ARITY = 3; tree size = 8; proof of leafs `2..5` (marked by `*`): 
+/// lvl 1: || A* B* C ||                 --> left: -;   right: C
+/// lvl 0: || 0 1 2* | 3* 4* 5 | 6 7 _ || --> left: 0 1; right: 5
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtRangeProof<Hash, const ARITY: usize> {
+    start: usize,
+    end: usize,
+    left: Vec<Vec<Hash>>,
+    right: Vec<Vec<Hash>>,
+    root: Hash,
+    tree_size: usize,
+}
+impl<Hash, const ARITY: usize> MtRangeProof<Hash, ARITY> {
+    /// Proven leafs.
+    pub fn range(&self) -> Range<LeafId> {
+        LeafId::new(self.start)..LeafId::new(self.end)
+    }
+
+    /// Amount of leafs in the tree from which the proof was made.
+    pub fn tree_size(&self) -> usize {
+        self.tree_size
+    }
+
+    /// Root embedded into the proof.
+    pub fn root(&self) -> &Hash {
+        &self.root
+    }
+
+    /// Test that range, tree size & amount of boundary siblings are agree with each other
+    /// and that there are `leafs_len` leafs in the range.
+    fn check_well_formed(&self, leafs_len: usize) -> Result<(), ProofError> {
+        let (mut start, mut end) = (self.start, self.end);
+        if start >= end {
+            return Err(ProofError::EmptyRange)
+        }
+        if end > self.tree_size {
+            return Err(ProofError::LeafOutOfTree { index: end - 1, tree_size: self.tree_size })
+        }
+        if leafs_len != end - start {
+            return Err(ProofError::WrongNodeCount { expected: end - start, actual: leafs_len })
+        }
+
+        let expected = length_in_base(self.tree_size - 1, ARITY) as usize;
+        for actual in [self.left.len(), self.right.len()] {
+            if actual != expected {
+                return Err(ProofError::WrongLvlCount { expected, actual })
+            }
+        }
+
+        let mut lvl_len = self.tree_size;
+        for (left, right) in self.left.iter().zip(&self.right) {
+            let group_start = start - start % ARITY;
+            let group_end = end.div_ceil(ARITY).saturating_mul(ARITY).min(lvl_len);
+            for (expected, actual) in [(start - group_start, left.len()), (group_end - end, right.len())] {
+                if actual != expected {
+                    return Err(ProofError::WrongNodeCount { expected, actual })
+                }
+            }
+
+            start /= ARITY;
+            end = end.div_ceil(ARITY);
+            lvl_len = lvl_len.div_ceil(ARITY);
+        }
+        Ok(())
+    }
+}
+impl<Hash: Eq, const ARITY: usize> MtRangeProof<Hash, ARITY> {
+    /// The same as [`Self::verify_detailed`] but without reason of rejection.
+    pub fn verify<Hasher>(&self, leafs: &[Hash], hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_detailed(leafs, hasher).is_ok()
+    }
+
+    /// Verify the proof against its own embedded root & tell why it was rejected.
+    /// 
+    /// ⚠️ The embedded root is chosen by the proof creator, see [`MtProof::verify_detailed`].
+    /// 
+    /// Never panics.
+    pub fn verify_detailed<Hasher>(&self, leafs: &[Hash], hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_with_root_detailed(&self.root, leafs, hasher)
+    }
+
+    /// Verify the proof against externally trusted root (the embedded root is ignored).
+    pub fn verify_with_root<Hasher>(&self, trusted_root: &Hash, leafs: &[Hash], hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_with_root_detailed(trusted_root, leafs, hasher).is_ok()
+    }
+
+    pub fn verify_data_with_root<Data, Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        data: impl IntoIterator<Item = Data>, 
+        hasher: &mut Hasher
+    ) -> bool
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let leafs: Vec<_> = data.into_iter().map(|data|hasher.hash_data(data)).collect();
+        self.verify_with_root(trusted_root, &leafs, hasher)
+    }
+
+    pub fn verify_data_with_root_detailed<Data, Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        data: impl IntoIterator<Item = Data>, 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY> + DataHasher<Hash, Data>
+    {
+        let leafs: Vec<_> = data.into_iter().map(|data|hasher.hash_data(data)).collect();
+        self.verify_with_root_detailed(trusted_root, &leafs, hasher)
+    }
+
+    /// Verify the proof against externally trusted root & tell why it was rejected.
+    /// 
+    /// `leafs` are hashes of all leafs of [`Self::range`]. 
+    /// 
+    /// Never panics, so it can be used on untrusted (for example deserialized) proofs.
+    pub fn verify_with_root_detailed<Hasher>(&self, trusted_root: &Hash, leafs: &[Hash], hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.check_well_formed(leafs.len())?;
+
+        let mut lvl_hashes: Option<Vec<Hash>> = None;
+        for (left, right) in self.left.iter().zip(&self.right) {
+            let range_hashes = lvl_hashes.as_deref().unwrap_or(leafs);
+            let lvl_nodes: Vec<_> = left.iter().chain(range_hashes).chain(right).collect();
+            let next_lvl = lvl_nodes
+                .chunks(ARITY)
+                .map(|group|hash_padded_group::<_, _, ARITY>(hasher, group.iter().copied()))
+                .collect();
+            lvl_hashes = Some(next_lvl);
+        }
+
+        // well formed proof always ends with single node
+        let root = &lvl_hashes.as_deref().unwrap_or(leafs)[0];
+        if root == trusted_root { Ok(()) } else { Err(ProofError::RootMismatch) }
+    }
+
+    /// Verify the proof against trusted root, trusted tree size & expected range of leafs,
+    /// see [`MtProof::verify_position`].
+    pub fn verify_range<Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        range: Range<LeafId>, 
+        tree_size: usize, 
+        leafs: &[Hash], 
+        hasher: &mut Hasher
+    ) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_range_detailed(trusted_root, range, tree_size, leafs, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify_range`] but tells why the proof was rejected.
+    pub fn verify_range_detailed<Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        range: Range<LeafId>, 
+        tree_size: usize, 
+        leafs: &[Hash], 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        check_tree_size(Some(self.tree_size), tree_size)?;
+        let expected = range.start.0..range.end.0;
+        if expected != (self.start..self.end) {
+            return Err(ProofError::RangeMismatch { expected, actual: self.start..self.end })
+        }
+        self.verify_with_root_detailed(trusted_root, leafs, hasher)
+    }
+}
+
+// [-] MerkleTree Range Proof
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] MerkleTree Node Proof
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Proof that a hash is the node `node` of the tree. 
+/// It's the usual [`MtProof`] that starts from level `node.lvl` instead of leafs level. 
+/// 
+/// Main usage is to prove a whole subtree (for example a shard made by [`MerkleTree::split`]) 
+/// by a single proof, see [`Self::verify_subtree`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtNodeProof<Hash, const ARITY: usize> {
+    node: NodeId,
+    proof: MtProof<Hash, ARITY>,
+}
+impl<Hash, const ARITY: usize> MtNodeProof<Hash, ARITY> {
+    /// Proven node.
+    pub fn node(&self) -> NodeId {
+        self.node
+    }
+
+    /// Amount of leafs in the tree from which the proof was made, see [`MtProof::tree_size`].
+    pub fn tree_size(&self) -> Option<usize> {
+        self.proof.tree_size
+    }
+
+    /// Proof of the node. Its path leads to `node.index` on level `node.lvl`.
+    pub fn proof(&self) -> &MtProof<Hash, ARITY> {
+        &self.proof
+    }
+
+    pub fn into_proof(self) -> MtProof<Hash, ARITY> {
+        self.proof
+    }
+}
+impl<Hash: Eq, const ARITY: usize> MtNodeProof<Hash, ARITY> {
+    /// Verify that `node_hash` is a node of the tree with trusted root.
+    /// 
+    /// Position of the node is not checked, for it use [`Self::verify_position`].
+    pub fn verify_with_root<Hasher>(&self, trusted_root: &Hash, node_hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_with_root_detailed(trusted_root, node_hash, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify_with_root`] but tells why the proof was rejected.
+    pub fn verify_with_root_detailed<Hasher>(&self, trusted_root: &Hash, node_hash: Hash, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.proof.verify_with_root_detailed(trusted_root, node_hash, hasher)
+    }
+
+    /// Verify that `node_hash` is exactly the node `node` of the tree with trusted root & trusted tree size.
+    pub fn verify_position<Hasher>(&self, trusted_root: &Hash, node: NodeId, tree_size: usize, node_hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_position_detailed(trusted_root, node, tree_size, node_hash, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify_position`] but tells why the proof was rejected.
+    /// 
+    /// Errors about leafs (like [`ProofError::LeafIndexMismatch`]) are about nodes of level `node.lvl`.
+    pub fn verify_position_detailed<Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        node: NodeId, 
+        tree_size: usize, 
+        node_hash: Hash, 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        if self.node != node {
+            return Err(ProofError::NodeMismatch { expected: node, actual: self.node })
+        }
+        check_tree_size(self.proof.tree_size, tree_size)?;
+
+        let height = if tree_size == 0 { 0 } else { length_in_base(tree_size - 1, ARITY) as usize + 1 };
+        let mut lvl_len = tree_size;
+        for _ in 0..node.lvl.min(height) {
+            lvl_len = lvl_len.div_ceil(ARITY);
+        }
+        if node.lvl >= height || node.index >= lvl_len {
+            let lvl_len = if node.lvl < height { lvl_len } else { 0 };
+            return Err(ProofError::NodeOutOfTree { lvl: node.lvl, index: node.index, lvl_len })
+        }
+
+        // nodes of level `node.lvl` are leafs of the proof
+        let proof = &self.proof;
+        check_aligned_proof::<ARITY>(proof.tree_lvl_nodes.len(), &proof.tree_lvl_path)?;
+        let group_lens = check_leaf_position::<ARITY>(&proof.tree_lvl_path, LeafId::new(node.index), lvl_len)?;
+        check_aligned_padding::<_, ARITY>(&proof.tree_lvl_nodes, &group_lens)?;
+        proof.verify_with_root_detailed(trusted_root, node_hash, hasher)
+    }
+}
+impl<Hash: Clone + Eq, const ARITY: usize> MtNodeProof<Hash, ARITY> {
+    /// Verify that `subtree` is the subtree of the node of the tree with trusted root.
+    /// 
+    /// `subtree` can be lower than the node (as the last tree of [`MerkleTree::split`]): 
+    /// in such case its root is lifted up to level `node.lvl` by padding-only groups.
+    /// 
+    /// Position of the node is not checked, for it use [`Self::verify_subtree_position`].
+    pub fn verify_subtree<TreeHasher, Hasher>(&self, trusted_root: &Hash, subtree: &MerkleTree<Hash, TreeHasher, ARITY>, hasher: &mut Hasher) -> bool
+    where 
+        TreeHasher: ArityHasher<Hash, ARITY>,
+        Hasher: ArityHasher<Hash, ARITY>,
+    {
+        self.verify_subtree_detailed(trusted_root, subtree, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify_subtree`] but tells why the proof was rejected.
+    pub fn verify_subtree_detailed<TreeHasher, Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        subtree: &MerkleTree<Hash, TreeHasher, ARITY>, 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where 
+        TreeHasher: ArityHasher<Hash, ARITY>,
+        Hasher: ArityHasher<Hash, ARITY>,
+    {
+        let node_hash = self.lift_subtree_root(subtree, hasher)?;
+        self.verify_with_root_detailed(trusted_root, node_hash, hasher)
+    }
+
+    /// Verify that `subtree` is exactly the subtree of node `node` of the tree with trusted root & trusted tree size.
+    pub fn verify_subtree_position<TreeHasher, Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        node: NodeId, 
+        tree_size: usize, 
+        subtree: &MerkleTree<Hash, TreeHasher, ARITY>, 
+        hasher: &mut Hasher
+    ) -> bool
+    where 
+        TreeHasher: ArityHasher<Hash, ARITY>,
+        Hasher: ArityHasher<Hash, ARITY>,
+    {
+        self.verify_subtree_position_detailed(trusted_root, node, tree_size, subtree, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify_subtree_position`] but tells why the proof was rejected.
+    pub fn verify_subtree_position_detailed<TreeHasher, Hasher>(
+        &self, 
+        trusted_root: &Hash, 
+        node: NodeId, 
+        tree_size: usize, 
+        subtree: &MerkleTree<Hash, TreeHasher, ARITY>, 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where 
+        TreeHasher: ArityHasher<Hash, ARITY>,
+        Hasher: ArityHasher<Hash, ARITY>,
+    {
+        let node_hash = self.lift_subtree_root(subtree, hasher)?;
+        self.verify_position_detailed(trusted_root, node, tree_size, node_hash, hasher)
+    }
+
+    /// Calculate hash of node `self.node` from the root of `subtree`.
+    fn lift_subtree_root<TreeHasher, Hasher>(&self, subtree: &MerkleTree<Hash, TreeHasher, ARITY>, hasher: &mut Hasher) -> Result<Hash, ProofError>
+    where 
+        TreeHasher: ArityHasher<Hash, ARITY>,
+        Hasher: ArityHasher<Hash, ARITY>,
+    {
+        let (lvl, height) = (self.node.lvl, subtree.height());
+        if height == 0 || height > lvl + 1 {
+            return Err(ProofError::SubtreeTooHigh { lvl, height })
+        }
+
+        let mut hash = subtree.root();
+        for _ in height..=lvl {
+            hash = hash_padded_group::<_, _, ARITY>(hasher, [&hash]);
+        }
+        Ok(hash)
+    }
+}
+
+/// Proofs of leafs of a shard (a tree made by [`MerkleTree::split`]) 
+/// can be converted into proofs of the whole tree & back:
+/// ```txt
+/// shard-local proof + node proof of the shard --compose--> proof of the whole tree
+/// proof of the whole tree --truncate--> shard-local proof
+/// ```
+impl<Hash: Clone + Eq, const ARITY: usize> MtProof<Hash, ARITY> {
+    /// Concatenate shard-local proof (`self`) with the node proof of the shard.
+    /// 
+    /// If the shard is lower than the node (as the last tree of [`MerkleTree::split`]) 
+    /// the proof is lifted up to level of the node by padding-only groups.
+    /// 
+    /// # Return
+    /// * [`Err`] if proofs can't be concatenated (for example root of the shard is not the node)
+    /// * [`Ok`] of proof against root of the whole tree. 
+    ///   It is equal to the proof that is made by the whole tree. 
+    pub fn compose<Hasher>(mut self, node_proof: &MtNodeProof<Hash, ARITY>, hasher: &mut Hasher) -> Result<Self, ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.check_well_formed()?;
+        node_proof.proof.check_well_formed()?;
+
+        let node = node_proof.node;
+        let local_lvls = self.lvl_count();
+        if local_lvls > node.lvl {
+            return Err(ProofError::SubtreeTooHigh { lvl: node.lvl, height: local_lvls + 1 })
+        }
+        if let (Some(local_size), Some(tree_size)) = (self.tree_size, node_proof.tree_size()) {
+            let Some(expected) = shard_size::<ARITY>(node, tree_size) else {
+                return Err(ProofError::NodeOutOfTree { lvl: node.lvl, index: node.index, lvl_len: tree_size })
+            };
+            check_tree_size(Some(local_size), expected)?;
+        }
+
+        let mut node_hash = self.root;
+        for _ in local_lvls..node.lvl {
+            self.tree_lvl_nodes.extend(std::iter::repeat_n(node_hash.clone(), ARITY));
+            self.tree_lvl_path.push(0);
+            node_hash = hash_padded_group::<_, _, ARITY>(hasher, [&node_hash]);
+        }
+
+        let proof = &node_proof.proof;
+        match proof.tree_lvl_path.first() {
+            Some(&path_index) if proof.tree_lvl_nodes[path_index] != node_hash => {
+                return Err(ProofError::WrongNode(node.lvl))
+            }
+            None if proof.root != node_hash => return Err(ProofError::RootMismatch),
+            _ => {}
+        }
+
+        self.tree_lvl_nodes.extend_from_slice(&proof.tree_lvl_nodes);
+        self.tree_lvl_path.extend_from_slice(&proof.tree_lvl_path);
+        self.root = proof.root.clone();
+        self.tree_size = proof.tree_size;
+        Ok(self)
+    }
+
+    /// Truncate proof of the whole tree into proof of the leaf in the shard 
+    /// that is made by [`MerkleTree::split`] with level `lvl`.
+    /// 
+    /// Padding-only levels of the last (lower) shard are dropped, 
+    /// so the result is equal to the proof that is made by the shard itself.
+    /// 
+    /// Proof must contain tree size (otherwise it's impossible to know height of the last shard).
+    pub fn truncate(&self, lvl: usize) -> Result<Self, ProofError> {
+        self.check_well_formed()?;
+        let tree_size = self.tree_size.ok_or(ProofError::UnknownTreeSize)?;
+        let index = self.leaf_index().map_or(usize::MAX, LeafId::index);
+        check_leaf_position::<ARITY>(&self.tree_lvl_path, LeafId::new(index), tree_size)?;
+
+        let lvl_count = self.lvl_count();
+        let lvl_pow = u32::try_from(lvl).ok().and_then(|lvl|ARITY.checked_pow(lvl));
+        let node = NodeId { lvl, index: lvl_pow.map_or(0, |pow|index / pow) };
+        if lvl > lvl_count {
+            return Err(ProofError::NodeOutOfTree { lvl, index: node.index, lvl_len: 0 })
+        }
+
+        // never `None` because leaf is in the tree & level is in the tree 
+        let shard_size = shard_size::<ARITY>(node, tree_size).ok_or(ProofError::LeafOutOfTree { index, tree_size })?;
+        let local_lvls = length_in_base(shard_size - 1, ARITY) as usize;
+        let root = if local_lvls == lvl_count {
+            self.root.clone()
+        } else {
+            self.tree_lvl_nodes[local_lvls * ARITY + self.tree_lvl_path[local_lvls]].clone()
+        };
+
+        Ok(Self {
+            tree_lvl_nodes: self.tree_lvl_nodes[..local_lvls * ARITY].to_vec(),
+            tree_lvl_path: self.tree_lvl_path[..local_lvls].to_vec(),
+            root,
+            tree_size: Some(shard_size),
+        })
+    }
+}
+
+/// Amount of leafs in the subtree of node `node` in the tree with `tree_size` leafs.
+/// 
+/// # Return
+/// * [`None`] if node is out of the tree
+fn shard_size<const ARITY: usize>(node: NodeId, tree_size: usize) -> Option<usize> {
+    let capacity = u32::try_from(node.lvl).ok().and_then(|lvl|ARITY.checked_pow(lvl)).unwrap_or(usize::MAX);
+    let start = node.index.checked_mul(capacity)?;
+    if start >= tree_size { return None }
+    Some(capacity.min(tree_size - start))
+}
+
+// [-] MerkleTree Node Proof
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] MerkleTree Consistency Proof
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Proof that the tree with `old_size` leafs is a prefix of the tree with `new_size` leafs
+/// (so an append-only tree was only appended between the two roots).
+/// 
+/// Nodes of the old tree that are left of the path to its last leaf `old_size - 1`
+/// are roots of complete subtrees, so they are the same in the new tree.
+/// Therefore the proof is the usual proof of leaf `old_size - 1` in the new tree: 
+/// the new root is calculated from the whole groups of the path & 
+/// the old root from the last leaf & its left siblings (padded by the node of the path).
+/// 
+/// ```txt
+/// ARITY = 2, old_size = 3, new_size = 4:
+///   new:      R'           old:      R
+///          A     B'               A     B
+///         a b   c d              a b   c (c)
+/// proof: {c d}, {A B'} --> old root: hash(A, hash(c, c))
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtConsistencyProof<Hash, const ARITY: usize> {
+    old_size: usize,
+    /// proof of leaf `old_size - 1` in the new tree
+    proof: MtDetachedProof<Hash, ARITY>,
+}
+impl<Hash, const ARITY: usize> MtConsistencyProof<Hash, ARITY> {
+    /// Amount of leafs of the old tree.
+    pub fn old_size(&self) -> usize {
+        self.old_size
+    }
+
+    /// Amount of leafs of the new tree.
+    pub fn new_size(&self) -> Option<usize> {
+        self.proof.tree_size
+    }
+}
+impl<Hash: Clone + Eq, const ARITY: usize> MtConsistencyProof<Hash, ARITY> {
+    /// Verify that the trusted old root is the root of the first `old_size` leafs 
+    /// of the tree with the trusted new root.
+    pub fn verify<Hasher>(&self, old_root: &Hash, old_size: usize, new_root: &Hash, new_size: usize, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_detailed(old_root, old_size, new_root, new_size, hasher).is_ok()
+    }
+
+    /// The same as [`Self::verify`] but tells why the proof was rejected.
+    /// 
+    /// Never panics, so it can be used on untrusted (for example deserialized) proofs.
+    pub fn verify_detailed<Hasher>(
+        &self, 
+        old_root: &Hash, 
+        old_size: usize, 
+        new_root: &Hash, 
+        new_size: usize, 
+        hasher: &mut Hasher
+    ) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        if self.old_size != old_size {
+            return Err(ProofError::OldSizeMismatch { expected: old_size, actual: self.old_size })
+        }
+        if old_size == 0 {
+            return Err(ProofError::EmptyOldTree)
+        }
+        let (nodes, path) = (&self.proof.tree_lvl_nodes, &self.proof.tree_lvl_path);
+        check_tree_size(self.proof.tree_size, new_size)?;
+        check_aligned_proof::<ARITY>(nodes.len(), path)?;
+        let group_lens = check_leaf_position::<ARITY>(path, LeafId(old_size - 1), new_size)?;
+        check_aligned_padding::<_, ARITY>(nodes, &group_lens)?;
+
+        // tree with a single leaf: the root is the leaf
+        let Some(&path_index) = path.first() else {
+            return if old_root == new_root { Ok(()) } else { Err(ProofError::OldRootMismatch) }
+        };
+        let last_leaf = nodes[path_index].clone();
+        self.proof.verify_with_root_detailed(new_root, last_leaf.clone(), hasher)?;
+
+        let old_lvls = length_in_base(old_size - 1, ARITY) as usize;
+        let mut hash = last_leaf;
+        for (group, path_index) in nodes.chunks(ARITY).zip(path.iter().copied()).take(old_lvls) {
+            for node in &group[..path_index] {
+                hasher.hash_arity_one_ref(node);
+            }
+            for _ in path_index..ARITY {
+                hasher.hash_arity_one_ref(&hash);
+            }
+            hash = hasher.finish_arity();
+        }
+        if &hash == old_root { Ok(()) } else { Err(ProofError::OldRootMismatch) }
+    }
+}
+
+// [-] MerkleTree Consistency Proof
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] MerkleTree All Proofs
+
+/// Proof from node `node` up to the root of the tree with levels `tree_lvls`.
+fn lvls_proof_ref<Hash, const ARITY: usize>(tree_lvls: &[Vec<Hash>], node: NodeId) -> MtProofRef<'_, Hash, ARITY> {
+    lvls_proof_ref_with(tree_lvls.len(), |lvl| &tree_lvls[lvl], node)
+}
+
+/// The same as [`lvls_proof_ref`] for levels that are not stored as `Vec`s.
+fn lvls_proof_ref_with<'tree, Hash, const ARITY: usize>(
+    lvl_count: usize,
+    tree_lvl: impl Fn(usize) -> &'tree [Hash],
+    node: NodeId,
+) -> MtProofRef<'tree, Hash, ARITY> {
+    let mut index = node.index;
+    let mut tree_lvl_nodes = vec![];
+    let mut tree_lvl_path = vec![];
+
+    let root_lvl = lvl_count.checked_sub(1).expect("tree always has at least one level");
+    for tree_lvl in (node.lvl.min(root_lvl)..root_lvl).map(&tree_lvl) {
+        let next_index = index / ARITY;
+        let index_start = next_index * ARITY;
+        let index_end = (index_start + ARITY).min(tree_lvl.len());
+        tree_lvl_nodes.push(&tree_lvl[index_start..index_end]);
+        tree_lvl_path.push(index % ARITY);
+        index = next_index;
+    }
+
+    MtProofRef {
+        tree_lvl_nodes,
+        tree_lvl_path,
+        root: &tree_lvl(root_lvl)[0],
+        tree_size: tree_lvl(0).len(),
+    }
+}
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Iterator over proofs of all leafs (in order of leafs). 
+/// 
+/// Proofs borrow the groups from the tree, so hashes are not cloned ([`MtProofRef::to_owned`] if you need it).
+/// Proofs of neighbour leafs share most of their groups, so the iterator keeps 
+/// the previous proof & replaces only groups that are changed: 
+/// the group on level `lvl` is changed once per `pow(ARITY, lvl + 1)` leafs.
+/// 
+/// You can get it by [`MerkleTree::proofs_iter`] or [`MtProofSet::iter`].
+#[derive(Debug, Clone)]
+pub struct MtProofsIter<'tree, Hash, const ARITY: usize> {
+    tree_lvls: &'tree [Vec<Hash>],
+    next_id: usize,
+    /// The previous proof. 
+    tree_lvl_nodes: Vec<&'tree [Hash]>,
+    tree_lvl_path: Vec<usize>,
+}
+impl<'tree, Hash, const ARITY: usize> MtProofsIter<'tree, Hash, ARITY> {
+    fn new(tree_lvls: &'tree [Vec<Hash>]) -> Self {
+        let lvls = tree_lvls.len().saturating_sub(1);
+        Self {
+            tree_lvls,
+            next_id: 0,
+            tree_lvl_nodes: Vec::with_capacity(lvls),
+            tree_lvl_path: vec![0; lvls],
+        }
+    }
+
+    /// Replace group on level `lvl` by the group that starts from node `group_start`.
+    fn set_group(&mut self, lvl: usize, group_start: usize) {
+        let tree_lvl = &self.tree_lvls[lvl];
+        let group = &tree_lvl[group_start..(group_start + ARITY).min(tree_lvl.len())];
+        if let Some(prev) = self.tree_lvl_nodes.get_mut(lvl) {
+            *prev = group;
+        } else {
+            self.tree_lvl_nodes.push(group);
+        }
+    }
+}
+impl<'tree, Hash, const ARITY: usize> Iterator for MtProofsIter<'tree, Hash, ARITY> {
+    type Item = MtProofRef<'tree, Hash, ARITY>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next_id;
+        if id >= self.tree_lvls[0].len() { return None }
+        self.next_id += 1;
+
+        // on the next leaf only the lowest levels with zero digit of `id` are changed
+        let mut index = id;
+        for lvl in 0..self.tree_lvl_path.len() {
+            let path_index = index % ARITY;
+            self.tree_lvl_path[lvl] = path_index;
+            if path_index != 0 { break }
+            self.set_group(lvl, index);
+            index /= ARITY;
+        }
+
+        let root_lvl = &self.tree_lvls[self.tree_lvls.len() - 1];
+        Some(MtProofRef {
+            tree_lvl_nodes: self.tree_lvl_nodes.clone(),
+            tree_lvl_path: self.tree_lvl_path.clone(),
+            root: &root_lvl[0],
+            tree_size: self.tree_lvls[0].len(),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.tree_lvls[0].len() - self.next_id;
+        (len, Some(len))
+    }
+}
+impl<'tree, Hash, const ARITY: usize> ExactSizeIterator for MtProofsIter<'tree, Hash, ARITY> { }
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Proofs of all leafs of the tree. Each node is stored only once & shared between proofs.
+/// 
+/// Unlike [`MerkleTree`] it doesn't need a hasher, so it can be sent as is 
+/// (for example to the service that hands out proofs to the leaf owners).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtProofSet<Hash, const ARITY: usize> {
+    /// All levels of the tree (including root level)
+    tree_lvls: Vec<Vec<Hash>>,
+}
+impl<Hash, const ARITY: usize> MtProofSet<Hash, ARITY> {
+    #[inline]
+    pub fn tree_size(&self) -> usize {
+        self.tree_lvls[0].len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree_lvls[0].is_empty()
+    }
+
+    /// # panic
+    /// * if `self.is_empty()`
+    #[inline]
+    pub fn root(&self) -> &Hash {
+        &self.tree_lvls[self.tree_lvls.len() - 1][0]
+    }
+
+    /// The same as [`MerkleTree::proof_ref`].
+    /// 
+    /// # panic
+    /// * if `id` is out of the tree
+    pub fn proof_ref(&self, id: LeafId) -> MtProofRef<'_, Hash, ARITY> {
+        assert!(id.0 < self.tree_size(), "leaf must be in the tree");
+        lvls_proof_ref(&self.tree_lvls, NodeId { lvl: 0, index: id.0 })
+    }
+
+    /// Proofs of all leafs, see [`MtProofsIter`].
+    pub fn iter(&self) -> MtProofsIter<'_, Hash, ARITY> {
+        MtProofsIter::new(&self.tree_lvls)
+    }
+}
+impl<Hash: Clone, const ARITY: usize> MtProofSet<Hash, ARITY> {
+    /// # panic
+    /// * if `id` is out of the tree
+    pub fn proof_owned(&self, id: LeafId) -> MtProof<Hash, ARITY> {
+        self.proof_ref(id).to_owned()
+    }
+}
+
+// [-] MerkleTree All Proofs
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] MerkleTree Batch Verification
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Verifier of many proofs against the same trusted root.
+/// 
+/// It remembers nodes of successfully verified proofs (they are authenticated by the trusted root),
+/// so verification of the next proof stops on the first already authenticated node of its path
+/// (groups above it are only compared with the remembered ones). 
+/// For example proofs of all leafs of the tree hash each group only once.
+/// 
+/// Nodes are remembered by their depth from the root & only for proofs of one tree size:
+/// the one passed to [`Self::with_tree_size`] or else of the first accepted proof.
+/// Padding is not remembered. Proofs of another (or unknown) tree size are verified alone.
+/// 
+/// Result is the same as of [`MtProof::verify_with_root_detailed`] & doesn't depend on the order of proofs.
+#[derive(Debug, Clone)]
+pub struct MtBatchVerifier<Hash, const ARITY: usize> {
+    trusted_root: Hash,
+    tree_size: Option<usize>,
+    /// key: `(depth, index)`, children of the root have depth `1`
+    authenticated: HashMap<(usize, usize), Hash>,
+}
+impl<Hash, const ARITY: usize> MtBatchVerifier<Hash, ARITY> {
+    pub fn new(trusted_root: Hash) -> Self {
+        Self {
+            trusted_root,
+            tree_size: None,
+            authenticated: HashMap::new(),
+        }
+    }
+
+    /// Verifier that remembers nodes only of proofs of the tree with `tree_size` leafs.
+    pub fn with_tree_size(trusted_root: Hash, tree_size: usize) -> Self {
+        Self {
+            tree_size: Some(tree_size),
+            ..Self::new(trusted_root)
+        }
+    }
+
+    pub fn trusted_root(&self) -> &Hash {
+        &self.trusted_root
+    }
+
+    /// Tree size of proofs whose nodes are remembered.
+    pub fn tree_size(&self) -> Option<usize> {
+        self.tree_size
+    }
+
+    /// Amount of remembered authenticated nodes.
+    pub fn authenticated_count(&self) -> usize {
+        self.authenticated.len()
+    }
+}
+impl<Hash: Clone + Eq, const ARITY: usize> MtBatchVerifier<Hash, ARITY> {
+    pub fn verify<Hasher>(&mut self, proof: &MtProof<Hash, ARITY>, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        self.verify_detailed(proof, hash, hasher).is_ok()
+    }
+
+    /// Verify the proof against the trusted root & tell why it was rejected.
+    /// 
+    /// Never panics.
+    pub fn verify_detailed<Hasher>(&mut self, proof: &MtProof<Hash, ARITY>, hash: Hash, hasher: &mut Hasher) -> Result<(), ProofError>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        if self.verify_remembered(proof, hash.clone(), hasher) {
+            return Ok(())
+        }
+        // errors are reported exactly as by the standalone verification
+        proof.verify_with_root_detailed(&self.trusted_root, hash, hasher)
+    }
+
+    /// Verify the proof using remembered nodes & remember its nodes if it's accepted.
+    /// 
+    /// # Return
+    /// `false` if the proof isn't accepted or can't be verified this way.
+    fn verify_remembered<Hasher>(&mut self, proof: &MtProof<Hash, ARITY>, mut hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        let Some(tree_size) = proof.tree_size else { return false };
+        if self.tree_size.is_some_and(|expected|expected != tree_size) || proof.check_well_formed().is_err() {
+            return false
+        }
+        let Some(leaf) = pack_digits::<ARITY>(&proof.tree_lvl_path) else { return false };
+        let Ok(group_lens) = check_leaf_position::<ARITY>(&proof.tree_lvl_path, LeafId::new(leaf), tree_size) else { 
+            return false 
+        };
+
+        let groups: Vec<&[Hash]> = proof.tree_lvl_nodes.chunks(ARITY).collect();
+        let lvls = groups.len();
+        let mut index = leaf;
+        let group_starts: Vec<usize> = proof.tree_lvl_path.iter().map(|path_index|{
+            let group_start = index - path_index;
+            index /= ARITY;
+            group_start
+        }).collect();
+
+        // hash up to the first remembered node
+        let mut hashed = 0;
+        while hashed < lvls {
+            let index = group_starts[hashed] + proof.tree_lvl_path[hashed];
+            if let Some(known) = self.authenticated.get(&(lvls - hashed, index)) {
+                if known != &hash {
+                    return false
+                }
+                break
+            }
+            if groups[hashed][proof.tree_lvl_path[hashed]] != hash {
+                return false
+            }
+            hash = hash_padded_group::<_, _, ARITY>(hasher, groups[hashed]);
+            hashed += 1;
+        }
+
+        if hashed == lvls {
+            if hash != self.trusted_root {
+                return false
+            }
+        } else {
+            // groups above are authenticated only if they are the remembered ones
+            for lvl in hashed..lvls {
+                let (group, len) = (groups[lvl], group_lens[lvl]);
+                let is_remembered = group[..len].iter().enumerate().all(|(i, node)|{
+                    self.authenticated.get(&(lvls - lvl, group_starts[lvl] + i)) == Some(node)
+                });
+                if !is_remembered || group[len..].iter().any(|node|node != &group[len - 1]) {
+                    return false
+                }
+            }
+        }
+
+        self.tree_size = Some(tree_size);
+        for lvl in 0..hashed {
+            for (i, node) in groups[lvl][..group_lens[lvl]].iter().enumerate() {
+                self.authenticated.entry((lvls - lvl, group_starts[lvl] + i)).or_insert_with(||node.clone());
+            }
+        }
+        true
+    }
+}
+
+impl<Hash: Clone + Eq, const ARITY: usize> MtProof<Hash, ARITY> {
+    /// Verify many proofs against the same trusted root (the embedded roots are ignored).
+    /// 
+    /// Nodes that are shared by the proofs are hashed only once, see [`MtBatchVerifier`].
+    /// 
+    /// # Return
+    /// Result of each proof (in the same order as `proofs`).
+    pub fn verify_batch<P, Hasher>(
+        trusted_root: &Hash, 
+        proofs: impl IntoIterator<Item = (P, Hash)>, 
+        hasher: &mut Hasher
+    ) -> Vec<Result<(), ProofError>>
+    where 
+        P: Borrow<Self>,
+        Hasher: ArityHasher<Hash, ARITY>,
+    {
+        let mut verifier = MtBatchVerifier::new(trusted_root.clone());
+        proofs
+            .into_iter()
+            .map(|(proof, hash)|verifier.verify_detailed(proof.borrow(), hash, hasher))
+            .collect()
+    }
+}
+
+// [-] MerkleTree Batch Verification
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] MerkleTree Proof Update
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Change record: new hashes of all nodes that are changed by a mutation of the tree.
+/// It allows to update outstanding proofs by [`MtProof::update`] without access to the tree.
+/// 
+/// It's made by [`MerkleTree::change_record`] right after the mutation:
+/// * after [`MerkleTree::replace`] of leaf `id`: with range `id..id + 1`
+/// * after [`MerkleTree::push_batched`], [`MerkleTree::replace_batched`] & others: with returned range
+/// * after [`MerkleTree::pop`]: with range of the new last leaf 
+///   (all changed nodes are its ancestors)
+/// 
+/// ```txt
+/// ARITY = 3; leaf `4` was replaced (changed nodes are marked by `*`):
+/// lvl 2: || R* ||                     --> (0, [R])
+/// lvl 1: || A B* _ ||                 --> (1, [B])
+/// lvl 0: || 0 1 2 | 3 4* _ ||         --> (4, [4])
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtChange<Hash, const ARITY: usize> {
+    /// For each level except root level (from leafs to root): 
+    /// index of the first changed node & new hashes of changed nodes.
+    tree_lvl_changes: Vec<(usize, Vec<Hash>)>,
+    root: Hash,
+    tree_size: usize,
+}
+impl<Hash, const ARITY: usize> MtChange<Hash, ARITY> {
+    /// Root of the tree after the change.
+    pub fn root(&self) -> &Hash {
+        &self.root
+    }
+
+    /// Amount of leafs in the tree after the change.
+    pub fn tree_size(&self) -> usize {
+        self.tree_size
+    }
+
+    /// New hash of the node (if it was changed).
+    pub fn node(&self, node: NodeId) -> Option<&Hash> {
+        let (start, hashes) = self.tree_lvl_changes.get(node.lvl)?;
+        hashes.get(node.index.checked_sub(*start)?)
+    }
+
+    /// Test that changed nodes are in the tree with claimed size.
+    fn check_well_formed(&self) -> Result<(), ProofError> {
+        if self.tree_size == 0 {
+            return Err(ProofError::LeafOutOfTree { index: 0, tree_size: 0 })
+        }
+        let expected = length_in_base(self.tree_size - 1, ARITY) as usize;
+        let actual = self.tree_lvl_changes.len();
+        if actual != expected {
+            return Err(ProofError::WrongLvlCount { expected, actual })
+        }
+
+        let mut lvl_len = self.tree_size;
+        for (lvl, (start, hashes)) in self.tree_lvl_changes.iter().enumerate() {
+            if start.saturating_add(hashes.len()) > lvl_len {
+                let index = lvl_len.max(*start);
+                return Err(ProofError::NodeOutOfTree { lvl, index, lvl_len })
+            }
+            lvl_len = lvl_len.div_ceil(ARITY);
+        }
+        Ok(())
+    }
+}
+
+impl<Hash: Clone, const ARITY: usize> MtProof<Hash, ARITY> {
+    /// Patch the stale proof by the change record (see [`MtChange`]).
+    /// 
+    /// Change records must be applied in the same order as the tree was mutated.\
+    /// Proof must contain tree size (otherwise it's impossible to know which nodes are padding).
+    /// 
+    /// After the update the proof is equal to the proof made by the mutated tree
+    /// (if the change record contains all changed nodes).
+    /// 
+    /// # Return
+    /// * [`Err`] if proof can't be updated (then it's unchanged), 
+    ///   for example if the proven leaf was popped from the tree
+    /// * [`Ok`] otherwise
+    pub fn update(&mut self, change: &MtChange<Hash, ARITY>) -> Result<(), ProofError> {
+        self.check_well_formed()?;
+        let old_size = self.tree_size.ok_or(ProofError::UnknownTreeSize)?;
+        let index = self.leaf_index().map_or(usize::MAX, LeafId::index);
+        let old_group_lens = check_leaf_position::<ARITY>(&self.tree_lvl_path, LeafId::new(index), old_size)?;
+
+        change.check_well_formed()?;
+        if index >= change.tree_size {
+            return Err(ProofError::LeafOutOfTree { index, tree_size: change.tree_size })
+        }
+
+        let lvls = change.tree_lvl_changes.len();
+        let mut tree_lvl_nodes = Vec::with_capacity(lvls * ARITY);
+        let mut tree_lvl_path = Vec::with_capacity(lvls);
+        let mut node_index = index;
+        let mut lvl_len = change.tree_size;
+        for lvl in 0..lvls {
+            let path_index = node_index % ARITY;
+            let group_start = node_index - path_index;
+            let group_len = ARITY.min(lvl_len - group_start);
+            for i in 0..group_len {
+                let node = NodeId { lvl, index: group_start + i };
+                let hash = match change.node(node) {
+                    Some(hash) => hash,
+                    // unchanged node of the old proof 
+                    None if old_group_lens.get(lvl).is_some_and(|&len|i < len) => &self.tree_lvl_nodes[lvl * ARITY + i],
+                    // the tree became higher, so the old root is just a node now
+                    None if lvl == old_group_lens.len() && i == path_index => &self.root,
+                    None => return Err(ProofError::ChangeMissingNode { lvl, index: node.index }),
+                };
+                tree_lvl_nodes.push(hash.clone());
+            }
+            let last = tree_lvl_nodes[tree_lvl_nodes.len() - 1].clone();
+            tree_lvl_nodes.resize((lvl + 1) * ARITY, last);
+            tree_lvl_path.push(path_index);
+
+            node_index /= ARITY;
+            lvl_len = lvl_len.div_ceil(ARITY);
+        }
+
+        self.tree_lvl_nodes = tree_lvl_nodes;
+        self.tree_lvl_path = tree_lvl_path;
+        self.root = change.root.clone();
+        self.tree_size = Some(change.tree_size);
+        Ok(())
+    }
+}
+
+// [-] MerkleTree Proof Update
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] MerkleTree Envelope
+
+/// Reason why an envelope was rejected.
+#[cfg(feature = "serde")]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MtEnvelopeError {
+    #[error("Unsupported envelope version {actual}. Supported version is {supported}.")]
+    UnsupportedVersion { supported: u32, actual: u32 },
+    #[error("Envelope is made by hash algorithm {actual:?}, but verifier uses {expected:?}.")]
+    AlgorithmMismatch { expected: String, actual: String },
+    #[error("Envelope is made for the tree with arity {actual}, but verifier expects arity {expected}.")]
+    ArityMismatch { expected: usize, actual: usize },
+    #[error("Envelope is made with padding rule {actual:?}, but verifier uses {expected:?}.")]
+    PaddingMismatch { expected: String, actual: String },
+    #[error("Envelope says that tree has {header} leafs, but its body has {body} leafs.")]
+    TreeSizeMismatch { header: usize, body: usize },
+    #[error("Envelope says that proven leaf is {header:?}, but its body proves leaf {body:?}.")]
+    LeafIndexMismatch { header: Option<usize>, body: Option<usize> },
+    #[error(transparent)]
+    Proof(#[from] ProofError),
+}
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Self-describing versioned envelope for serialized [`MtProof`] or [`MtSerde`] (the `body`).
+/// 
+/// Header says how the body was made: hash algorithm (see [`MtHasherId`](crate::MtHasherId)), 
+/// arity, padding rule, tree size & proven leaf (only for proofs).
+/// 
+/// Verifier can read only the header (deserialize as `MtEnvelope<serde::de::IgnoredAny>`), 
+/// dispatch on it & then open the envelope with suitable hasher & arity. 
+/// Any mismatch of parameters is reported by [`MtEnvelopeError`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MtEnvelope<Body> {
+    version: u32,
+    algorithm: String,
+    arity: usize,
+    padding: String,
+    leaf_index: Option<usize>,
+    tree_size: usize,
+    body: Body,
+}
+#[cfg(feature = "serde")]
+impl<Body> MtEnvelope<Body> {
+    /// Current version of the envelope format.
+    pub const VERSION: u32 = 1;
+    /// Padding rule of this crate: uneven group is padded by its last node.
+    pub const PADDING: &'static str = "duplicate-last";
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn algorithm(&self) -> &str {
+        &self.algorithm
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn padding(&self) -> &str {
+        &self.padding
+    }
+
+    /// [`None`] for non-proof bodies.
+    pub fn leaf_index(&self) -> Option<LeafId> {
+        self.leaf_index.map(LeafId::new)
+    }
+
+    pub fn tree_size(&self) -> usize {
+        self.tree_size
+    }
+
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    fn new<Hasher: HasherId, const ARITY: usize>(leaf_index: Option<usize>, tree_size: usize, body: Body) -> Self {
+        Self {
+            version: Self::VERSION,
+            algorithm: Hasher::ALGORITHM.to_string(),
+            arity: ARITY,
+            padding: Self::PADDING.to_string(),
+            leaf_index,
+            tree_size,
+            body,
+        }
+    }
+
+    /// Test that header is agree with the verifier's hasher & arity.
+    pub fn check_header<Hasher: HasherId, const ARITY: usize>(&self) -> Result<(), MtEnvelopeError> {
+        if self.version != Self::VERSION {
+            return Err(MtEnvelopeError::UnsupportedVersion { supported: Self::VERSION, actual: self.version })
+        }
+        if self.algorithm != Hasher::ALGORITHM {
+            let expected = Hasher::ALGORITHM.to_string();
+            return Err(MtEnvelopeError::AlgorithmMismatch { expected, actual: self.algorithm.clone() })
+        }
+        if self.arity != ARITY {
+            return Err(MtEnvelopeError::ArityMismatch { expected: ARITY, actual: self.arity })
+        }
+        if self.padding != Self::PADDING {
+            let expected = Self::PADDING.to_string();
+            return Err(MtEnvelopeError::PaddingMismatch { expected, actual: self.padding.clone() })
+        }
+        Ok(())
+    }
+}
+#[cfg(feature = "serde")]
+impl<Hash, const ARITY: usize> MtEnvelope<MtProof<Hash, ARITY>> {
+    /// Wrap the proof made by `Hasher`.
+    /// 
+    /// Proof must contain tree size & valid path.
+    pub fn from_proof<Hasher: HasherId>(proof: MtProof<Hash, ARITY>) -> Result<Self, MtEnvelopeError> {
+        let tree_size = proof.tree_size().ok_or(ProofError::UnknownTreeSize)?;
+        let Some(leaf_index) = proof.leaf_index() else {
+            return Err(MtEnvelopeError::LeafIndexMismatch { header: None, body: None })
+        };
+        Ok(Self::new::<Hasher, ARITY>(Some(leaf_index.index()), tree_size, proof))
+    }
+
+    /// Test the header & that it's agree with the body.
+    fn check_proof<Hasher: HasherId>(&self) -> Result<(), MtEnvelopeError> {
+        self.check_header::<Hasher, ARITY>()?;
+        if let Some(body) = self.body.tree_size() && body != self.tree_size {
+            return Err(MtEnvelopeError::TreeSizeMismatch { header: self.tree_size, body })
+        }
+        let body = self.body.leaf_index().map(LeafId::index);
+        if self.leaf_index.is_none() || body != self.leaf_index {
+            return Err(MtEnvelopeError::LeafIndexMismatch { header: self.leaf_index, body })
+        }
+        Ok(())
+    }
+
+    /// Unwrap the proof if it was made by `Hasher` & has the same arity.
+    pub fn open<Hasher: HasherId>(self) -> Result<MtProof<Hash, ARITY>, MtEnvelopeError> {
+        self.check_proof::<Hasher>()?;
+        Ok(self.body)
+    }
+}
+#[cfg(feature = "serde")]
+impl<Hash: Eq, const ARITY: usize> MtEnvelope<MtProof<Hash, ARITY>> {
+    /// The same as [`Self::verify_detailed`] but without reason of rejection.
+    pub fn verify<Hasher>(&self, trusted_root: &Hash, hash: Hash, hasher: &mut Hasher) -> bool
+    where Hasher: ArityHasher<Hash, ARITY> + HasherId
+    {
+        self.verify_detailed(trusted_root, hash, hasher).is_ok()
+    }
+
+    /// Test the header & verify the proof against trusted root.
+    /// 
+    /// Proof is bound to leaf & tree size of the header (see [`MtProof::verify_position`]), 
+    /// so if they are known by the verifier, it should compare them with [`Self::leaf_index`] & [`Self::tree_size`].
+    pub fn verify_detailed<Hasher>(&self, trusted_root: &Hash, hash: Hash, hasher: &mut Hasher) -> Result<(), MtEnvelopeError>
+    where Hasher: ArityHasher<Hash, ARITY> + HasherId
+    {
+        self.check_proof::<Hasher>()?;
+        // never `None` after the check
+        let id = LeafId::new(self.leaf_index.unwrap_or_default());
+        Ok(self.body.verify_position_detailed(trusted_root, id, self.tree_size, hash, hasher)?)
+    }
+}
+#[cfg(feature = "serde")]
+impl<Hash, const ARITY: usize> MtEnvelope<MtSerde<Hash, ARITY>> {
+    /// Wrap the serializable tree.
+    pub fn from_tree<Hasher, Observer>(tree: &MerkleTree<Hash, Hasher, ARITY, Observer>) -> Self
+    where
+        Hash: Clone,
+        Hasher: ArityHasher<Hash, ARITY> + HasherId,
+    {
+        Self::new::<Hasher, ARITY>(None, tree.leaf_count(), tree.serializable())
+    }
+
+    /// Unwrap serializable tree if it was made by `Hasher` & has the same arity.
+    pub fn open<Hasher: HasherId>(self) -> Result<MtSerde<Hash, ARITY>, MtEnvelopeError> {
+        self.check_header::<Hasher, ARITY>()?;
+        if self.body.leafs.len() != self.tree_size {
+            return Err(MtEnvelopeError::TreeSizeMismatch { header: self.tree_size, body: self.body.leafs.len() })
+        }
+        if self.leaf_index.is_some() {
+            return Err(MtEnvelopeError::LeafIndexMismatch { header: self.leaf_index, body: None })
+        }
+        Ok(self.body)
+    }
+}
+
+// [-] MerkleTree Envelope
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
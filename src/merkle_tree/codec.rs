@@ -0,0 +1,386 @@
+//! Canonical binary encoding of proofs & trees (without serde).
+//! 
+//! All values are encoded as:
+//! ```txt
+//! tag: u8 | arity: varint | <fields of the value>
+//! ```
+//! * varint: unsigned LEB128 in the shortest form
+//! * option: flag `0` (none) or `1` (some) followed by the value
+//! * hash: fixed width bytes, see [`MtHashBytes`]
+//! * sequence: length (as varint) followed by elements, 
+//!   length is omitted if it's known from previous fields
+//! 
+//! Decoding is strict: each value has exactly one valid encoding, 
+//! so non-shortest varints, unknown flags, malformed values & trailing bytes are rejected.
+
+use crate::MtArityHasher as ArityHasher;
+use super::{MerkleTree, MtProof, MtCompactProof, ProofError};
+#[cfg(feature = "serde")]
+use super::MtSerde;
+
+const PROOF_TAG: u8 = 0x01;
+const COMPACT_PROOF_TAG: u8 = 0x02;
+const TREE_TAG: u8 = 0x03;
+
+/// `Mt` stands for `MerkleTree`
+/// 
+/// Fixed width binary representation of a hash.
+pub trait MtHashBytes: Sized {
+    /// Amount of bytes of each encoded hash.
+    const LEN: usize;
+
+    /// Write exactly [`Self::LEN`] bytes.
+    fn write_bytes(&self, out: &mut Vec<u8>);
+
+    /// Read hash from exactly [`Self::LEN`] bytes.
+    /// 
+    /// # Return
+    /// * [`None`] if bytes are not a valid hash
+    fn read_bytes(bytes: &[u8]) -> Option<Self>;
+}
+impl MtHashBytes for u64 {
+    const LEN: usize = 8;
+
+    fn write_bytes(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_be_bytes());
+    }
+    fn read_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(u64::from_be_bytes(bytes.try_into().ok()?))
+    }
+}
+
+/// Reason why bytes can't be decoded.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MtDecodeError {
+    #[error("Unexpected end of input.")]
+    UnexpectedEnd,
+    #[error("There are {0} unexpected bytes after the encoded value.")]
+    TrailingBytes(usize),
+    #[error("Varint is not in the shortest form.")]
+    NonCanonicalVarint,
+    #[error("Varint doesn't fit into `usize`.")]
+    VarintOverflow,
+    #[error("Unexpected tag {actual:#04x}, expected tag is {expected:#04x}.")]
+    WrongTag { expected: u8, actual: u8 },
+    #[error("Invalid option flag {0}. It must be 0 or 1.")]
+    InvalidFlag(u8),
+    #[error("Encoded arity is {actual}, but expected arity is {expected}.")]
+    ArityMismatch { expected: usize, actual: usize },
+    #[error("Invalid bytes of hash.")]
+    InvalidHash,
+    #[error("Root must be present only in non-empty tree.")]
+    RootPresence,
+    #[error("Root is not equal to the root calculated from the leafs. The input was tampered with (or it was made by another hasher).")]
+    WrongRoot,
+    #[error(transparent)]
+    Malformed(#[from] ProofError),
+}
+
+/// Reason why the value can't be encoded.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MtEncodeError {
+    #[error("Value has arity {actual}, but it's encoded with arity {expected}.")]
+    ArityMismatch { expected: usize, actual: usize },
+    #[error("Levels above the leafs can't be encoded, only the leafs & the root are.")]
+    UnsupportedLvls,
+    #[error("Hasher configuration can't be encoded.")]
+    UnsupportedHasherConfig,
+}
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] Primitives
+
+struct Writer {
+    out: Vec<u8>,
+}
+impl Writer {
+    fn new<const ARITY: usize>(tag: u8) -> Self {
+        let mut writer = Self { out: vec![tag] };
+        writer.varint(ARITY);
+        writer
+    }
+
+    fn varint(&mut self, mut x: usize) {
+        while x >= 0x80 {
+            self.out.push((x as u8 & 0x7F) | 0x80);
+            x >>= 7;
+        }
+        self.out.push(x as u8);
+    }
+
+    fn option_varint(&mut self, x: Option<usize>) {
+        match x {
+            Some(x) => {
+                self.out.push(1);
+                self.varint(x);
+            }
+            None => self.out.push(0),
+        }
+    }
+
+    fn hash<Hash: MtHashBytes>(&mut self, hash: &Hash) {
+        hash.write_bytes(&mut self.out);
+    }
+
+    fn hashes<Hash: MtHashBytes>(&mut self, hashes: &[Hash]) {
+        self.out.reserve(hashes.len() * Hash::LEN);
+        hashes.iter().for_each(|hash|self.hash(hash));
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> Reader<'a> {
+    fn new<const ARITY: usize>(bytes: &'a [u8], tag: u8) -> Result<Self, MtDecodeError> {
+        let mut reader = Self { bytes };
+        let actual = reader.byte()?;
+        if actual != tag {
+            return Err(MtDecodeError::WrongTag { expected: tag, actual })
+        }
+        let actual = reader.varint()?;
+        if actual != ARITY {
+            return Err(MtDecodeError::ArityMismatch { expected: ARITY, actual })
+        }
+        Ok(reader)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], MtDecodeError> {
+        if self.bytes.len() < len {
+            return Err(MtDecodeError::UnexpectedEnd)
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, MtDecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<usize, MtDecodeError> {
+        let mut x: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7F) as usize;
+            if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                return Err(MtDecodeError::VarintOverflow)
+            }
+            x |= bits << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                // the last byte can be zero only if it's the only byte
+                if byte == 0 && shift > 7 {
+                    return Err(MtDecodeError::NonCanonicalVarint)
+                }
+                return Ok(x)
+            }
+        }
+    }
+
+    fn option_varint(&mut self) -> Result<Option<usize>, MtDecodeError> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => Ok(Some(self.varint()?)),
+            flag => Err(MtDecodeError::InvalidFlag(flag)),
+        }
+    }
+
+    fn hash<Hash: MtHashBytes>(&mut self) -> Result<Hash, MtDecodeError> {
+        Hash::read_bytes(self.take(Hash::LEN)?).ok_or(MtDecodeError::InvalidHash)
+    }
+
+    fn hashes<Hash: MtHashBytes>(&mut self, len: usize) -> Result<Vec<Hash>, MtDecodeError> {
+        // test len before allocation, so malicious len can't allocate too much
+        let bytes_len = len.checked_mul(Hash::LEN).ok_or(MtDecodeError::UnexpectedEnd)?;
+        let mut bytes = self.take(bytes_len)?;
+        let mut hashes = Vec::with_capacity(len);
+        while !bytes.is_empty() {
+            let (hash, rest) = bytes.split_at(Hash::LEN);
+            hashes.push(Hash::read_bytes(hash).ok_or(MtDecodeError::InvalidHash)?);
+            bytes = rest;
+        }
+        Ok(hashes)
+    }
+
+    fn finish(self) -> Result<(), MtDecodeError> {
+        match self.bytes.len() {
+            0 => Ok(()),
+            len => Err(MtDecodeError::TrailingBytes(len)),
+        }
+    }
+}
+
+// [-] Primitives
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+/// ```txt
+/// 0x01 | arity | lvls | path: [varint; lvls] | tree_size: option | nodes: [hash; lvls * arity] | root: hash
+/// ```
+impl<Hash: MtHashBytes, const ARITY: usize> MtProof<Hash, ARITY> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new::<ARITY>(PROOF_TAG);
+        writer.varint(self.tree_lvl_path.len());
+        self.tree_lvl_path.iter().for_each(|&path_index|writer.varint(path_index));
+        writer.option_varint(self.tree_size);
+        writer.hashes(&self.tree_lvl_nodes);
+        writer.hash(&self.root);
+        writer.out
+    }
+
+    /// Decode the proof encoded by [`Self::to_bytes`].
+    /// 
+    /// Proof with path index out of `ARITY` is rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MtDecodeError> {
+        let mut reader = Reader::new::<ARITY>(bytes, PROOF_TAG)?;
+        let lvls = reader.varint()?;
+        // each path index has at least one byte, so malicious `lvls` can't allocate too much
+        if lvls > reader.bytes.len() {
+            return Err(MtDecodeError::UnexpectedEnd)
+        }
+        let tree_lvl_path = (0..lvls).map(|_|reader.varint()).collect::<Result<Vec<_>, _>>()?;
+        let tree_size = reader.option_varint()?;
+        let nodes_len = lvls.checked_mul(ARITY).ok_or(MtDecodeError::UnexpectedEnd)?;
+        let tree_lvl_nodes = reader.hashes(nodes_len)?;
+        let root = reader.hash()?;
+        reader.finish()?;
+
+        let proof = Self { tree_lvl_nodes, tree_lvl_path, root, tree_size };
+        proof.check_well_formed()?;
+        Ok(proof)
+    }
+}
+
+/// ```txt
+/// 0x02 | arity | lvls | path | padding | tree_size: option | siblings: [hash; siblings_len] | root: hash
+/// ```
+/// `siblings_len` is known from `lvls` & `padding`: each level has `ARITY - 1 - padding_digit` siblings.
+impl<Hash: MtHashBytes, const ARITY: usize> MtCompactProof<Hash, ARITY> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Writer::new::<ARITY>(COMPACT_PROOF_TAG);
+        writer.varint(self.lvls);
+        writer.varint(self.path);
+        writer.varint(self.padding);
+        writer.option_varint(self.tree_size);
+        writer.hashes(&self.siblings);
+        writer.hash(&self.root);
+        writer.out
+    }
+
+    /// Decode the proof encoded by [`Self::to_bytes`].
+    /// 
+    /// Proof whose path, padding & siblings are not agree with each other is rejected.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MtDecodeError> {
+        let mut reader = Reader::new::<ARITY>(bytes, COMPACT_PROOF_TAG)?;
+        let lvls = reader.varint()?;
+        let path = reader.varint()?;
+        let padding = reader.varint()?;
+        let tree_size = reader.option_varint()?;
+        let mut siblings_len = 0usize;
+        let mut lvl_padding = padding;
+        for _ in 0..lvls {
+            siblings_len += ARITY - 1 - lvl_padding % ARITY;
+            lvl_padding /= ARITY;
+            // each level above the padding has siblings, so malicious `lvls` can't loop for too long
+            if siblings_len.saturating_mul(Hash::LEN) > reader.bytes.len() {
+                return Err(MtDecodeError::UnexpectedEnd)
+            }
+        }
+        let siblings = reader.hashes(siblings_len)?;
+        let root = reader.hash()?;
+        reader.finish()?;
+
+        let proof = Self { siblings, path, padding, lvls, root, tree_size };
+        proof.check_well_formed()?;
+        Ok(proof)
+    }
+}
+
+/// ```txt
+/// 0x03 | arity | leafs_len | leafs: [hash; leafs_len] | root: option of hash
+/// ```
+/// Only the leafs & the root are encoded, levels above the leafs are rebuilt on decoding.
+impl<Hash: MtHashBytes, Hasher, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer>
+where Hasher: ArityHasher<Hash, ARITY>
+{
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let root = (!self.is_empty()).then(|| self.root_ref());
+        write_tree::<_, ARITY>(&self.tree_lvls[0], root)
+    }
+}
+impl<Hash: MtHashBytes, Hasher, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY>
+where Hasher: ArityHasher<Hash, ARITY>
+{
+    /// Decode the tree encoded by [`Self::to_bytes`] & test its root.
+    pub fn from_bytes(bytes: &[u8], hasher: Hasher) -> Result<Self, MtDecodeError>
+    where Hash: Eq
+    {
+        let (leafs, root) = read_tree::<_, ARITY>(bytes)?;
+        let tree = Self::new_from_leafs(hasher, leafs);
+        if root.is_some_and(|root|&root != tree.root_ref()) {
+            return Err(MtDecodeError::WrongRoot)
+        }
+        Ok(tree)
+    }
+}
+
+/// The same encoding as of [`MerkleTree::to_bytes`].
+#[cfg(feature = "serde")]
+impl<Hash: MtHashBytes, const ARITY: usize> MtSerde<Hash, ARITY> {
+    /// # Return
+    /// * [`Err`] if `self` was deserialized with arity that is not equal to `ARITY`
+    ///   or it has the levels above the leafs or the hasher configuration
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MtEncodeError> {
+        if self.arity != ARITY {
+            return Err(MtEncodeError::ArityMismatch { expected: ARITY, actual: self.arity })
+        }
+        if self.lvls.is_some() {
+            return Err(MtEncodeError::UnsupportedLvls)
+        }
+        if self.hasher.is_some() {
+            return Err(MtEncodeError::UnsupportedHasherConfig)
+        }
+        Ok(write_tree::<_, ARITY>(&self.leafs, self.root.as_ref()))
+    }
+
+    /// Decode the tree encoded by [`Self::to_bytes`].
+    /// 
+    /// Root is not recalculated here, it's tested by [`Self::to_merkle_tree`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MtDecodeError> {
+        let (leafs, root) = read_tree::<_, ARITY>(bytes)?;
+        Ok(Self { leafs, root, arity: ARITY, lvls: None, hasher: None })
+    }
+}
+
+fn write_tree<Hash: MtHashBytes, const ARITY: usize>(leafs: &[Hash], root: Option<&Hash>) -> Vec<u8> {
+    let mut writer = Writer::new::<ARITY>(TREE_TAG);
+    writer.varint(leafs.len());
+    writer.hashes(leafs);
+    match root {
+        Some(root) => {
+            writer.out.push(1);
+            writer.hash(root);
+        }
+        None => writer.out.push(0),
+    }
+    writer.out
+}
+
+fn read_tree<Hash: MtHashBytes, const ARITY: usize>(bytes: &[u8]) -> Result<(Vec<Hash>, Option<Hash>), MtDecodeError> {
+    let mut reader = Reader::new::<ARITY>(bytes, TREE_TAG)?;
+    let leafs_len = reader.varint()?;
+    let leafs = reader.hashes(leafs_len)?;
+    let root = match reader.byte()? {
+        0 => None,
+        1 => Some(reader.hash()?),
+        flag => return Err(MtDecodeError::InvalidFlag(flag)),
+    };
+    reader.finish()?;
+
+    if leafs.is_empty() == root.is_some() {
+        return Err(MtDecodeError::RootPresence)
+    }
+    Ok((leafs, root))
+}
@@ -0,0 +1,109 @@
+//! [`ConcurrentMerkleTree`]: the tree shared between threads, readers are not blocked by the writer.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use crate::MtArityHasher as ArityHasher;
+use super::{LeafId, MerkleTree, MtJournalError, MtOp};
+
+/// Tree that is written by the writer (one at a time) & is read by any amount of readers in parallel.
+///
+/// Readers get the published snapshot ([`Self::snapshot`]): it's a consistent tree
+/// (proofs from it are valid for its root) that isn't changed while it's held.
+/// Writer applies the batch to the standby copy of the tree & publishes it by an atomic swap
+/// of the pointer, so readers never see a half-updated tree & aren't blocked by the batch.
+///
+/// Two copies are kept (left-right): the previous snapshot becomes the standby copy
+/// and catches up by replay of the previous batch before the next one.
+/// If readers still hold it at that time, it's cloned instead.
+#[derive(Debug)]
+pub struct ConcurrentMerkleTree<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> {
+    /// lock is held only to clone or to swap the pointer
+    published: RwLock<Arc<MerkleTree<Hash, Hasher, ARITY>>>,
+    writer: Mutex<Writer<Hash, Hasher, ARITY>>,
+}
+
+#[derive(Debug)]
+struct Writer<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> {
+    standby: Arc<MerkleTree<Hash, Hasher, ARITY>>,
+    /// ops that are published, but are not applied to `standby` yet
+    pending: Vec<MtOp<Hash>>,
+}
+
+impl<Hash, Hasher, const ARITY: usize> ConcurrentMerkleTree<Hash, Hasher, ARITY>
+where
+    Hash: Clone + Eq,
+    Hasher: Clone + ArityHasher<Hash, ARITY>,
+{
+    pub fn new(tree: MerkleTree<Hash, Hasher, ARITY>) -> Self {
+        let standby = Arc::new(tree.clone());
+        Self {
+            published: RwLock::new(Arc::new(tree)),
+            writer: Mutex::new(Writer { standby, pending: vec![] }),
+        }
+    }
+
+    /// The last published tree, it's never changed.
+    pub fn snapshot(&self) -> Arc<MerkleTree<Hash, Hasher, ARITY>> {
+        self.published.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Apply `ops` (see [`MerkleTree::apply`]) & publish the result.
+    ///
+    /// Writers wait for each other, readers are not blocked.
+    ///
+    /// # Return
+    /// * [`Ok`] of the published tree
+    /// * [`Err`] of the first invalid op, nothing is published then
+    pub fn apply_batch(&self, ops: impl IntoIterator<Item = MtOp<Hash>>) -> Result<Arc<MerkleTree<Hash, Hasher, ARITY>>, MtJournalError> {
+        let mut writer = self.lock_writer();
+        let Writer { standby, pending } = &mut *writer;
+
+        let tree = Arc::make_mut(standby);
+        for op in pending.drain(..) {
+            tree.apply(op).expect("op is already applied to the published tree");
+        }
+        let mut batch = vec![];
+        for op in ops {
+            if let Err(err) = tree.apply(op.clone()) {
+                // standby is partially changed: start from the published tree
+                *standby = self.snapshot();
+                return Err(err)
+            }
+            batch.push(op);
+        }
+
+        let published = standby.clone();
+        let previous = std::mem::replace(&mut *self.published.write().unwrap_or_else(PoisonError::into_inner), published.clone());
+        *standby = previous;
+        *pending = batch;
+        Ok(published)
+    }
+
+    /// [`MerkleTree::push_batched`] by [`Self::apply_batch`].
+    pub fn push_batched(&self, batch: impl IntoIterator<Item = Hash>) -> std::ops::Range<LeafId> {
+        let hashes: Vec<Hash> = batch.into_iter().collect();
+        let count = hashes.len();
+        let tree = self.apply_batch([MtOp::PushBatched(hashes)]).expect("push is always valid");
+        LeafId::new(tree.leaf_count() - count)..LeafId::new(tree.leaf_count())
+    }
+
+    /// The last published tree (it's cloned if readers still hold it).
+    pub fn into_inner(self) -> MerkleTree<Hash, Hasher, ARITY> {
+        let published = self.published.into_inner().unwrap_or_else(PoisonError::into_inner);
+        Arc::unwrap_or_clone(published)
+    }
+
+    /// Writer state is valid after a panic of another writer:
+    /// the standby copy is restored from the published tree.
+    fn lock_writer(&self) -> MutexGuard<'_, Writer<Hash, Hasher, ARITY>> {
+        match self.writer.lock() {
+            Ok(writer) => writer,
+            Err(poisoned) => {
+                let mut writer = poisoned.into_inner();
+                writer.standby = self.snapshot();
+                writer.pending.clear();
+                self.writer.clear_poison();
+                writer
+            }
+        }
+    }
+}
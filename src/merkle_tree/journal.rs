@@ -0,0 +1,285 @@
+//! Replication of the tree by the journal of logical operations.
+//!
+//! Leader changes its tree only through [`MtJournal::record`], so the journal has each operation ([`MtOp`])
+//! with the leaf count & the root after it.
+//! Follower applies the entries by [`MerkleTree::apply_journal`] & checks its root after each entry,
+//! so it gets an identical tree (or an error at the first entry that diverged).
+//!
+//! Entries are numbered by positions: entry `seq` moves the tree from position `seq` to `seq + 1`.
+//! The leader can [truncate](MtJournal::truncate) the journal at any position (checkpoint):
+//! a new follower starts from the tree at the checkpoint (for example [`MtSerde`](crate::MtSerde) dump of it),
+//! checks it by [`MtCheckpoint::check`] & applies the rest of the journal.
+
+use crate::MtArityHasher as ArityHasher;
+use super::{LeafId, MerkleTree, MtObserver};
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Logical operation on [`MerkleTree`], leafs are addressed by indexes.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MtOp<Hash> {
+    /// [`MerkleTree::push`]
+    Push(Hash),
+    /// [`MerkleTree::push_batched`]
+    PushBatched(Vec<Hash>),
+    /// [`MerkleTree::replace`]
+    Replace { index: usize, hash: Hash },
+    /// [`MerkleTree::pop`]
+    Pop,
+    /// [`MerkleTree::swap`]
+    Swap { a: usize, b: usize },
+    /// [`MerkleTree::swap_remove`]
+    SwapRemove(usize),
+    /// [`MerkleTree::merge`] with trees that have the leafs, in order.
+    Merge(Vec<Vec<Hash>>),
+}
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Operation & the state of the tree after it.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtJournalEntry<Hash> {
+    pub op: MtOp<Hash>,
+    pub leaf_count: usize,
+    /// [`None`] for an empty tree
+    pub root: Option<Hash>,
+}
+
+/// `Mt` stands for `MerkleTree`
+///
+/// State of the tree at the position `seq` of the journal.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtCheckpoint<Hash> {
+    pub seq: usize,
+    pub leaf_count: usize,
+    /// [`None`] for an empty tree
+    pub root: Option<Hash>,
+}
+impl<Hash: Eq> MtCheckpoint<Hash> {
+    fn of_tree<Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer>(seq: usize, tree: &MerkleTree<Hash, Hasher, ARITY, Observer>) -> Self
+    where Hash: Clone
+    {
+        Self { seq, leaf_count: tree.leaf_count(), root: (!tree.is_empty()).then(|| tree.root()) }
+    }
+
+    /// # Return
+    /// * [`Ok`] if `tree` is in this state
+    /// * [`Err`] otherwise
+    pub fn check<Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer>(&self, tree: &MerkleTree<Hash, Hasher, ARITY, Observer>) -> Result<(), MtJournalError> {
+        if tree.leaf_count() != self.leaf_count {
+            return Err(MtJournalError::LeafCountMismatch { seq: self.seq, expected: self.leaf_count, actual: tree.leaf_count() })
+        }
+        let root = (!tree.is_empty()).then(|| tree.root_ref());
+        if root != self.root.as_ref() {
+            return Err(MtJournalError::RootMismatch { seq: self.seq })
+        }
+        Ok(())
+    }
+}
+
+/// Reason why the operation can't be applied or the journal diverged from the tree.
+///
+/// The tree is a valid tree after any error, but the operation that failed is not applied.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MtJournalError {
+    #[error("Leaf {index} is out of the tree with {leaf_count} leafs.")]
+    LeafOutOfTree { index: usize, leaf_count: usize },
+    #[error("Journal starts from position {start}, position {seq} is truncated.")]
+    Truncated { seq: usize, start: usize },
+    #[error("Journal ends at position {end}, position {seq} is not recorded yet.")]
+    NotRecorded { seq: usize, end: usize },
+    #[error("At position {seq} the tree must have {expected} leafs, but it has {actual} leafs.")]
+    LeafCountMismatch { seq: usize, expected: usize, actual: usize },
+    #[error("At position {seq} the root of the tree is not equal to the root in the journal.")]
+    RootMismatch { seq: usize },
+    #[error("Journal starts from position {start} & has {len} entries, so its end is out of `usize`.")]
+    PositionOverflow { start: usize, len: usize },
+}
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Journal of the operations on the tree, see [module docs](self).
+/// 
+/// Deserialization rejects the journal whose end is out of `usize` ([`MtJournalError::PositionOverflow`]).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "MtJournalParts<Hash>"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MtJournal<Hash> {
+    /// state at the first position of the journal
+    base: MtCheckpoint<Hash>,
+    entries: Vec<MtJournalEntry<Hash>>,
+}
+/// Fields of [`MtJournal`] before they are tested
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct MtJournalParts<Hash> {
+    base: MtCheckpoint<Hash>,
+    entries: Vec<MtJournalEntry<Hash>>,
+}
+#[cfg(feature = "serde")]
+impl<Hash> TryFrom<MtJournalParts<Hash>> for MtJournal<Hash> {
+    type Error = MtJournalError;
+    fn try_from(MtJournalParts { base, entries }: MtJournalParts<Hash>) -> Result<Self, MtJournalError> {
+        if base.seq.checked_add(entries.len()).is_none() {
+            return Err(MtJournalError::PositionOverflow { start: base.seq, len: entries.len() })
+        }
+        Ok(Self { base, entries })
+    }
+}
+impl<Hash: Clone + Eq> MtJournal<Hash> {
+    /// Empty journal that starts from the current state of `tree` (position `0`).
+    pub fn new<Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer>(tree: &MerkleTree<Hash, Hasher, ARITY, Observer>) -> Self {
+        Self { base: MtCheckpoint::of_tree(0, tree), entries: vec![] }
+    }
+
+    /// Apply `op` to `tree` (see [`MerkleTree::apply`]) & record it.
+    ///
+    /// # Return
+    /// * result of [`MerkleTree::apply`]
+    /// * [`Err`] if `tree` is not in the state of the end of the journal (it was changed outside of the journal)
+    /// * [`Err`] if the end of the journal is `usize::MAX`
+    pub fn record<Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer: MtObserver<Hash>>(
+        &mut self, tree: &mut MerkleTree<Hash, Hasher, ARITY, Observer>, op: MtOp<Hash>
+    ) -> Result<Option<Hash>, MtJournalError> {
+        self.checkpoint().check(tree)?;
+        if self.end() == usize::MAX {
+            return Err(MtJournalError::PositionOverflow { start: self.start(), len: self.len() + 1 })
+        }
+        let result = tree.apply(op.clone())?;
+        let state = MtCheckpoint::of_tree(self.end(), tree);
+        self.entries.push(MtJournalEntry { op, leaf_count: state.leaf_count, root: state.root });
+        Ok(result)
+    }
+
+    /// First position of the journal (entries before it are truncated).
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.base.seq
+    }
+
+    /// Position after the last entry.
+    /// 
+    /// It never overflows: the journal can't have more entries than positions after [`Self::start`].
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.start() + self.entries.len()
+    }
+
+    /// Entries from [`Self::start`] to [`Self::end`].
+    #[inline]
+    pub fn entries(&self) -> &[MtJournalEntry<Hash>] {
+        &self.entries
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// State of the tree at position `seq`.
+    pub fn state_at(&self, seq: usize) -> Result<MtCheckpoint<Hash>, MtJournalError> {
+        if seq < self.start() {
+            return Err(MtJournalError::Truncated { seq, start: self.start() })
+        }
+        if seq > self.end() {
+            return Err(MtJournalError::NotRecorded { seq, end: self.end() })
+        }
+        if seq == self.start() {
+            return Ok(self.base.clone())
+        }
+        let entry = &self.entries[seq - 1 - self.start()];
+        Ok(MtCheckpoint { seq, leaf_count: entry.leaf_count, root: entry.root.clone() })
+    }
+
+    /// State of the tree at [`Self::end`].
+    pub fn checkpoint(&self) -> MtCheckpoint<Hash> {
+        self.state_at(self.end()).expect("end is always in the journal")
+    }
+
+    /// Remove entries before position `seq`, so the journal starts from it.
+    pub fn truncate(&mut self, seq: usize) -> Result<(), MtJournalError> {
+        let base = self.state_at(seq)?;
+        self.entries.drain(..seq - self.start());
+        self.base = base;
+        Ok(())
+    }
+}
+
+impl<Hash: Clone + Eq, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer: MtObserver<Hash>> MerkleTree<Hash, Hasher, ARITY, Observer> {
+    /// Apply logical operation `op`.
+    ///
+    /// [`MtOp::Merge`] is applied by `push_batched` of the leafs of the merged trees:
+    /// the result is the same, but it doesn't need the trees.
+    ///
+    /// # Return
+    /// * `Hash` of the replaced or removed leaf (if there is one)
+    /// * [`Err`] if `op` addresses a leaf out of the tree (the tree is not changed)
+    ///
+    /// # panic
+    /// * never
+    pub fn apply(&mut self, op: MtOp<Hash>) -> Result<Option<Hash>, MtJournalError> {
+        let leaf_id = |index: usize| {
+            if index < self.leaf_count() {
+                Ok(LeafId::new(index))
+            } else {
+                Err(MtJournalError::LeafOutOfTree { index, leaf_count: self.leaf_count() })
+            }
+        };
+        let result = match op {
+            MtOp::Push(hash) => {
+                self.push(hash);
+                None
+            }
+            MtOp::PushBatched(hashes) => {
+                self.push_batched(hashes);
+                None
+            }
+            MtOp::Replace { index, hash } => {
+                let id = leaf_id(index)?;
+                Some(self.replace(hash, id))
+            }
+            MtOp::Pop => self.pop(),
+            MtOp::Swap { a, b } => {
+                let (a, b) = (leaf_id(a)?, leaf_id(b)?);
+                self.swap(a, b);
+                None
+            }
+            MtOp::SwapRemove(index) => {
+                let id = leaf_id(index)?;
+                Some(self.swap_remove(id))
+            }
+            MtOp::Merge(trees) => {
+                for leafs in trees {
+                    self.push_batched(leafs);
+                }
+                None
+            }
+        };
+        Ok(result)
+    }
+
+    /// Apply entries of `journal` from position `seq` & check the tree after each of them,
+    /// see [module docs](self).
+    ///
+    /// # Return
+    /// * [`Ok`] of the position of the tree afterwards ([`MtJournal::end`])
+    /// * [`Err`] if the tree is not in the state of position `seq` or it diverged from the journal
+    ///   (entries before the failed one are applied)
+    pub fn apply_journal(&mut self, journal: &MtJournal<Hash>, seq: usize) -> Result<usize, MtJournalError> {
+        journal.state_at(seq)?.check(self)?;
+        for (offset, entry) in journal.entries()[seq - journal.start()..].iter().enumerate() {
+            self.apply(entry.op.clone())?;
+            let state = MtCheckpoint { seq: seq + offset + 1, leaf_count: entry.leaf_count, root: entry.root.clone() };
+            state.check(self)?;
+        }
+        Ok(journal.end())
+    }
+}
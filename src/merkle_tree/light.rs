@@ -0,0 +1,390 @@
+//! Memory-light tree that keeps only the leafs & the top levels.
+//!
+//! Levels near the leafs take almost all the memory of the internal nodes
+//! (level `lvl` has `pow(ARITY, lvl)` times fewer nodes than the leafs),
+//! but they are needed only for the nodes that are close to the leafs.
+//! So [`MtLightStore`] keeps the leafs & the top `cached_lvls` levels,
+//! & nodes of the other levels are recalculated from the leafs when they are needed.\
+//! [`LightMerkleTree`] is [`StoredMerkleTree`] over this store.
+//!
+//! ```txt
+//! cached_lvls = 2:
+//! lvl 3:              R            <-- kept
+//! lvl 2:       A             B     <-- kept
+//! lvl 1:   a  b  c  d     e  f     <-- recalculated
+//! lvl 0:  .. .. .. ..    .. ..     <-- kept (leafs)
+//! ```
+//!
+//! The node on level `lvl` below the cached levels costs `pow(ARITY, lvl)` hashes,
+//! so the proof (or the replace) costs `O(pow(ARITY, height - cached_lvls))` hashes
+//! & the cached levels take `O(leaf_count / pow(ARITY, height - cached_lvls))` memory.
+
+use std::convert::Infallible;
+use std::ops::Range;
+use crate::MtArityHasher as ArityHasher;
+use super::{hash_lvl, LeafId, MerkleTree, MtNodeStore, MtProof, NodeId, StoredMerkleTree};
+
+/// Result of the operation of [`MtLightStore`] (it never fails).
+fn never_fails<T>(result: Result<T, Infallible>) -> T {
+    match result {
+        Ok(x) => x,
+        Err(never) => match never {},
+    }
+}
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] Light Store
+
+/// `Mt` stands for `MerkleTree`
+///
+/// [`MtNodeStore`] that keeps only the leafs & the top `cached_lvls` levels.\
+/// Writes into the other levels are dropped & their nodes are recalculated from the leafs,
+/// so the store has its own hasher.
+#[derive(Debug, Clone)]
+pub struct MtLightStore<Hash, Hasher, const ARITY: usize> {
+    leafs: Vec<Hash>,
+    /// lengths of all the levels (including the ones that are not kept)
+    lvl_lens: Vec<usize>,
+    /// levels `self.first_lvl()..self.lvl_count()`
+    top_lvls: Vec<Vec<Hash>>,
+    cached_lvls: usize,
+    hasher: Hasher,
+}
+
+impl<Hash, Hasher, const ARITY: usize> MtLightStore<Hash, Hasher, ARITY>
+where
+    Hash: Clone,
+    Hasher: Clone + ArityHasher<Hash, ARITY>,
+{
+    /// # Panic
+    /// * if `cached_lvls == 0` (at least the root level is kept)
+    pub fn new(hasher: Hasher, cached_lvls: usize) -> Self {
+        assert!(cached_lvls > 0, "at least the root level must be cached");
+        Self {
+            leafs: vec![],
+            lvl_lens: vec![0],
+            top_lvls: vec![],
+            cached_lvls,
+            hasher,
+        }
+    }
+
+    #[inline]
+    pub fn cached_lvls(&self) -> usize {
+        self.cached_lvls
+    }
+
+    /// Change amount of the kept levels (it takes `O(leaf_count)` hashes).
+    ///
+    /// # Panic
+    /// * if `cached_lvls == 0`
+    pub fn set_cached_lvls(&mut self, cached_lvls: usize) {
+        assert!(cached_lvls > 0, "at least the root level must be cached");
+        self.cached_lvls = cached_lvls;
+        self.top_lvls = self.calc_lvls(self.first_lvl()..self.lvl_count());
+    }
+
+    /// Amount of the kept internal nodes (without the leafs).
+    pub fn cached_nodes(&self) -> usize {
+        self.top_lvls.iter().map(Vec::len).sum()
+    }
+
+    #[inline]
+    pub fn leafs(&self) -> &[Hash] {
+        &self.leafs
+    }
+
+    /// Lowest kept level (without the leafs).
+    fn first_lvl(&self) -> usize {
+        self.lvl_count().saturating_sub(self.cached_lvls).max(1)
+    }
+
+    /// Levels `lvls` calculated from the leafs.
+    fn calc_lvls(&self, lvls: Range<usize>) -> Vec<Vec<Hash>> {
+        let mut hasher = self.hasher.clone();
+        let mut calculated = Vec::with_capacity(lvls.len());
+        let mut nodes = vec![];
+        for lvl in 1..lvls.end {
+            nodes = hash_lvl::<_, _, ARITY>(&mut hasher, if lvl == 1 { &self.leafs } else { &nodes });
+            if lvl >= lvls.start {
+                calculated.push(nodes.clone());
+            }
+        }
+        calculated
+    }
+}
+
+impl<Hash, Hasher, const ARITY: usize> MtNodeStore<Hash> for MtLightStore<Hash, Hasher, ARITY>
+where
+    Hash: Clone,
+    Hasher: Clone + ArityHasher<Hash, ARITY>,
+{
+    type Error = Infallible;
+
+    fn lvl_count(&self) -> usize {
+        self.lvl_lens.len()
+    }
+    fn lvl_len(&self, lvl: usize) -> usize {
+        self.lvl_lens.get(lvl).copied().unwrap_or(0)
+    }
+    fn get(&self, node: NodeId) -> Result<Hash, Self::Error> {
+        // never panic: the range contains one node
+        Ok(self.get_range(node.lvl, node.index..node.index + 1)?.pop().unwrap())
+    }
+    /// Nodes of the kept levels are taken as is, others are recalculated from the leafs
+    /// (node of the level `lvl` costs `pow(ARITY, lvl)` hashes).
+    fn get_range(&self, lvl: usize, range: Range<usize>) -> Result<Vec<Hash>, Self::Error> {
+        assert!(range.end <= self.lvl_len(lvl), "nodes must be in the level");
+        if let Some(tree_lvl) = self.lvl_slice(lvl) {
+            return Ok(tree_lvl[range].to_vec())
+        }
+        let span = ARITY.pow(lvl as u32);
+        let mut hasher = self.hasher.clone();
+        let mut nodes = hash_lvl::<_, _, ARITY>(&mut hasher, &self.leafs[range.start * span..(range.end * span).min(self.leafs.len())]);
+        for _ in 1..lvl {
+            nodes = hash_lvl::<_, _, ARITY>(&mut hasher, &nodes);
+        }
+        Ok(nodes)
+    }
+    fn lvl_slice(&self, lvl: usize) -> Option<&[Hash]> {
+        if lvl == 0 {
+            return Some(&self.leafs)
+        }
+        let first_lvl = self.first_lvl();
+        (lvl >= first_lvl).then(|| self.top_lvls.get(lvl - first_lvl).map(Vec::as_slice)).flatten()
+    }
+    fn put_batch(&mut self, lvl: usize, start: usize, hashes: Vec<Hash>) -> Result<(), Self::Error> {
+        if lvl == self.lvl_count() {
+            let first_lvl = self.first_lvl();
+            self.lvl_lens.push(0);
+            self.top_lvls.push(vec![]);
+            // the lowest kept level isn't kept anymore
+            self.top_lvls.drain(..self.first_lvl() - first_lvl);
+        }
+        assert!(start <= self.lvl_lens[lvl], "nodes must be written without gaps");
+        self.lvl_lens[lvl] = self.lvl_lens[lvl].max(start + hashes.len());
+
+        let first_lvl = self.first_lvl();
+        let tree_lvl = match lvl {
+            0 => &mut self.leafs,
+            _ if lvl >= first_lvl => &mut self.top_lvls[lvl - first_lvl],
+            _ => return Ok(()),
+        };
+        let end = (start + hashes.len()).min(tree_lvl.len());
+        tree_lvl.splice(start..end, hashes);
+        Ok(())
+    }
+    fn truncate(&mut self, lvl: usize, len: usize) -> Result<(), Self::Error> {
+        self.lvl_lens[lvl] = self.lvl_lens[lvl].min(len);
+        if lvl == 0 {
+            self.leafs.truncate(len);
+        } else if lvl >= self.first_lvl() {
+            let first_lvl = self.first_lvl();
+            self.top_lvls[lvl - first_lvl].truncate(len);
+        }
+        Ok(())
+    }
+    /// Levels that become kept are recalculated from the leafs (it takes `O(leaf_count)` hashes).
+    fn truncate_lvls(&mut self, lvl_count: usize) -> Result<(), Self::Error> {
+        let lvl_count = lvl_count.max(1);
+        if lvl_count >= self.lvl_count() { return Ok(()) }
+
+        let first_lvl = self.first_lvl();
+        self.lvl_lens.truncate(lvl_count);
+        self.top_lvls.truncate(lvl_count.saturating_sub(first_lvl));
+        let calculated = self.calc_lvls(self.first_lvl()..first_lvl.min(lvl_count));
+        self.top_lvls.splice(0..0, calculated);
+        Ok(())
+    }
+}
+
+// [-] Light Store
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] Light Merkle Tree
+
+/// Merkle tree that keeps only the leafs & the top `cached_lvls` levels, see [module docs](self).\
+/// Nodes, roots & proofs are the same as the ones of [`MerkleTree`] with the same leafs.
+///
+/// It's [`StoredMerkleTree`] over [`MtLightStore`], that never fails.
+#[derive(Debug, Clone)]
+pub struct LightMerkleTree<Hash, Hasher, const ARITY: usize> {
+    tree: StoredMerkleTree<Hash, Hasher, MtLightStore<Hash, Hasher, ARITY>, ARITY>,
+}
+
+impl<Hash, Hasher, const ARITY: usize> LightMerkleTree<Hash, Hasher, ARITY>
+where
+    Hash: Clone,
+    Hasher: Clone + ArityHasher<Hash, ARITY>,
+{
+    pub const ARITY: usize = ARITY;
+
+    /// # Panic
+    /// * if `cached_lvls == 0` (at least the root level is kept)
+    pub fn new(hasher: Hasher, cached_lvls: usize) -> Self {
+        let store = MtLightStore::new(hasher.clone(), cached_lvls);
+        // never panic: the store is empty
+        let tree = StoredMerkleTree::new(hasher, store).expect("empty store has valid shape");
+        Self { tree }
+    }
+
+    /// # Panic
+    /// * if `cached_lvls == 0`
+    pub fn from_leafs(hasher: Hasher, cached_lvls: usize, leafs: impl IntoIterator<Item = Hash>) -> Self {
+        let mut tree = Self::new(hasher, cached_lvls);
+        tree.push_batched(leafs);
+        tree
+    }
+
+    /// Drop the lower levels of the `tree` (without rehashing).
+    ///
+    /// # Panic
+    /// * if `cached_lvls == 0`
+    pub fn from_merkle_tree(tree: MerkleTree<Hash, Hasher, ARITY>, cached_lvls: usize) -> Self {
+        let store = MtLightStore::new(tree.hasher.as_ref().clone(), cached_lvls);
+        // never panic: levels of `MerkleTree` have valid shape
+        let tree = StoredMerkleTree::from_merkle_tree(tree, store).expect("`MerkleTree` has valid shape");
+        Self { tree }
+    }
+
+    /// Calculate all the levels (it takes `O(leaf_count)` hashes).
+    pub fn to_merkle_tree(self) -> MerkleTree<Hash, Hasher, ARITY> {
+        let hasher = self.tree.hasher().clone();
+        MerkleTree::new_from_leafs(hasher, self.tree.into_store().leafs)
+    }
+
+    /// The underlying tree.
+    #[inline]
+    pub fn stored(&self) -> &StoredMerkleTree<Hash, Hasher, MtLightStore<Hash, Hasher, ARITY>, ARITY> {
+        &self.tree
+    }
+
+    #[inline]
+    pub fn cached_lvls(&self) -> usize {
+        self.tree.store().cached_lvls()
+    }
+
+    /// See [`MtLightStore::set_cached_lvls`]
+    ///
+    /// # Panic
+    /// * if `cached_lvls == 0`
+    pub fn set_cached_lvls(&mut self, cached_lvls: usize) {
+        self.tree.store_mut().set_cached_lvls(cached_lvls);
+    }
+
+    /// Amount of the kept internal nodes (without the leafs).
+    pub fn cached_nodes(&self) -> usize {
+        self.tree.store().cached_nodes()
+    }
+
+    #[inline]
+    pub fn hasher(&self) -> &Hasher {
+        self.tree.hasher()
+    }
+    #[inline]
+    pub fn leafs(&self) -> &[Hash] {
+        self.tree.store().leafs()
+    }
+    #[inline]
+    pub fn leaf_count(&self) -> usize {
+        self.tree.leaf_count()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.tree.height()
+    }
+    #[inline]
+    pub fn is_valid_leaf_id(&self, id: LeafId) -> bool {
+        self.tree.is_valid_leaf_id(id)
+    }
+
+    /// # panic
+    /// * if `self.is_empty()`
+    pub fn root_ref(&self) -> &Hash {
+        let store = self.tree.store();
+        // never panic: the root level is always kept
+        &store.lvl_slice(store.lvl_count() - 1).unwrap()[0]
+    }
+    /// # panic
+    /// * if `self.is_empty()`
+    #[inline]
+    pub fn root(&self) -> Hash {
+        self.root_ref().clone()
+    }
+
+    /// Nodes of the kept levels are taken as is, others are recalculated from the leafs.
+    ///
+    /// # Panic
+    /// * if `node_id` is not in the tree
+    pub fn get_node(&self, node_id: NodeId) -> Hash {
+        assert!(node_id.lvl < self.height(), "invalid `node_id`");
+        never_fails(self.tree.get_node(node_id))
+    }
+
+    pub fn push(&mut self, hash: Hash) -> LeafId {
+        never_fails(self.tree.push(hash))
+    }
+
+    /// Add batch of leafs, it's faster than many single pushes.
+    pub fn push_batched(&mut self, batch: impl IntoIterator<Item = Hash>) -> Range<LeafId> {
+        never_fails(self.tree.push_batched(batch))
+    }
+
+    /// # Panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    ///
+    /// # Return
+    /// `Hash` of removed leaf
+    pub fn replace(&mut self, hash: Hash, id: LeafId) -> Hash {
+        never_fails(self.tree.replace(hash, id))
+    }
+
+    /// See [`StoredMerkleTree::replace_batched`]
+    ///
+    /// # panic
+    /// * if `start_id` > `last_leaf_id`
+    pub fn replace_batched(&mut self, batch: impl IntoIterator<Item = Hash>, start_id: LeafId) -> Range<LeafId> {
+        never_fails(self.tree.replace_batched(batch, start_id))
+    }
+
+    pub fn pop(&mut self) -> Option<Hash> {
+        never_fails(self.tree.pop())
+    }
+
+    /// # Return
+    /// `Hash` of removed leaf
+    ///
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn swap_remove(&mut self, id: LeafId) -> Hash {
+        never_fails(self.tree.swap_remove(id))
+    }
+
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id_a \ id_b)`
+    pub fn swap(&mut self, id_a: LeafId, id_b: LeafId) {
+        never_fails(self.tree.swap(id_a, id_b))
+    }
+
+    /// See [`StoredMerkleTree::merge`]
+    pub fn merge<Other>(&mut self, iter: impl IntoIterator<Item = MerkleTree<Hash, Hasher, ARITY, Other>>) {
+        never_fails(self.tree.merge(iter))
+    }
+
+    /// Groups below the kept levels are recalculated from the leafs
+    /// (group of the level `lvl` takes `pow(ARITY, lvl + 1)` hashes).
+    ///
+    /// # Panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn proof_owned(&self, id: LeafId) -> MtProof<Hash, ARITY> {
+        never_fails(self.tree.proof_owned(id))
+    }
+}
+
+// [-] Light Merkle Tree
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
@@ -0,0 +1,467 @@
+//! Crash-safe append-only log of hashes on top of [`MerkleTree`], see [`MerkleLog`].
+//!
+//! Directory of the log contains:
+//! * `snapshot.bin`: all the leafs at the moment of the last compaction
+//! * `wal.bin`: write-ahead journal of the batches that were appended after the snapshot
+//!
+//! ```txt
+//! snapshot: magic: b"MTLOGSNP" | arity | hash width | leaf count | leafs: [hash; leaf count] | root: hash (if leaf count > 0)
+//! journal:  magic: b"MTLOGWAL" | arity | hash width | frames
+//! frame:    len | records
+//! record:   start | count | leafs: [hash; count] | root: hash
+//! ```
+//! * all integers are `u64` little endian, hashes are written by [`MtHashBytes`]
+//! * frame has the records of one write, `len` is their size in bytes (`len > 0`)
+//! * `start` is the index of the first leaf of the record (`count > 0`)
+//! * `root` is the root of the tree after the record
+//!
+//! Appended batches are written into the journal as one frame & `fsync`ed together (see [`MtLogConfig::sync_batch`]),
+//! only synced batches survive a crash.\
+//! On open the snapshot is loaded & the journal is replayed: the root of the snapshot & the root of each record
+//! are compared with the recalculated ones. The next frame is written only after the previous one is synced,
+//! so only the last frame can be written partially (a crash in the middle of the write): any of its records
+//! can be torn, so the whole frame is cut off if any of them is invalid (or the frame header is),
+//! any other mismatch is an error.\
+//! Compaction writes a new snapshot into a temporary file & renames it over the old one,
+//! so a crash at any moment leaves either the old or the new snapshot. Records that are already
+//! in the snapshot (a crash between the rename & the journal reset) are skipped on replay.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use crate::MtArityHasher as ArityHasher;
+use super::{LeafId, MerkleTree, MtConsistencyProof, MtHashBytes, MtProof};
+
+const SNAPSHOT_MAGIC: &[u8; 8] = b"MTLOGSNP";
+const JOURNAL_MAGIC: &[u8; 8] = b"MTLOGWAL";
+const SNAPSHOT_FILE: &str = "snapshot.bin";
+const SNAPSHOT_TMP_FILE: &str = "snapshot.tmp";
+const JOURNAL_FILE: &str = "wal.bin";
+/// magic, arity & hash width
+const HEADER_LEN: usize = 24;
+
+/// Reason why the log can't be opened or written.
+#[derive(thiserror::Error, Debug)]
+pub enum MtLogError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("File {0} is not a part of a merkle log (wrong magic).")]
+    WrongMagic(&'static str),
+    #[error("Log is made for the tree with arity {actual}, but arity {expected} is expected.")]
+    ArityMismatch { expected: usize, actual: usize },
+    #[error("Log has hashes of {actual} bytes, but hash has {expected} bytes.")]
+    HashWidthMismatch { expected: usize, actual: usize },
+    #[error("Snapshot is malformed: {0}.")]
+    MalformedSnapshot(&'static str),
+    #[error("Invalid bytes of hash.")]
+    InvalidHash,
+    #[error("Root of the snapshot is not equal to the root of its leafs.")]
+    SnapshotRootMismatch,
+    #[error("Journal record starts from leaf {start}, but the log has {len} leafs.")]
+    JournalGap { start: usize, len: usize },
+    #[error("Root of the journal record of leafs {start}..{end} is not equal to the recalculated root.")]
+    JournalRootMismatch { start: usize, end: usize },
+    #[error("Journal is malformed: {0}.")]
+    MalformedJournal(&'static str),
+}
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Durability & compaction settings of [`MerkleLog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MtLogConfig {
+    /// The journal is synced when at least `sync_batch` leafs were appended after the last sync.\
+    /// `1` makes each append durable, bigger values share one `fsync` between many appends
+    /// (appends that are not synced yet are lost on crash).
+    pub sync_batch: usize,
+    /// The log is compacted into the snapshot on sync when the journal has at least `compact_after` leafs.\
+    /// [`None`] means that the log is compacted only by [`MerkleLog::compact`].
+    pub compact_after: Option<usize>,
+}
+impl MtLogConfig {
+    pub const DEFAULT_SYNC_BATCH: usize = 1;
+    pub const DEFAULT_COMPACT_AFTER: usize = 1 << 20;
+}
+impl Default for MtLogConfig {
+    fn default() -> Self {
+        Self {
+            sync_batch: Self::DEFAULT_SYNC_BATCH,
+            compact_after: Some(Self::DEFAULT_COMPACT_AFTER),
+        }
+    }
+}
+
+fn put_u64(out: &mut Vec<u8>, x: usize) {
+    out.extend_from_slice(&(x as u64).to_le_bytes());
+}
+
+/// Set `len` of the frame that has a placeholder of it.
+fn seal_frame(frame: &mut [u8]) {
+    let len = frame.len() - 8;
+    frame[..8].copy_from_slice(&(len as u64).to_le_bytes());
+}
+
+fn header<Hash: MtHashBytes, const ARITY: usize>(magic: &[u8; 8]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(magic);
+    put_u64(&mut header, ARITY);
+    put_u64(&mut header, Hash::LEN);
+    header
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len { return None }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn u64(&mut self) -> Option<usize> {
+        let bytes = self.take(8)?.try_into().ok()?;
+        usize::try_from(u64::from_le_bytes(bytes)).ok()
+    }
+
+    /// # panic
+    /// * if there are less than `Hash::LEN` bytes
+    fn hash<Hash: MtHashBytes>(&mut self) -> Result<Hash, MtLogError> {
+        Hash::read_bytes(self.take(Hash::LEN).unwrap()).ok_or(MtLogError::InvalidHash)
+    }
+
+    fn check_header<Hash: MtHashBytes, const ARITY: usize>(&mut self, magic: &[u8; 8], file: &'static str) -> Result<(), MtLogError> {
+        let read_magic = self.take(magic.len()).ok_or(MtLogError::WrongMagic(file))?;
+        if read_magic != magic {
+            return Err(MtLogError::WrongMagic(file))
+        }
+        let arity = self.u64().ok_or(MtLogError::WrongMagic(file))?;
+        if arity != ARITY {
+            return Err(MtLogError::ArityMismatch { expected: ARITY, actual: arity })
+        }
+        let width = self.u64().ok_or(MtLogError::WrongMagic(file))?;
+        if width != Hash::LEN {
+            return Err(MtLogError::HashWidthMismatch { expected: Hash::LEN, actual: width })
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Append-only merkle tree that is persisted in a directory, see [module docs](self).
+///
+/// It serves inclusion proofs ([`Self::inclusion_proof`]) & consistency proofs between
+/// its previous & current roots ([`Self::consistency_proof`]).
+/// Publish roots only after [`Self::sync`], otherwise they can be lost on crash.
+///
+/// Appends that are not synced yet are written (but not compacted) on drop.
+pub struct MerkleLog<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> {
+    dir: PathBuf,
+    config: MtLogConfig,
+    tree: MerkleTree<Hash, Hasher, ARITY>,
+    journal: File,
+    journal_len: u64,
+    /// frame of the records that are not written into the journal yet (`len` is set on write)
+    pending: Vec<u8>,
+    /// amount of leafs that are synced
+    synced: usize,
+    /// amount of leafs in the snapshot
+    snapshot_len: usize,
+}
+
+impl<Hash, Hasher, const ARITY: usize> MerkleLog<Hash, Hasher, ARITY>
+where
+    Hash: MtHashBytes + Clone + Eq,
+    Hasher: ArityHasher<Hash, ARITY>,
+{
+    /// Open the log in the directory `dir` (it's created if it doesn't exist) & recover it.
+    ///
+    /// # panic
+    /// * if `config.sync_batch == 0`
+    pub fn open(dir: impl AsRef<Path>, hasher: Hasher, config: MtLogConfig) -> Result<Self, MtLogError> {
+        assert!(config.sync_batch > 0, "`sync_batch` must be positive");
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        // the compaction was interrupted before the rename: the old snapshot is still valid
+        match fs::remove_file(dir.join(SNAPSHOT_TMP_FILE)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+
+        let tree = match fs::read(dir.join(SNAPSHOT_FILE)) {
+            Ok(bytes) => Self::read_snapshot(&bytes, hasher)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => MerkleTree::new_minimal(hasher),
+            Err(err) => return Err(err.into()),
+        };
+        let snapshot_len = tree.leaf_count();
+
+        let mut journal = OpenOptions::new().read(true).append(true).create(true).open(dir.join(JOURNAL_FILE))?;
+        let mut bytes = vec![];
+        journal.read_to_end(&mut bytes)?;
+        let mut log = Self {
+            dir,
+            config,
+            tree,
+            journal,
+            journal_len: 0,
+            pending: vec![],
+            synced: 0,
+            snapshot_len,
+        };
+
+        if bytes.len() < HEADER_LEN {
+            // the journal was just created (or its creation was interrupted)
+            log.journal.set_len(0)?;
+            log.journal.write_all(&header::<Hash, ARITY>(JOURNAL_MAGIC))?;
+            log.journal.sync_all()?;
+            sync_dir(&log.dir)?;
+            log.journal_len = HEADER_LEN as u64;
+        } else {
+            let valid_len = log.replay(&bytes)?;
+            if valid_len != bytes.len() {
+                log.journal.set_len(valid_len as u64)?;
+                log.journal.sync_data()?;
+            }
+            log.journal_len = valid_len as u64;
+        }
+        log.synced = log.tree.leaf_count();
+        Ok(log)
+    }
+
+    fn read_snapshot(bytes: &[u8], hasher: Hasher) -> Result<MerkleTree<Hash, Hasher, ARITY>, MtLogError> {
+        let mut reader = Reader { bytes };
+        reader.check_header::<Hash, ARITY>(SNAPSHOT_MAGIC, SNAPSHOT_FILE)?;
+        let len = reader.u64().ok_or(MtLogError::MalformedSnapshot("no leaf count"))?;
+        let expected = (len + (len > 0) as usize).checked_mul(Hash::LEN);
+        if expected != Some(reader.bytes.len()) {
+            return Err(MtLogError::MalformedSnapshot("size doesn't match leaf count"))
+        }
+
+        let leafs = (0..len).map(|_| reader.hash()).collect::<Result<Vec<Hash>, _>>()?;
+        let tree = MerkleTree::new_from_leafs(hasher, leafs);
+        if len > 0 && &reader.hash::<Hash>()? != tree.root_ref() {
+            return Err(MtLogError::SnapshotRootMismatch)
+        }
+        Ok(tree)
+    }
+
+    /// Apply frames of the journal.
+    ///
+    /// # Return
+    /// Length of the valid part of the journal (without the partially written last frame).
+    fn replay(&mut self, bytes: &[u8]) -> Result<usize, MtLogError> {
+        let mut reader = Reader { bytes };
+        reader.check_header::<Hash, ARITY>(JOURNAL_MAGIC, JOURNAL_FILE)?;
+
+        loop {
+            let valid_len = bytes.len() - reader.bytes.len();
+            if reader.bytes.is_empty() {
+                return Ok(valid_len)
+            }
+            // torn header of the last frame
+            let Some(frame) = reader.u64().filter(|&len| len > 0).and_then(|len| reader.take(len)) else {
+                return Ok(valid_len)
+            };
+            let len = self.tree.leaf_count();
+            match self.replay_frame(frame) {
+                Ok(()) => {}
+                Err(_) if reader.bytes.is_empty() => {
+                    // torn write of the last frame: its records are written together, so all of them are cut off
+                    (len..self.tree.leaf_count()).for_each(|_| { self.tree.pop(); });
+                    return Ok(valid_len)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Apply records of the frame.
+    fn replay_frame(&mut self, frame: &[u8]) -> Result<(), MtLogError> {
+        let mut reader = Reader { bytes: frame };
+        while !reader.bytes.is_empty() {
+            let record = reader.u64().zip(reader.u64()).filter(|&(_, count)| count > 0);
+            let (start, count) = record.ok_or(MtLogError::MalformedJournal("invalid record header"))?;
+            let record_len = count.checked_add(1).and_then(|hashes| hashes.checked_mul(Hash::LEN));
+            if record_len.is_none_or(|record_len| record_len > reader.bytes.len()) {
+                return Err(MtLogError::MalformedJournal("record doesn't fit into its frame"))
+            }
+            let leafs = (0..count).map(|_| reader.hash()).collect::<Result<Vec<Hash>, _>>()?;
+            let root = reader.hash::<Hash>()?;
+
+            let (len, end) = (self.tree.leaf_count(), start.saturating_add(count));
+            if end <= self.snapshot_len {
+                // the record is already in the snapshot
+                continue
+            }
+            if start != len {
+                return Err(MtLogError::JournalGap { start, len })
+            }
+            self.tree.push_batched(leafs);
+            if self.tree.root_ref() != &root {
+                return Err(MtLogError::JournalRootMismatch { start, end })
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+    #[inline]
+    pub fn config(&self) -> &MtLogConfig {
+        &self.config
+    }
+    #[inline]
+    pub fn tree(&self) -> &MerkleTree<Hash, Hasher, ARITY> {
+        &self.tree
+    }
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tree.leaf_count()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+    /// Amount of leafs that survive a crash.
+    #[inline]
+    pub fn synced_len(&self) -> usize {
+        self.synced
+    }
+    /// Amount of leafs in the snapshot (the rest of the leafs are in the journal).
+    #[inline]
+    pub fn snapshot_len(&self) -> usize {
+        self.snapshot_len
+    }
+
+    /// # panic
+    /// * if `self.is_empty()`
+    #[inline]
+    pub fn root(&self) -> Hash {
+        self.tree.root()
+    }
+
+    pub fn append(&mut self, hash: Hash) -> Result<LeafId, MtLogError> {
+        Ok(self.append_batch(std::iter::once(hash))?.start)
+    }
+
+    /// Append batch of leafs as a single journal record.
+    ///
+    /// The journal is synced if there are at least [`MtLogConfig::sync_batch`] unsynced leafs.
+    pub fn append_batch(&mut self, batch: impl IntoIterator<Item = Hash>) -> Result<Range<LeafId>, MtLogError> {
+        let range = self.tree.push_batched(batch);
+        let (start, end) = (range.start.index(), range.end.index());
+        if start == end {
+            return Ok(range)
+        }
+
+        if self.pending.is_empty() {
+            // frame len
+            put_u64(&mut self.pending, 0);
+        }
+        put_u64(&mut self.pending, start);
+        put_u64(&mut self.pending, end - start);
+        self.pending.reserve((end - start + 1) * Hash::LEN);
+        for hash in &self.tree.tree_lvls[0][start..end] {
+            hash.write_bytes(&mut self.pending);
+        }
+        self.tree.root_ref().write_bytes(&mut self.pending);
+
+        if self.len() - self.synced >= self.config.sync_batch {
+            self.sync()?;
+        }
+        Ok(range)
+    }
+
+    /// Write the pending frame into the journal & `fsync` it.
+    fn write_pending(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(())
+        }
+        seal_frame(&mut self.pending);
+        let written = self.journal.write_all(&self.pending).and_then(|()| self.journal.sync_data());
+        if let Err(err) = written {
+            // drop the partially written frame, it's written again by the next sync
+            let _ = self.journal.set_len(self.journal_len);
+            return Err(err)
+        }
+        self.journal_len += self.pending.len() as u64;
+        self.pending.clear();
+        self.synced = self.len();
+        Ok(())
+    }
+
+    /// Make all the appended leafs durable (& compact the log, see [`MtLogConfig::compact_after`]).
+    pub fn sync(&mut self) -> Result<(), MtLogError> {
+        self.write_pending()?;
+        if let Some(compact_after) = self.config.compact_after
+            && self.len() - self.snapshot_len >= compact_after
+        {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Write all the leafs into the snapshot & clear the journal.
+    pub fn compact(&mut self) -> Result<(), MtLogError> {
+        self.write_pending()?;
+
+        let tmp_path = self.dir.join(SNAPSHOT_TMP_FILE);
+        let mut out = BufWriter::new(File::create(&tmp_path)?);
+        let mut bytes = header::<Hash, ARITY>(SNAPSHOT_MAGIC);
+        put_u64(&mut bytes, self.len());
+        out.write_all(&bytes)?;
+        for hash in self.tree.tree_lvls[0].iter().chain((!self.is_empty()).then(|| self.tree.root_ref())) {
+            bytes.clear();
+            hash.write_bytes(&mut bytes);
+            out.write_all(&bytes)?;
+        }
+        out.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+        fs::rename(&tmp_path, self.dir.join(SNAPSHOT_FILE))?;
+        sync_dir(&self.dir)?;
+        self.snapshot_len = self.len();
+
+        self.journal.set_len(HEADER_LEN as u64)?;
+        self.journal.sync_data()?;
+        self.journal_len = HEADER_LEN as u64;
+        Ok(())
+    }
+
+    /// Proof of leaf `id` in the current tree.
+    ///
+    /// # panic
+    /// * if `id` is not in the log
+    pub fn inclusion_proof(&self, id: LeafId) -> MtProof<Hash, ARITY> {
+        self.tree.proof_owned(id)
+    }
+
+    /// Proof that the log with `old_size` leafs is a prefix of the current log, see [`MtConsistencyProof`].
+    ///
+    /// # panic
+    /// * if `old_size == 0`
+    /// * if `old_size > self.len()`
+    pub fn consistency_proof(&self, old_size: usize) -> MtConsistencyProof<Hash, ARITY> {
+        self.tree.consistency_proof(old_size)
+    }
+}
+
+impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize> Drop for MerkleLog<Hash, Hasher, ARITY> {
+    fn drop(&mut self) {
+        if !self.pending.is_empty() {
+            // best effort: errors can't be reported from drop, call `sync` to get them
+            seal_frame(&mut self.pending);
+            let _ = self.journal.write_all(&self.pending).and_then(|()| self.journal.sync_data());
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Zero-copy memory-mapped layout of the tree.
+//!
+//! ```txt
+//! offset | size  | field
+//! -------|-------|-----------------------------------------------------------
+//! 0      | 8     | magic: b"MRKLTREE"
+//! 8      | 4     | version: u32 = 1
+//! 12     | 1     | byte order of hashes: 0 (little endian) or 1 (big endian)
+//! 13     | 3     | reserved: zeros
+//! 16     | 8     | arity
+//! 24     | 8     | hash width: `size_of::<Hash>()`
+//! 32     | 8     | leaf count
+//! 40     | 8     | level count: `n`
+//! 48     | 8 * n | level offsets (from the start of the file)
+//! ...    |       | levels from the leafs to the root: [hash; len of the level]
+//! ```
+//! * all header integers are little endian
+//! * each level starts at offset that is a multiple of 8 (gaps are filled by zeros)
+//! * length of level `lvl` is not stored: it's `leaf count` divided by `arity`
+//!   (rounding up) `lvl` times, the root level has one node (empty tree has only empty level of leafs)
+//! * hashes are stored in their in-memory representation (see [`MtHashPod`]),
+//!   so the file can be opened only on the machine with the same byte order
+//!
+//! Hashes are read in place, so the file isn't tested to be a valid tree on open,
+//! use [`MappedMerkleTree::audit`] for it.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+use memmap2::Mmap;
+use crate::MtArityHasher as ArityHasher;
+use super::{audit_lvls, lvls_proof_ref_with};
+use super::{LeafId, MerkleTree, MtCompactProof, MtDetachedProof, MtProof, MtProofRef, NodeId};
+
+const MAGIC: &[u8; 8] = b"MRKLTREE";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 48;
+const LVL_ALIGN: usize = 8;
+
+#[cfg(target_endian = "little")]
+const BYTE_ORDER: u8 = 0;
+#[cfg(target_endian = "big")]
+const BYTE_ORDER: u8 = 1;
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Hash that is plain bytes, so it can be read in place from [`MappedMerkleTree`].
+///
+/// # Safety
+/// * any `size_of::<Self>()` bytes are a valid value of the type
+/// * the type has no padding, no pointers & no drop glue
+/// * `align_of::<Self>()` is at most 8
+pub unsafe trait MtHashPod: Sized {}
+unsafe impl MtHashPod for u64 {}
+unsafe impl<const N: usize> MtHashPod for [u8; N] {}
+
+/// Reason why a file can't be opened as [`MappedMerkleTree`].
+#[derive(thiserror::Error, Debug)]
+pub enum MtMapError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("File is not a mapped merkle tree (wrong magic).")]
+    WrongMagic,
+    #[error("Unsupported layout version {actual}. Supported version is {supported}.")]
+    UnsupportedVersion { supported: u32, actual: u32 },
+    #[error("Hashes of the file are stored with another byte order.")]
+    ByteOrderMismatch,
+    #[error("File is made for the tree with arity {actual}, but arity {expected} is expected.")]
+    ArityMismatch { expected: usize, actual: usize },
+    #[error("File has hashes of {actual} bytes, but hash has {expected} bytes.")]
+    HashWidthMismatch { expected: usize, actual: usize },
+    #[error("File layout is malformed: {0}.")]
+    Malformed(&'static str),
+}
+
+/// Lengths of the levels of the tree with `leaf_count` leafs.
+fn lvl_lens<const ARITY: usize>(leaf_count: usize) -> Vec<usize> {
+    let mut lens = vec![leaf_count];
+    let mut len = leaf_count;
+    while len > 1 {
+        len = len.div_ceil(ARITY);
+        lens.push(len);
+    }
+    lens
+}
+
+fn lvl_bytes<Hash: MtHashPod>(lvl: &[Hash]) -> &[u8] {
+    // SAFETY: `MtHashPod` has no padding, so all bytes of the slice are initialized
+    unsafe { std::slice::from_raw_parts(lvl.as_ptr().cast::<u8>(), std::mem::size_of_val(lvl)) }
+}
+
+impl<Hash: MtHashPod, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
+    /// Dump the tree into the layout of [`MappedMerkleTree`] (see [module docs](self)).
+    pub fn write_mapped(&self, mut out: impl Write) -> io::Result<()> {
+        let lens = lvl_lens::<ARITY>(self.leaf_count());
+        let width = size_of::<Hash>();
+
+        let mut header = Vec::with_capacity(HEADER_LEN + 8 * lens.len());
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&[BYTE_ORDER, 0, 0, 0]);
+        for x in [ARITY, width, self.leaf_count(), lens.len()] {
+            header.extend_from_slice(&(x as u64).to_le_bytes());
+        }
+        let mut offset = HEADER_LEN + 8 * lens.len();
+        let mut pads = Vec::with_capacity(lens.len());
+        for len in &lens {
+            let pad = offset.next_multiple_of(LVL_ALIGN) - offset;
+            offset += pad;
+            header.extend_from_slice(&(offset as u64).to_le_bytes());
+            offset += len * width;
+            pads.push(pad);
+        }
+
+        out.write_all(&header)?;
+        for (lvl, (len, pad)) in lens.into_iter().zip(pads).enumerate() {
+            out.write_all(&[0; LVL_ALIGN][..pad])?;
+            out.write_all(lvl_bytes(&self.tree_lvls[lvl][..len]))?;
+        }
+        out.flush()
+    }
+
+    /// Dump the tree into the file `path`, see [`Self::write_mapped`].
+    pub fn write_mapped_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_mapped(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Read-only view of the tree that is written by [`MerkleTree::write_mapped`].\
+/// Nodes are served straight from the memory-mapped file, without deserialization.
+pub struct MappedMerkleTree<Hash, const ARITY: usize> {
+    mmap: Mmap,
+    /// byte offset & length of each level
+    lvls: Vec<(usize, usize)>,
+    _hash: PhantomData<Hash>,
+}
+
+impl<Hash: MtHashPod, const ARITY: usize> MappedMerkleTree<Hash, ARITY> {
+    pub const ARITY: usize = ARITY;
+
+    /// Map the file & test its header.
+    ///
+    /// # Safety
+    /// The file must not be modified (by this or another process) while it's mapped.
+    pub unsafe fn open(path: impl AsRef<Path>) -> Result<Self, MtMapError> {
+        let file = File::open(path)?;
+        // SAFETY: guaranteed by the caller
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::from_mmap(mmap)
+    }
+
+    fn from_mmap(mmap: Mmap) -> Result<Self, MtMapError> {
+        let bytes: &[u8] = &mmap;
+        if bytes.len() < HEADER_LEN {
+            return Err(MtMapError::Malformed("header is truncated"))
+        }
+        if &bytes[0..8] != MAGIC {
+            return Err(MtMapError::WrongMagic)
+        }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        if version != VERSION {
+            return Err(MtMapError::UnsupportedVersion { supported: VERSION, actual: version })
+        }
+        if bytes[12] != BYTE_ORDER {
+            return Err(MtMapError::ByteOrderMismatch)
+        }
+        if bytes[13..16] != [0; 3] {
+            return Err(MtMapError::Malformed("reserved bytes are not zeros"))
+        }
+
+        let read = |at: usize| -> Result<usize, MtMapError> {
+            let x = bytes.get(at..at + 8).ok_or(MtMapError::Malformed("level offsets are truncated"))?;
+            usize::try_from(u64::from_le_bytes(x.try_into().unwrap()))
+                .map_err(|_| MtMapError::Malformed("value doesn't fit into `usize`"))
+        };
+        let arity = read(16)?;
+        if arity != ARITY {
+            return Err(MtMapError::ArityMismatch { expected: ARITY, actual: arity })
+        }
+        let width = read(24)?;
+        if width != size_of::<Hash>() || width == 0 {
+            return Err(MtMapError::HashWidthMismatch { expected: size_of::<Hash>(), actual: width })
+        }
+        assert!(align_of::<Hash>() <= LVL_ALIGN, "`MtHashPod` must have alignment at most {LVL_ALIGN}");
+
+        let leaf_count = read(32)?;
+        let lens = lvl_lens::<ARITY>(leaf_count);
+        if read(40)? != lens.len() {
+            return Err(MtMapError::Malformed("level count doesn't match leaf count"))
+        }
+        let mut lvls = Vec::with_capacity(lens.len());
+        for (lvl, len) in lens.into_iter().enumerate() {
+            let offset = read(HEADER_LEN + 8 * lvl)?;
+            if offset % LVL_ALIGN != 0 {
+                return Err(MtMapError::Malformed("level is not aligned"))
+            }
+            let end = len.checked_mul(width).and_then(|x| x.checked_add(offset));
+            if end.is_none_or(|end| end > bytes.len()) {
+                return Err(MtMapError::Malformed("level is out of the file"))
+            }
+            lvls.push((offset, len));
+        }
+
+        Ok(Self { mmap, lvls, _hash: PhantomData })
+    }
+
+    /// Nodes of the level `lvl`.
+    ///
+    /// # panic
+    /// * if `lvl` is not a level of the tree
+    pub fn lvl_nodes(&self, lvl: usize) -> &[Hash] {
+        let (offset, len) = self.lvls[lvl];
+        // SAFETY: `from_mmap` tested that the level is inside the map & aligned for `Hash`
+        // (map itself is page aligned), and any bytes are a valid `Hash` (`MtHashPod`)
+        unsafe { std::slice::from_raw_parts(self.mmap.as_ptr().add(offset).cast::<Hash>(), len) }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count() == 0
+    }
+    #[inline]
+    pub fn height(&self) -> usize {
+        if self.is_empty() { return 0 }
+        self.lvls.len()
+    }
+    #[inline]
+    pub fn leaf_count(&self) -> usize {
+        self.lvls[0].1
+    }
+    #[inline]
+    pub fn is_valid_leaf_id(&self, id: LeafId) -> bool {
+        id.0 < self.leaf_count()
+    }
+
+    /// # panic
+    /// * if `self.is_empty()`
+    #[inline]
+    pub fn root_ref(&self) -> &Hash {
+        &self.lvl_nodes(self.height() - 1)[0]
+    }
+
+    /// # Panic
+    /// * if `node_id` is not in the tree
+    #[inline]
+    pub fn get_node_ref(&self, node_id: NodeId) -> &Hash {
+        &self.lvl_nodes(node_id.lvl)[node_id.index]
+    }
+
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn proof_ref(&self, id: LeafId) -> MtProofRef<'_, Hash, ARITY> {
+        assert!(self.height() != 0);
+        lvls_proof_ref_with(self.lvls.len(), |lvl| self.lvl_nodes(lvl), NodeId { lvl: 0, index: id.0 })
+    }
+
+    /// Verify hashes of all the nodes, see [`MerkleTree::audit`].
+    pub fn audit<Hasher>(&self, hasher: &mut Hasher) -> Result<(), NodeId>
+    where
+        Hash: Eq,
+        Hasher: ArityHasher<Hash, ARITY>,
+    {
+        audit_lvls::<_, _, ARITY>(self.lvls.len(), |lvl| self.lvl_nodes(lvl), hasher)
+    }
+}
+
+impl<Hash: MtHashPod + Clone, const ARITY: usize> MappedMerkleTree<Hash, ARITY> {
+    /// # panic
+    /// * if `self.is_empty()`
+    #[inline]
+    pub fn root(&self) -> Hash {
+        self.root_ref().clone()
+    }
+
+    /// # Panic
+    /// * if `node_id` is not in the tree
+    #[inline]
+    pub fn get_node(&self, node_id: NodeId) -> Hash {
+        self.get_node_ref(node_id).clone()
+    }
+
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn proof_owned(&self, id: LeafId) -> MtProof<Hash, ARITY> {
+        self.proof_ref(id).to_owned()
+    }
+
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn proof_detached(&self, id: LeafId) -> MtDetachedProof<Hash, ARITY> {
+        self.proof_owned(id).detach()
+    }
+
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn proof_compact(&self, id: LeafId) -> MtCompactProof<Hash, ARITY> {
+        self.proof_ref(id).to_compact()
+    }
+
+    /// Copy the tree into memory (without rehashing).
+    pub fn to_merkle_tree<Hasher>(&self, hasher: Hasher) -> MerkleTree<Hash, Hasher, ARITY>
+    where Hasher: ArityHasher<Hash, ARITY>
+    {
+        let mut tree = MerkleTree::new_minimal(hasher);
+        tree.tree_lvls = (0..self.lvls.len()).map(|lvl| self.lvl_nodes(lvl).to_vec()).collect();
+        tree
+    }
+}
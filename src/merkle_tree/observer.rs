@@ -0,0 +1,90 @@
+//! Hooks on node changes of [`MerkleTree`], see [`MtObserver`].
+
+use crate::MtArityHasher as ArityHasher;
+use super::{MerkleTree, NodeId};
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Observer of the node changes of [`MerkleTree`] (for example to mirror the tree into an external index),
+/// see [`MerkleTree::with_observer`].
+///
+/// It's called for each node (leafs are nodes of level `0`) that is written or removed:
+/// * `old` is [`None`] for a new node
+/// * `new` is [`None`] for a removed node
+///
+/// The node is already changed in the tree when it's called.
+/// 
+/// `()` is the observer of the tree without observer: it's a no-op, so the tree has no overhead.
+pub trait MtObserver<Hash> {
+    /// `false` if [`Self::on_node`] does nothing, so the changed nodes are not even iterated.
+    const ENABLED: bool = true;
+
+    fn on_node(&mut self, node: NodeId, old: Option<&Hash>, new: Option<&Hash>);
+}
+impl<Hash> MtObserver<Hash> for () {
+    const ENABLED: bool = false;
+
+    #[inline(always)]
+    fn on_node(&mut self, _: NodeId, _: Option<&Hash>, _: Option<&Hash>) { }
+}
+impl<Hash, F> MtObserver<Hash> for F
+where F: FnMut(NodeId, Option<&Hash>, Option<&Hash>)
+{
+    fn on_node(&mut self, node: NodeId, old: Option<&Hash>, new: Option<&Hash>) {
+        self(node, old, new)
+    }
+}
+
+impl<Hash, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
+    /// The same tree that calls `observer` on each change of its nodes, see [`MtObserver`].
+    /// 
+    /// The previous observer is dropped.\
+    /// The observer is a type parameter of the tree, so another type of observer is attached only by move,
+    /// see [`Self::set_observer`] to replace the observer in place.
+    pub fn with_observer<NewObserver: MtObserver<Hash>>(self, observer: NewObserver) -> MerkleTree<Hash, Hasher, ARITY, NewObserver> {
+        MerkleTree {
+            tree_lvls: self.tree_lvls,
+            hasher: self.hasher,
+            observer,
+            new_lvl_cap: self.new_lvl_cap,
+        }
+    }
+
+    /// The same tree without observer & the observer.
+    /// 
+    /// It's by move only (like [`Self::with_observer`]).
+    pub fn detach_observer(self) -> (MerkleTree<Hash, Hasher, ARITY>, Observer) {
+        let tree = MerkleTree {
+            tree_lvls: self.tree_lvls,
+            hasher: self.hasher,
+            observer: (),
+            new_lvl_cap: self.new_lvl_cap,
+        };
+        (tree, self.observer)
+    }
+
+    /// Replace the observer in place by the observer of the same type.
+    /// 
+    /// # Return
+    /// The previous observer.
+    /// 
+    /// Use the boxed closure (`Box<dyn FnMut(NodeId, Option<&Hash>, Option<&Hash>)>`) as `Observer`
+    /// to attach & detach observers of the tree behind `&mut` (for example a field of another struct):
+    /// ```ignore
+    /// let old = tree.set_observer(Box::new(|_, _, _| {}));
+    /// ```
+    #[inline]
+    pub fn set_observer(&mut self, observer: Observer) -> Observer {
+        std::mem::replace(&mut self.observer, observer)
+    }
+
+    #[inline]
+    pub fn observer(&self) -> &Observer {
+        &self.observer
+    }
+
+    #[inline]
+    pub fn observer_mut(&mut self) -> &mut Observer {
+        &mut self.observer
+    }
+}
@@ -0,0 +1,95 @@
+//! Parallel hashing of the levels (feature `rayon`).
+//!
+//! Groups of a level are hashed by chunks on the rayon thread pool, each worker has its own hasher
+//! (clone of the tree hasher or created by a factory). Hashes are the same as by the sequential methods.
+
+use std::ops::Range;
+use rayon::prelude::*;
+use crate::MtArityHasher as ArityHasher;
+use super::{hash_padded_group, LeafId, MerkleTree, MtObserver};
+
+/// Levels with less groups are hashed sequentially: parallelism doesn't pay off for them.
+const PAR_MIN_GROUPS: usize = 1024;
+
+impl<Hash, Hasher, const ARITY: usize> MerkleTree<Hash, Hasher, ARITY>
+where
+    Hash: Send + Sync,
+    Hasher: ArityHasher<Hash, ARITY> + Clone + Send + Sync,
+{
+    /// [`Self::new_from_leafs`] with parallel hashing, see [module docs](self).
+    pub fn new_from_leafs_par<I>(hasher: Hasher, leafs_iter: I) -> Self
+    where I: IntoIterator<Item = Hash>
+    {
+        let mut tree = Self::new_minimal(hasher);
+        tree.push_batched_par(leafs_iter);
+        tree
+    }
+}
+
+impl<Hash, Hasher, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer>
+where
+    Hash: Send + Sync,
+    Hasher: ArityHasher<Hash, ARITY> + Clone + Send + Sync,
+    Observer: MtObserver<Hash>,
+{
+
+    /// [`Self::push_batched`] with parallel hashing, see [module docs](self).
+    pub fn push_batched_par(&mut self, batch: impl IntoIterator<Item = Hash>) -> std::ops::Range<LeafId> {
+        self.replace_batched_par(batch, self.next_leaf_id())
+    }
+
+    /// [`Self::replace_batched`] with parallel hashing, see [module docs](self).
+    ///
+    /// Workers use clones of the tree hasher.
+    ///
+    /// # panic
+    /// * if `start_id` > `last_leaf_id`
+    pub fn replace_batched_par(&mut self, batch: impl IntoIterator<Item = Hash>, start_id: LeafId) -> std::ops::Range<LeafId> {
+        let hasher = (*self.hasher).clone();
+        self.replace_batched_par_with(batch, start_id, || hasher.clone())
+    }
+}
+
+impl<Hash, Hasher, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer>
+where
+    Hash: Send + Sync,
+    Hasher: ArityHasher<Hash, ARITY>,
+    Observer: MtObserver<Hash>,
+{
+    /// [`Self::replace_batched`] with parallel hashing, see [module docs](self).
+    ///
+    /// Workers use hashers created by `new_hasher`, they must hash as the tree hasher.
+    ///
+    /// # panic
+    /// * if `start_id` > `last_leaf_id`
+    pub fn replace_batched_par_with<F>(&mut self, batch: impl IntoIterator<Item = Hash>, start_id: LeafId, new_hasher: F) -> std::ops::Range<LeafId>
+    where F: Fn() -> Hasher + Sync
+    {
+        let range = self.replace_leafs(batch, start_id);
+        self.recalc_upper_lvls(range.start.0, range.end.0, |tree, pre_lvl_range, lvl| tree.calc_lvl_hashes_par(pre_lvl_range, lvl, &new_hasher));
+        range
+    }
+
+    /// Parallel [`Self::calc_lvl_hashes`].
+    fn calc_lvl_hashes_par<F>(&mut self, pre_lvl_range: Range<usize>, lvl: usize, new_hasher: &F) -> bool
+    where F: Fn() -> Hasher + Sync
+    {
+        let (from, to) = (pre_lvl_range.start, pre_lvl_range.end);
+        let groups = from / ARITY..to / ARITY;
+        if groups.len() < PAR_MIN_GROUPS {
+            return self.calc_lvl_hashes(pre_lvl_range, lvl)
+        }
+
+        let pre_lvl = &self.tree_lvls[lvl - 1][groups.start * ARITY..groups.end * ARITY];
+        let hashes: Vec<Hash> = pre_lvl.par_chunks(ARITY)
+            .with_min_len(PAR_MIN_GROUPS)
+            .map_init(new_hasher, |hasher, group| hash_padded_group::<_, _, ARITY>(hasher, group))
+            .collect();
+        for (index, hash) in groups.zip(hashes) {
+            self.set_or_push(index, lvl, hash);
+        }
+
+        // the rest is the last uneven group, if there is one
+        self.calc_lvl_hashes(to - to % ARITY..to, lvl)
+    }
+}
@@ -0,0 +1,745 @@
+//! Pluggable storage of the tree nodes.
+//!
+//! [`MtNodeStore`] keeps levels of the tree (from the leafs to the root) & [`StoredMerkleTree`]
+//! does all the tree operations through it, so the tree doesn't have to live in memory:
+//! * [`MtMemStore`]: levels are `Vec`s in memory
+//! * [`MtFileStore`]: each level is a file in the directory, so the tree can be larger than memory
+//!   & it's persisted across restarts
+//!
+//! [`MtStreamBuilder`] builds the tree from a stream of leafs straight into the store.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use crate::MtArityHasher as ArityHasher;
+use super::{hash_lvl, hash_padded_group, LeafId, MerkleTree, MtHashBytes, MtProof, NodeId};
+
+/// Max amount of nodes that are read from the store at once by [`StoredMerkleTree::audit`]
+/// (& leafs that are written at once by [`StoredMerkleTree::replace_batched`]).
+const CHUNK: usize = 1 << 12;
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Storage of the tree levels. Store always has at least one level (level of leafs).
+pub trait MtNodeStore<Hash> {
+    type Error: std::error::Error;
+
+    fn lvl_count(&self) -> usize;
+
+    /// `0` if `lvl >= self.lvl_count()`
+    fn lvl_len(&self, lvl: usize) -> usize;
+
+    /// # Panic
+    /// * if `node` is not in the store
+    fn get(&self, node: NodeId) -> Result<Hash, Self::Error>;
+
+    /// # Panic
+    /// * if `range` is out of the level
+    fn get_range(&self, lvl: usize, range: Range<usize>) -> Result<Vec<Hash>, Self::Error> {
+        range.map(|index| self.get(NodeId { lvl, index })).collect()
+    }
+
+    /// The whole level `lvl` without copying, if the store keeps it in memory.
+    fn lvl_slice(&self, _lvl: usize) -> Option<&[Hash]> {
+        None
+    }
+
+    /// Write `hashes` into the level `lvl` starting from node `start`.\
+    /// The level is extended if needed, the level `self.lvl_count()` is created if needed.
+    ///
+    /// # Panic
+    /// * if `lvl > self.lvl_count()` or `start > self.lvl_len(lvl)`
+    fn put_batch(&mut self, lvl: usize, start: usize, hashes: Vec<Hash>) -> Result<(), Self::Error>;
+
+    /// See [`Self::put_batch`]
+    fn put(&mut self, node: NodeId, hash: Hash) -> Result<(), Self::Error> {
+        self.put_batch(node.lvl, node.index, vec![hash])
+    }
+
+    /// Shrink the level `lvl` to `len` nodes (nothing happens if it's not longer).
+    fn truncate(&mut self, lvl: usize, len: usize) -> Result<(), Self::Error>;
+
+    /// Remove all the levels from `lvl_count` (at least one level is kept).
+    fn truncate_lvls(&mut self, lvl_count: usize) -> Result<(), Self::Error>;
+
+    /// Make all writes durable.
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] Memory Store
+
+/// `Mt` stands for `MerkleTree`
+///
+/// In-memory [`MtNodeStore`]
+#[derive(Debug, Clone)]
+pub struct MtMemStore<Hash> {
+    lvls: Vec<Vec<Hash>>,
+}
+impl<Hash> MtMemStore<Hash> {
+    pub fn new() -> Self {
+        Self { lvls: vec![vec![]] }
+    }
+}
+impl<Hash> Default for MtMemStore<Hash> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<Hash: Clone> MtNodeStore<Hash> for MtMemStore<Hash> {
+    type Error = std::convert::Infallible;
+
+    fn lvl_count(&self) -> usize {
+        self.lvls.len()
+    }
+    fn lvl_len(&self, lvl: usize) -> usize {
+        self.lvls.get(lvl).map_or(0, Vec::len)
+    }
+    fn get(&self, node: NodeId) -> Result<Hash, Self::Error> {
+        Ok(self.lvls[node.lvl][node.index].clone())
+    }
+    fn get_range(&self, lvl: usize, range: Range<usize>) -> Result<Vec<Hash>, Self::Error> {
+        Ok(self.lvls[lvl][range].to_vec())
+    }
+    fn lvl_slice(&self, lvl: usize) -> Option<&[Hash]> {
+        self.lvls.get(lvl).map(Vec::as_slice)
+    }
+    fn put_batch(&mut self, lvl: usize, start: usize, hashes: Vec<Hash>) -> Result<(), Self::Error> {
+        if lvl == self.lvls.len() {
+            self.lvls.push(vec![]);
+        }
+        let tree_lvl = &mut self.lvls[lvl];
+        assert!(start <= tree_lvl.len(), "nodes must be written without gaps");
+        let end = (start + hashes.len()).min(tree_lvl.len());
+        tree_lvl.splice(start..end, hashes);
+        Ok(())
+    }
+    fn truncate(&mut self, lvl: usize, len: usize) -> Result<(), Self::Error> {
+        self.lvls[lvl].truncate(len);
+        Ok(())
+    }
+    fn truncate_lvls(&mut self, lvl_count: usize) -> Result<(), Self::Error> {
+        self.lvls.truncate(lvl_count.max(1));
+        Ok(())
+    }
+}
+
+// [-] Memory Store
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] File Store
+
+/// `Mt` stands for `MerkleTree`
+///
+/// File-backed [`MtNodeStore`]: level `lvl` is the file `lvl_{lvl}.bin` in the directory,
+/// that contains hashes of the level one by one (see [`MtHashBytes`]).
+///
+/// Writes are not atomic: if the process is interrupted in the middle of an update,
+/// [`StoredMerkleTree::new`] or [`StoredMerkleTree::audit`] reports it on the next open.
+#[derive(Debug)]
+pub struct MtFileStore<Hash> {
+    dir: PathBuf,
+    lvls: Vec<File>,
+    lvl_lens: Vec<usize>,
+    _hash: PhantomData<fn() -> Hash>,
+}
+impl<Hash: MtHashBytes> MtFileStore<Hash> {
+    /// Open the store in the directory `dir` (it's created if it doesn't exist).
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let mut store = Self { dir, lvls: vec![], lvl_lens: vec![], _hash: PhantomData };
+        while store.lvls.is_empty() || store.lvl_path(store.lvls.len()).exists() {
+            let file = store.open_lvl(store.lvls.len())?;
+            let len = file.metadata()?.len() as usize;
+            if !len.is_multiple_of(Hash::LEN) {
+                let msg = format!("size of level file {} isn't a multiple of hash size", store.lvls.len());
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg))
+            }
+            store.lvls.push(file);
+            store.lvl_lens.push(len / Hash::LEN);
+        }
+        Ok(store)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn lvl_path(&self, lvl: usize) -> PathBuf {
+        self.dir.join(format!("lvl_{lvl}.bin"))
+    }
+
+    fn open_lvl(&self, lvl: usize) -> io::Result<File> {
+        OpenOptions::new().read(true).write(true).create(true).truncate(false).open(self.lvl_path(lvl))
+    }
+}
+/// Read `buf` from `offset` of the `file`.\
+/// Cursor of the file is shared by all its `&File`s, so readers through `&self` must not seek it.
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+/// See the unix version.
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(read) => {
+                buf = &mut buf[read..];
+                offset += read as u64;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+/// See the unix version.
+#[cfg(not(any(unix, windows)))]
+fn read_exact_at(_file: &File, _buf: &mut [u8], _offset: u64) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "positioned reads are not supported on this platform"))
+}
+
+impl<Hash: MtHashBytes> MtNodeStore<Hash> for MtFileStore<Hash> {
+    type Error = io::Error;
+
+    fn lvl_count(&self) -> usize {
+        self.lvls.len()
+    }
+    fn lvl_len(&self, lvl: usize) -> usize {
+        self.lvl_lens.get(lvl).copied().unwrap_or(0)
+    }
+    fn get(&self, node: NodeId) -> Result<Hash, Self::Error> {
+        Ok(self.get_range(node.lvl, node.index..node.index + 1)?.remove(0))
+    }
+    fn get_range(&self, lvl: usize, range: Range<usize>) -> Result<Vec<Hash>, Self::Error> {
+        assert!(range.end <= self.lvl_lens[lvl], "nodes must be in the level");
+        let mut bytes = vec![0; range.len() * Hash::LEN];
+        read_exact_at(&self.lvls[lvl], &mut bytes, (range.start * Hash::LEN) as u64)?;
+        bytes.chunks_exact(Hash::LEN)
+            .map(|x| Hash::read_bytes(x).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid bytes of hash")))
+            .collect()
+    }
+    fn put_batch(&mut self, lvl: usize, start: usize, hashes: Vec<Hash>) -> Result<(), Self::Error> {
+        if lvl == self.lvls.len() {
+            let file = self.open_lvl(lvl)?;
+            file.set_len(0)?;
+            self.lvls.push(file);
+            self.lvl_lens.push(0);
+        }
+        assert!(start <= self.lvl_lens[lvl], "nodes must be written without gaps");
+        let mut bytes = Vec::with_capacity(hashes.len() * Hash::LEN);
+        for hash in &hashes {
+            hash.write_bytes(&mut bytes);
+        }
+        let mut file = &self.lvls[lvl];
+        file.seek(SeekFrom::Start((start * Hash::LEN) as u64))?;
+        file.write_all(&bytes)?;
+        self.lvl_lens[lvl] = self.lvl_lens[lvl].max(start + hashes.len());
+        Ok(())
+    }
+    fn truncate(&mut self, lvl: usize, len: usize) -> Result<(), Self::Error> {
+        if len < self.lvl_lens[lvl] {
+            self.lvls[lvl].set_len((len * Hash::LEN) as u64)?;
+            self.lvl_lens[lvl] = len;
+        }
+        Ok(())
+    }
+    fn truncate_lvls(&mut self, lvl_count: usize) -> Result<(), Self::Error> {
+        let lvl_count = lvl_count.max(1);
+        // the highest levels are removed first, so store is still readable after interruption
+        while self.lvls.len() > lvl_count {
+            let lvl = self.lvls.len() - 1;
+            self.lvls.pop();
+            self.lvl_lens.pop();
+            fs::remove_file(self.lvl_path(lvl))?;
+        }
+        Ok(())
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        for file in &self.lvls {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+// [-] File Store
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] Stored Merkle Tree
+
+/// Reason why [`StoredMerkleTree`] operation failed.
+#[derive(thiserror::Error, Debug)]
+pub enum MtStoreError<E: std::error::Error> {
+    #[error(transparent)]
+    Store(E),
+    #[error("Store has {actual} levels, but {expected} levels are expected for its leafs.")]
+    WrongLvlCount { expected: usize, actual: usize },
+    #[error("Level {lvl} of the store has {actual} nodes, but {expected} nodes are expected.")]
+    WrongLvlLen { lvl: usize, expected: usize, actual: usize },
+    #[error("Node {0:?} of the store is not equal to the hash of its children.")]
+    TamperedNode(NodeId),
+}
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Merkle tree whose nodes are kept in [`MtNodeStore`].\
+/// Nodes are the same as the nodes of [`MerkleTree`] with the same leafs.
+#[derive(Debug, Clone)]
+pub struct StoredMerkleTree<Hash, Hasher, Store, const ARITY: usize> {
+    store: Store,
+    hasher: Hasher,
+    _hash: PhantomData<fn() -> Hash>,
+}
+
+impl<Hash, Hasher, Store, const ARITY: usize> StoredMerkleTree<Hash, Hasher, Store, ARITY>
+where
+    Hasher: ArityHasher<Hash, ARITY>,
+    Store: MtNodeStore<Hash>,
+{
+    pub const ARITY: usize = ARITY;
+
+    /// Open the tree in the `store` (for example after restart).
+    ///
+    /// Only the shape of the levels is tested, see [`Self::audit`].
+    pub fn new(hasher: Hasher, store: Store) -> Result<Self, MtStoreError<Store::Error>> {
+        assert!(ARITY > 1, "`MerkleTree` is a tree, so `ARITY` must be more than 1");
+
+        let mut len = store.lvl_len(0);
+        let mut expected = vec![len];
+        while len > 1 {
+            len = len.div_ceil(ARITY);
+            expected.push(len);
+        }
+        if store.lvl_count() != expected.len() {
+            return Err(MtStoreError::WrongLvlCount { expected: expected.len(), actual: store.lvl_count() })
+        }
+        for (lvl, expected) in expected.into_iter().enumerate() {
+            let actual = store.lvl_len(lvl);
+            if actual != expected {
+                return Err(MtStoreError::WrongLvlLen { lvl, expected, actual })
+            }
+        }
+
+        Ok(Self { store, hasher, _hash: PhantomData })
+    }
+
+    /// Build the tree from the `leafs` straight into the `store`, see [`MtStreamBuilder`].
+    pub fn from_leafs(hasher: Hasher, store: Store, leafs: impl IntoIterator<Item = Hash>) -> Result<Self, MtStoreError<Store::Error>>
+    where Hash: Clone
+    {
+        let mut builder = MtStreamBuilder::new(hasher, store).map_err(MtStoreError::Store)?;
+        builder.extend(leafs).map_err(MtStoreError::Store)?;
+        builder.finish()
+    }
+
+    /// Write all the nodes of the `tree` into the `store` (previous content of the store is removed).
+    pub fn from_merkle_tree(tree: MerkleTree<Hash, Hasher, ARITY>, mut store: Store) -> Result<Self, MtStoreError<Store::Error>> {
+        let height = tree.height().max(1);
+        store.truncate_lvls(1).map_err(MtStoreError::Store)?;
+        store.truncate(0, 0).map_err(MtStoreError::Store)?;
+        for (lvl, nodes) in tree.tree_lvls.into_iter().take(height).enumerate() {
+            store.put_batch(lvl, 0, nodes).map_err(MtStoreError::Store)?;
+        }
+        Self::new(*tree.hasher, store)
+    }
+
+    /// Read all the nodes into memory.
+    pub fn to_merkle_tree<OtherHasher>(&self, hasher: OtherHasher) -> Result<MerkleTree<Hash, OtherHasher, ARITY>, Store::Error>
+    where OtherHasher: ArityHasher<Hash, ARITY>
+    {
+        let mut tree = MerkleTree::new_minimal(hasher);
+        tree.tree_lvls = (0..self.store.lvl_count())
+            .map(|lvl| self.store.get_range(lvl, 0..self.store.lvl_len(lvl)))
+            .collect::<Result<_, _>>()?;
+        Ok(tree)
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+    /// Changes must keep the nodes of the tree (see [`super::MtLightStore::set_cached_lvls`]).
+    pub(super) fn store_mut(&mut self) -> &mut Store {
+        &mut self.store
+    }
+    pub fn into_store(self) -> Store {
+        self.store
+    }
+    pub fn hasher(&self) -> &Hasher {
+        &self.hasher
+    }
+
+    /// See [`MtNodeStore::flush`]
+    pub fn flush(&mut self) -> Result<(), Store::Error> {
+        self.store.flush()
+    }
+
+    #[inline]
+    pub fn leaf_count(&self) -> usize {
+        self.store.lvl_len(0)
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count() == 0
+    }
+    #[inline]
+    pub fn height(&self) -> usize {
+        if self.is_empty() { return 0 }
+        self.store.lvl_count()
+    }
+    #[inline]
+    pub fn is_valid_leaf_id(&self, id: LeafId) -> bool {
+        id.0 < self.leaf_count()
+    }
+
+    /// # panic
+    /// * if `self.is_empty()`
+    pub fn root(&self) -> Result<Hash, Store::Error> {
+        self.store.get(NodeId { lvl: self.height() - 1, index: 0 })
+    }
+
+    /// # Panic
+    /// * if `node_id` is not in the tree
+    pub fn get_node(&self, node_id: NodeId) -> Result<Hash, Store::Error> {
+        self.store.get(node_id)
+    }
+
+    pub fn push(&mut self, hash: Hash) -> Result<LeafId, Store::Error> {
+        let id = LeafId::new(self.leaf_count());
+        self.store.put(NodeId { lvl: 0, index: id.0 }, hash)?;
+        self.recalc(0, id.0..id.0 + 1)?;
+        Ok(id)
+    }
+
+    /// Push all the hashes & recalculate each changed node once.
+    pub fn push_batched(&mut self, batch: impl IntoIterator<Item = Hash>) -> Result<Range<LeafId>, Store::Error> {
+        self.replace_batched(batch, LeafId::new(self.leaf_count()))
+    }
+
+    /// Replace leafs from `start_id` (the tree is extended if the batch is longer)
+    /// & recalculate the changed nodes.
+    ///
+    /// The batch is written & hashed by chunks, so it's never collected into memory
+    /// (only the nodes above the chunk boundaries are recalculated more than once).
+    ///
+    /// # panic
+    /// * if `start_id` > `last_leaf_id`
+    pub fn replace_batched(&mut self, batch: impl IntoIterator<Item = Hash>, start_id: LeafId) -> Result<Range<LeafId>, Store::Error> {
+        assert!(start_id.0 <= self.leaf_count(), "invalid `start_id`");
+        let mut batch = batch.into_iter();
+        let mut end = start_id.0;
+        loop {
+            let chunk: Vec<_> = batch.by_ref().take(CHUNK).collect();
+            if chunk.is_empty() { break }
+            let start = end;
+            end += chunk.len();
+            self.store.put_batch(0, start, chunk)?;
+            self.recalc(0, start..end)?;
+        }
+        Ok(start_id..LeafId::new(end))
+    }
+
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    ///
+    /// # Return
+    /// `Hash` of removed leaf
+    pub fn replace(&mut self, hash: Hash, id: LeafId) -> Result<Hash, Store::Error> {
+        assert!(self.is_valid_leaf_id(id), "invalid `id`");
+        let node = NodeId { lvl: 0, index: id.0 };
+        let removed = self.store.get(node)?;
+        self.store.put(node, hash)?;
+        self.recalc(0, id.0..id.0 + 1)?;
+        Ok(removed)
+    }
+
+    /// # Return
+    /// `Hash` of removed leaf
+    ///
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn swap_remove(&mut self, id: LeafId) -> Result<Hash, Store::Error> {
+        assert!(self.is_valid_leaf_id(id), "invalid `id`");
+        // never panic: the tree isn't empty
+        let last_hash = self.pop()?.unwrap();
+        if id.0 == self.leaf_count() {
+            return Ok(last_hash)
+        }
+        self.replace(last_hash, id)
+    }
+
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id_a \ id_b)`
+    pub fn swap(&mut self, id_a: LeafId, id_b: LeafId) -> Result<(), Store::Error> {
+        assert!(self.is_valid_leaf_id(id_a) && self.is_valid_leaf_id(id_b), "invalid `id`");
+        if id_a == id_b { return Ok(()) }
+        let hash_a = self.store.get(NodeId { lvl: 0, index: id_a.0 })?;
+        let hash_b = self.replace(hash_a, id_b)?;
+        self.replace(hash_b, id_a)?;
+        Ok(())
+    }
+
+    /// Append leafs of the trees, see [`MerkleTree::merge`].
+    ///
+    /// Levels of a tree are written as is while its nodes start new groups
+    /// (for example if leaf amount of `self` is `pow(ARITY, exp)`), only the rest is rehashed.
+    pub fn merge<Other>(&mut self, iter: impl IntoIterator<Item = MerkleTree<Hash, Hasher, ARITY, Other>>) -> Result<(), Store::Error> {
+        for other in iter {
+            if other.is_empty() { continue }
+            let other_height = other.height();
+            let start = self.leaf_count();
+
+            let mut span = 1;
+            let mut lvl = 0;
+            for nodes in other.tree_lvls.into_iter().take(other_height) {
+                if !start.is_multiple_of(span) { break }
+                self.store.put_batch(lvl, start / span, nodes)?;
+                span *= ARITY;
+                lvl += 1;
+            }
+
+            // parents of the last written level are rehashed
+            let span = span / ARITY;
+            let lvl = lvl - 1;
+            self.recalc(lvl, start / span..self.store.lvl_len(lvl))?;
+        }
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<Option<Hash>, Store::Error> {
+        let Some(last) = self.leaf_count().checked_sub(1) else {
+            return Ok(None)
+        };
+        let removed = self.store.get(NodeId { lvl: 0, index: last })?;
+        self.store.truncate(0, last)?;
+        if last == 0 {
+            self.store.truncate_lvls(1)?;
+        } else {
+            self.recalc(0, last - 1..last)?;
+        }
+        Ok(Some(removed))
+    }
+
+    /// Recalculate parents of changed nodes `changed` of the level `lvl` (level by level up to the root)
+    /// & remove nodes that are not in the tree anymore.
+    fn recalc(&mut self, mut lvl: usize, changed: Range<usize>) -> Result<(), Store::Error> {
+        let (mut start, mut end) = (changed.start, changed.end);
+        loop {
+            let len = self.store.lvl_len(lvl);
+            if len <= 1 { break }
+
+            let (parent_start, parent_end) = (start / ARITY, end.div_ceil(ARITY));
+            let children = self.store.get_range(lvl, parent_start * ARITY..(parent_end * ARITY).min(len))?;
+            let parents = hash_lvl::<_, _, ARITY>(&mut self.hasher, &children);
+            self.store.put_batch(lvl + 1, parent_start, parents)?;
+            self.store.truncate(lvl + 1, len.div_ceil(ARITY))?;
+
+            (start, end) = (parent_start, parent_end);
+            lvl += 1;
+        }
+        self.store.truncate_lvls(lvl + 1)
+    }
+
+    /// The same as [`MerkleTree::proof_owned`]: groups are read from the store,
+    /// so it needs only `&self` (readers of a shared tree don't block each other).
+    ///
+    /// # panic
+    /// * if `!self.is_valid_leaf_id(id)`
+    pub fn proof_owned(&self, id: LeafId) -> Result<MtProof<Hash, ARITY>, Store::Error>
+    where Hash: Clone
+    {
+        assert!(self.is_valid_leaf_id(id), "invalid `id`");
+        let height = self.height();
+        let mut tree_lvl_nodes = Vec::with_capacity((height - 1) * ARITY);
+        let mut tree_lvl_path = Vec::with_capacity(height - 1);
+        let mut index = id.0;
+        for lvl in 0..height - 1 {
+            let start = index / ARITY * ARITY;
+            let mut group = self.store.get_range(lvl, start..(start + ARITY).min(self.store.lvl_len(lvl)))?;
+            // never panic: group contains node `index`
+            let last = group.last().unwrap().clone();
+            group.resize(ARITY, last);
+            tree_lvl_nodes.extend(group);
+            tree_lvl_path.push(index % ARITY);
+            index /= ARITY;
+        }
+
+        Ok(MtProof {
+            tree_lvl_nodes,
+            tree_lvl_path,
+            root: self.root()?,
+            tree_size: Some(self.leaf_count()),
+        })
+    }
+
+    /// Verify hashes of all the nodes, see [`MerkleTree::audit`].
+    pub fn audit(&mut self) -> Result<(), MtStoreError<Store::Error>>
+    where Hash: Eq
+    {
+        for lvl in 1..self.store.lvl_count() {
+            let len = self.store.lvl_len(lvl);
+            let children_len = self.store.lvl_len(lvl - 1);
+            for start in (0..len).step_by(CHUNK) {
+                let end = (start + CHUNK).min(len);
+                let nodes = self.store.get_range(lvl, start..end).map_err(MtStoreError::Store)?;
+                let children = self.store.get_range(lvl - 1, start * ARITY..(end * ARITY).min(children_len))
+                    .map_err(MtStoreError::Store)?;
+                let hashes = hash_lvl::<_, _, ARITY>(&mut self.hasher, &children);
+                if let Some(offset) = nodes.iter().zip(&hashes).position(|(node, hash)| node != hash) {
+                    return Err(MtStoreError::TamperedNode(NodeId { lvl, index: start + offset }))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// [-] Stored Merkle Tree
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
+// [+] Stream Builder
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Builder of the tree from a stream of leafs (for trees larger than memory).
+///
+/// Each level is written into the store sequentially & only the unfinished group of each level
+/// is kept in memory, plus a write buffer of each level (see [`Self::with_buffer`]),
+/// so memory is `O((ARITY + buffer) * height)` hashes.
+///
+/// ```ignore
+/// let mut builder = MtStreamBuilder::<_, _, _, 2>::new(hasher, MtFileStore::open(dir)?)?;
+/// builder.extend(leafs)?;
+/// let tree = builder.finish()?;
+/// let root = tree.root()?;
+/// ```
+pub struct MtStreamBuilder<Hash, Hasher, Store, const ARITY: usize> {
+    store: Store,
+    hasher: Hasher,
+    /// unfinished group of each level
+    groups: Vec<Vec<Hash>>,
+    /// nodes of each level that are not written into the store yet
+    buffers: Vec<Vec<Hash>>,
+    /// amount of nodes of each level that are written into the store
+    written: Vec<usize>,
+    buffer: usize,
+}
+
+impl<Hash, Hasher, Store, const ARITY: usize> MtStreamBuilder<Hash, Hasher, Store, ARITY>
+where
+    Hash: Clone,
+    Hasher: ArityHasher<Hash, ARITY>,
+    Store: MtNodeStore<Hash>,
+{
+    pub const DEFAULT_BUFFER: usize = 1 << 10;
+
+    /// Previous content of the `store` is removed.
+    pub fn new(hasher: Hasher, mut store: Store) -> Result<Self, Store::Error> {
+        assert!(ARITY > 1, "`MerkleTree` is a tree, so `ARITY` must be more than 1");
+        store.truncate_lvls(1)?;
+        store.truncate(0, 0)?;
+        Ok(Self {
+            store,
+            hasher,
+            groups: vec![],
+            buffers: vec![],
+            written: vec![],
+            buffer: Self::DEFAULT_BUFFER,
+        })
+    }
+
+    /// Amount of nodes of each level that are written into the store at once.
+    ///
+    /// # panic
+    /// * if `buffer == 0`
+    pub fn with_buffer(mut self, buffer: usize) -> Self {
+        assert!(buffer > 0, "`buffer` must be positive");
+        self.buffer = buffer;
+        self
+    }
+
+    #[inline]
+    pub fn leaf_count(&self) -> usize {
+        self.lvl_len(0)
+    }
+
+    fn lvl_len(&self, lvl: usize) -> usize {
+        self.written.get(lvl).map_or(0, |written| written + self.buffers[lvl].len())
+    }
+
+    pub fn push(&mut self, hash: Hash) -> Result<LeafId, Store::Error> {
+        let id = LeafId::new(self.leaf_count());
+        self.add_node(0, hash)?;
+        Ok(id)
+    }
+
+    pub fn extend(&mut self, leafs: impl IntoIterator<Item = Hash>) -> Result<(), Store::Error> {
+        for hash in leafs {
+            self.add_node(0, hash)?;
+        }
+        Ok(())
+    }
+
+    /// Add node to the end of the level `lvl` & hash all the groups that are finished by it.
+    fn add_node(&mut self, mut lvl: usize, mut hash: Hash) -> Result<(), Store::Error> {
+        loop {
+            if lvl == self.groups.len() {
+                self.groups.push(Vec::with_capacity(ARITY));
+                self.buffers.push(Vec::with_capacity(self.buffer));
+                self.written.push(0);
+            }
+            self.buffers[lvl].push(hash.clone());
+            if self.buffers[lvl].len() >= self.buffer {
+                self.flush_lvl(lvl)?;
+            }
+
+            let group = &mut self.groups[lvl];
+            group.push(hash);
+            if group.len() < ARITY {
+                return Ok(())
+            }
+            let parent = hash_padded_group::<_, _, ARITY>(&mut self.hasher, group.iter());
+            group.clear();
+            (lvl, hash) = (lvl + 1, parent);
+        }
+    }
+
+    fn flush_lvl(&mut self, lvl: usize) -> Result<(), Store::Error> {
+        let nodes = std::mem::take(&mut self.buffers[lvl]);
+        let len = nodes.len();
+        self.store.put_batch(lvl, self.written[lvl], nodes)?;
+        self.written[lvl] += len;
+        Ok(())
+    }
+
+    /// Hash the unfinished groups, write all the nodes & open the tree.
+    pub fn finish(mut self) -> Result<StoredMerkleTree<Hash, Hasher, Store, ARITY>, MtStoreError<Store::Error>> {
+        let mut lvl = 0;
+        while self.lvl_len(lvl) > 1 {
+            if !self.groups[lvl].is_empty() {
+                let parent = hash_padded_group::<_, _, ARITY>(&mut self.hasher, &self.groups[lvl]);
+                self.groups[lvl].clear();
+                self.add_node(lvl + 1, parent).map_err(MtStoreError::Store)?;
+            }
+            lvl += 1;
+        }
+        for lvl in 0..self.buffers.len() {
+            self.flush_lvl(lvl).map_err(MtStoreError::Store)?;
+        }
+        StoredMerkleTree::new(self.hasher, self.store)
+    }
+}
+
+// [-] Stream Builder
+// ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
@@ -0,0 +1,260 @@
+//! Anti-entropy synchronization of replicas of the tree.
+//!
+//! Local replica pulls the remote one by requests ([`MtSyncRequest`]) through any transport ([`MtSyncTransport`]):
+//! 1. ask the leaf count & the root of the remote replica
+//! 2. cut off extra local leafs or fetch the missing ones, so both trees have the same shape
+//! 3. descend from the root level by level: fetch children of the nodes that differ
+//!    & compare them with the local ones
+//! 4. replace the local leafs that differ
+//!
+//! So it takes `O(differences * ARITY * height)` hashes instead of the whole leaf set
+//! (plus the leafs that are missing locally).
+//!
+//! Remote side answers by [`MerkleTree::sync_respond`], [`MtSyncChannel`] is in-memory transport.
+
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, Sender};
+use crate::MtArityHasher as ArityHasher;
+use super::{LeafId, MerkleTree, MtObserver, NodeId};
+
+/// `Mt` stands for `MerkleTree`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MtSyncRequest {
+    /// Leaf count & root, the answer is [`MtSyncResponse::Summary`].
+    Summary,
+    /// Nodes `indexes` of level `lvl` (level `0` is leafs), the answer is [`MtSyncResponse::Hashes`].
+    Nodes { lvl: usize, indexes: Vec<usize> },
+    /// Leafs `range`, the answer is [`MtSyncResponse::Hashes`].
+    Leafs { range: Range<usize> },
+}
+
+/// `Mt` stands for `MerkleTree`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MtSyncResponse<Hash> {
+    /// `root` is [`None`] for an empty tree.
+    Summary { leaf_count: usize, root: Option<Hash> },
+    /// Requested hashes in the requested order.
+    Hashes(Vec<Hash>),
+    /// Request asks nodes that are not in the tree.
+    Invalid,
+}
+impl<Hash> MtSyncResponse<Hash> {
+    /// Amount of hashes in the response.
+    pub fn hash_count(&self) -> usize {
+        match self {
+            Self::Summary { root, .. } => root.is_some() as usize,
+            Self::Hashes(hashes) => hashes.len(),
+            Self::Invalid => 0,
+        }
+    }
+}
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Way to send [`MtSyncRequest`] to the remote replica & get its [`MtSyncResponse`].
+pub trait MtSyncTransport<Hash> {
+    type Error: std::error::Error;
+
+    fn request(&mut self, request: MtSyncRequest) -> Result<MtSyncResponse<Hash>, Self::Error>;
+}
+
+/// Reason why the sync failed.
+///
+/// The local tree is a valid tree after any error, but it can be synced partially.
+#[derive(thiserror::Error, Debug)]
+pub enum MtSyncError<E> {
+    #[error(transparent)]
+    Transport(E),
+    #[error("Remote replica rejected request as invalid.")]
+    Rejected,
+    #[error("Response doesn't match the request (wrong kind or wrong amount of hashes).")]
+    UnexpectedResponse,
+    #[error("Root after the sync is not equal to the remote root (the remote replica was changed during the sync?).")]
+    RootMismatch,
+}
+
+/// `Mt` stands for `MerkleTree`
+///
+/// What was done by [`MerkleTree::sync_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MtSyncReport {
+    pub requests: usize,
+    /// amount of hashes received from the remote replica
+    pub received_hashes: usize,
+    /// leafs that differed & were replaced
+    pub replaced: Vec<LeafId>,
+    /// amount of leafs that were missing locally
+    pub appended: usize,
+    /// amount of extra local leafs that were removed
+    pub removed: usize,
+}
+
+struct SyncSession<'t, T> {
+    transport: &'t mut T,
+    report: MtSyncReport,
+}
+impl<T> SyncSession<'_, T> {
+    fn request<Hash>(&mut self, request: MtSyncRequest) -> Result<MtSyncResponse<Hash>, MtSyncError<T::Error>>
+    where T: MtSyncTransport<Hash>
+    {
+        let response = self.transport.request(request).map_err(MtSyncError::Transport)?;
+        self.report.requests += 1;
+        self.report.received_hashes += response.hash_count();
+        match response {
+            MtSyncResponse::Invalid => Err(MtSyncError::Rejected),
+            response => Ok(response),
+        }
+    }
+
+    fn hashes<Hash>(&mut self, request: MtSyncRequest, expected_len: usize) -> Result<Vec<Hash>, MtSyncError<T::Error>>
+    where T: MtSyncTransport<Hash>
+    {
+        match self.request(request)? {
+            MtSyncResponse::Hashes(hashes) if hashes.len() == expected_len => Ok(hashes),
+            _ => Err(MtSyncError::UnexpectedResponse),
+        }
+    }
+}
+
+impl<Hash: Clone, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer> MerkleTree<Hash, Hasher, ARITY, Observer> {
+    /// Answer the sync request of another replica, see [module docs](self).
+    ///
+    /// # panic
+    /// * never, invalid requests get [`MtSyncResponse::Invalid`]
+    pub fn sync_respond(&self, request: &MtSyncRequest) -> MtSyncResponse<Hash> {
+        match request {
+            MtSyncRequest::Summary => MtSyncResponse::Summary {
+                leaf_count: self.leaf_count(),
+                root: (!self.is_empty()).then(|| self.root()),
+            },
+            MtSyncRequest::Nodes { lvl, indexes } => {
+                let lvl_len = self.get_lvl(*lvl).len();
+                if indexes.iter().any(|&index| index >= lvl_len) {
+                    return MtSyncResponse::Invalid
+                }
+                let nodes = indexes.iter().map(|&index| self.get_node_ref(NodeId { lvl: *lvl, index }).clone());
+                MtSyncResponse::Hashes(nodes.collect())
+            }
+            MtSyncRequest::Leafs { range } => {
+                match self.get_lvl(0).as_slice().get(range.clone()).map(<[_]>::to_vec) {
+                    Some(leafs) => MtSyncResponse::Hashes(leafs),
+                    None => MtSyncResponse::Invalid,
+                }
+            }
+        }
+    }
+}
+
+impl<Hash: Clone + Eq, Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer: MtObserver<Hash>> MerkleTree<Hash, Hasher, ARITY, Observer> {
+    /// Make the tree equal to the remote replica, see [module docs](self).
+    pub fn sync_with<T>(&mut self, transport: &mut T) -> Result<MtSyncReport, MtSyncError<T::Error>>
+    where T: MtSyncTransport<Hash>
+    {
+        let mut session = SyncSession { transport, report: MtSyncReport::default() };
+        let MtSyncResponse::Summary { leaf_count, root } = session.request(MtSyncRequest::Summary)? else {
+            return Err(MtSyncError::UnexpectedResponse)
+        };
+        if (leaf_count == 0) != root.is_none() {
+            return Err(MtSyncError::UnexpectedResponse)
+        }
+
+        while self.leaf_count() > leaf_count {
+            self.pop();
+            session.report.removed += 1;
+        }
+        if self.leaf_count() < leaf_count {
+            let range = self.leaf_count()..leaf_count;
+            let leafs = session.hashes(MtSyncRequest::Leafs { range: range.clone() }, range.len())?;
+            self.push_batched(leafs);
+            session.report.appended = range.len();
+        }
+        let Some(root) = root else {
+            return Ok(session.report)
+        };
+
+        // indexes of the nodes that differ on the current level
+        let mut differ = if self.root_ref() != &root { vec![0] } else { vec![] };
+        // the root of a single leaf tree is the leaf itself
+        if self.height() == 1 && !differ.is_empty() {
+            self.replace(root.clone(), LeafId::new(0));
+            session.report.replaced.push(LeafId::new(0));
+        }
+        for lvl in (0..self.height() - 1).rev() {
+            if differ.is_empty() { break }
+            let lvl_len = self.get_lvl(lvl).len();
+            let indexes: Vec<usize> = differ.iter()
+                .flat_map(|&parent| parent * ARITY..((parent + 1) * ARITY).min(lvl_len))
+                .collect();
+            let nodes = session.hashes(MtSyncRequest::Nodes { lvl, indexes: indexes.clone() }, indexes.len())?;
+
+            differ.clear();
+            for (index, node) in indexes.into_iter().zip(nodes) {
+                if self.get_node_ref(NodeId { lvl, index }) == &node { continue }
+                if lvl == 0 {
+                    self.replace(node, LeafId::new(index));
+                    session.report.replaced.push(LeafId::new(index));
+                } else {
+                    differ.push(index);
+                }
+            }
+        }
+
+        if self.root_ref() != &root {
+            return Err(MtSyncError::RootMismatch)
+        }
+        Ok(session.report)
+    }
+}
+
+/// Error of [`MtSyncChannel`]: the other side of the channel is dropped.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Sync channel is closed.")]
+pub struct MtSyncChannelClosed;
+
+/// `Mt` stands for `MerkleTree`
+///
+/// In-memory [`MtSyncTransport`] (for example for tests): requests are answered by
+/// [`MtSyncChannelServer::serve`] that is running in another thread.
+#[derive(Debug)]
+pub struct MtSyncChannel<Hash> {
+    requests: Sender<MtSyncRequest>,
+    responses: Receiver<MtSyncResponse<Hash>>,
+}
+impl<Hash> MtSyncChannel<Hash> {
+    pub fn new() -> (Self, MtSyncChannelServer<Hash>) {
+        let (request_sender, request_receiver) = mpsc::channel();
+        let (response_sender, response_receiver) = mpsc::channel();
+        let client = Self { requests: request_sender, responses: response_receiver };
+        let server = MtSyncChannelServer { requests: request_receiver, responses: response_sender };
+        (client, server)
+    }
+}
+impl<Hash> MtSyncTransport<Hash> for MtSyncChannel<Hash> {
+    type Error = MtSyncChannelClosed;
+
+    fn request(&mut self, request: MtSyncRequest) -> Result<MtSyncResponse<Hash>, Self::Error> {
+        self.requests.send(request).map_err(|_| MtSyncChannelClosed)?;
+        self.responses.recv().map_err(|_| MtSyncChannelClosed)
+    }
+}
+
+/// `Mt` stands for `MerkleTree`
+///
+/// Remote side of [`MtSyncChannel`].
+#[derive(Debug)]
+pub struct MtSyncChannelServer<Hash> {
+    requests: Receiver<MtSyncRequest>,
+    responses: Sender<MtSyncResponse<Hash>>,
+}
+impl<Hash: Clone> MtSyncChannelServer<Hash> {
+    /// Answer requests by the `tree` until the client is dropped.
+    pub fn serve<Hasher: ArityHasher<Hash, ARITY>, const ARITY: usize, Observer>(&self, tree: &MerkleTree<Hash, Hasher, ARITY, Observer>) {
+        while let Ok(request) = self.requests.recv() {
+            if self.responses.send(tree.sync_respond(&request)).is_err() {
+                return
+            }
+        }
+    }
+}
@@ -810,8 +810,8 @@ fn proof_test() {
             assert!(!proof.verify_data(data + 3, hasher));
             assert!(proof.verify(hash, hasher));
             assert!(proof.verify_data(data, hasher));
-            assert!(proof_owned.verify(hash, hasher));
-            assert!(proof_owned.verify_data(data, hasher));
+            assert!(proof_owned.verify_with_root(x_tree.root_ref(), hash, hasher));
+            assert!(proof_owned.verify_data_with_root(x_tree.root_ref(), data, hasher));
 
             for (_, data2) in vec.iter().copied().enumerate() {
                 if data == data2 { continue; }
@@ -819,8 +819,8 @@ fn proof_test() {
                 let hash2 = hasher.hash_data(data2);
                 assert!(!proof.verify(hash2, hasher));
                 assert!(!proof.verify_data(data2, hasher));
-                assert!(!proof_owned.verify(hash2, hasher));
-                assert!(!proof_owned.verify_data(data2, hasher));
+                assert!(!proof_owned.verify_with_root(x_tree.root_ref(), hash2, hasher));
+                assert!(!proof_owned.verify_data_with_root(x_tree.root_ref(), data2, hasher));
              }
         }
     }
@@ -852,6 +852,2210 @@ fn proof_test() {
     }
 }
 
+#[test]
+fn compact_proof_test() {
+    type Hasher = UnsecureHasher; // AddHasher;
+    let mut rng = rand::rng();
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let x_tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let mut hasher = Hasher::new();
+        let hasher = &mut hasher;
+
+        for (id, data) in vec.iter().copied().enumerate() {
+            let proof = x_tree.proof_compact(LeafId::new(id));
+            let proof_owned = x_tree.proof_owned(LeafId::new(id));
+            let hash = hasher.hash_data(data);
+
+            assert!(proof.verify(hash, hasher));
+            assert!(proof.verify_data(data, hasher));
+            assert!(!proof.verify_data(data + 3, hasher));
+
+            // lossless conversion: 
+            assert_eq!(proof_owned.to_compact().as_ref(), Some(&proof));
+            assert_eq!(proof.to_proof(hash, hasher), Some(proof_owned));
+
+            for data2 in vec.iter().copied() {
+                if data == data2 { continue; }
+                assert!(!proof.verify_data(data2, hasher));
+            }
+        }
+    }
+
+    let a = to_vec_u64("0 1");
+    test::<2>(&a);
+    test::<3>(&a);
+    test::<5>(&a);
+
+    let a = to_vec_u64("0");
+    test::<2>(&a);
+    test::<3>(&a);
+    test::<5>(&a);
+
+    let vecs = vec![
+        (1u64..=4).collect::<Vec<_>>(),
+        (1u64..=9).collect::<Vec<_>>(),
+        (1u64..=12).collect::<Vec<_>>(),
+        (1u64..=24).collect::<Vec<_>>(),
+        (1u64..=39).collect::<Vec<_>>(),
+        (1u64..=25).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+        (1u64..=27).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+        (1u64..=58).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+    ];
+    for vec in vecs {
+        test::<2>(&vec);
+        test::<3>(&vec);
+        test::<5>(&vec);        
+    }
+
+    // only true siblings: `ARITY - 1` per lvl for full tree & nothing for padding 
+    let tree = MerkleTree::<_, _, 2>::new_from_data(Hasher::new(), 0..16u64);
+    let proof = tree.proof_compact(LeafId::new(5));
+    assert_eq!(proof.siblings().len(), 4);
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), to_vec_u64("0 1 2 | 3 4 5 | 6 7 8 || 9"));
+    let proof = tree.proof_compact(LeafId::new(9));
+    assert_eq!(proof.siblings().len(), 1);
+
+    // equal siblings are not padding:
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), [7, 7, 7]);
+    for id in 0..3 {
+        let proof = tree.proof_owned(LeafId::new(id)).to_compact().unwrap();
+        assert_eq!(proof.siblings().len(), 2);
+        assert!(proof.verify_data(7, &mut Hasher::new()));
+    }
+    let tree = MerkleTree::<_, _, 2>::new_from_data(Hasher::new(), [5, 5, 5, 5]);
+    let mut hasher = Hasher::new();
+    let hash = hasher.hash_data(5);
+    for id in (0..4).map(LeafId::new) {
+        let compact = tree.proof_owned(id).to_compact().unwrap();
+        assert_eq!(compact, tree.proof_compact(id));
+        let proof = compact.to_proof(hash, &mut hasher).unwrap();
+        assert_eq!(proof.verify_position_detailed(tree.root_ref(), id, 4, hash, &mut hasher), Ok(()));
+    }
+
+    // tree size is needed to tell padding from equal siblings
+    let lvls = vec![vec![hash, hash], vec![hasher.hash_data(6), hasher.hash_data(6)]];
+    let without_size = crate::MtProof::<_, 2>::from_parts(lvls, vec![0, 0], tree.root()).unwrap();
+    assert_eq!(without_size.to_compact(), None);
+}
+
+#[test]
+fn proof_detailed_test() {
+    use crate::ProofError;
+    type Hasher = UnsecureHasher;
+
+    let vec = to_vec_u64("0 1 2 | 3 4 5 | 6 7 8 || 9 10");
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), vec.clone());
+    let mut hasher = Hasher::new();
+    for id in 0..vec.len() {
+        let proof_ref = tree.proof_ref(LeafId::new(id));
+        let proof = tree.proof_owned(LeafId::new(id));
+        let compact = tree.proof_compact(LeafId::new(id));
+
+        assert_eq!(proof_ref.verify_data_detailed(vec[id], &mut hasher), Ok(()));
+        assert_eq!(proof.verify_with_root_detailed(tree.root_ref(), hasher.hash_data(vec[id]), &mut hasher), Ok(()));
+        assert_eq!(compact.verify_data_detailed(vec[id], &mut hasher), Ok(()));
+
+        let wrong = vec[(id + 1) % vec.len()];
+        assert_eq!(proof_ref.verify_data_detailed(wrong, &mut hasher), Err(ProofError::WrongNode(0)));
+        assert_eq!(proof.verify_with_root_detailed(tree.root_ref(), hasher.hash_data(wrong), &mut hasher), Err(ProofError::WrongNode(0)));
+        assert_eq!(compact.verify_data_detailed(wrong, &mut hasher), Err(ProofError::RootMismatch));
+
+        // the same diagnostics against trusted root
+        let root = tree.root_ref();
+        assert_eq!(proof.verify_data_detailed(vec[id], &mut hasher), Ok(()));
+        assert_eq!(proof.verify_data_with_root_detailed(root, vec[id], &mut hasher), Ok(()));
+        assert_eq!(proof_ref.verify_data_with_root_detailed(root, vec[id], &mut hasher), Ok(()));
+        assert_eq!(compact.verify_data_with_root_detailed(root, vec[id], &mut hasher), Ok(()));
+        assert_eq!(proof.clone().detach().verify_data_with_root_detailed(root, vec[id], &mut hasher), Ok(()));
+        assert_eq!(proof.verify_data_with_root_detailed(root, wrong, &mut hasher), Err(ProofError::WrongNode(0)));
+        assert_eq!(proof_ref.verify_data_with_root_detailed(root, wrong, &mut hasher), Err(ProofError::WrongNode(0)));
+        assert_eq!(compact.verify_data_with_root_detailed(root, wrong, &mut hasher), Err(ProofError::RootMismatch));
+        assert_eq!(proof.verify_data_with_root_detailed(&(root + 1), vec[id], &mut hasher), Err(ProofError::RootMismatch));
+
+        let range = tree.range_proof(LeafId::new(id)..LeafId::new(id + 1));
+        assert_eq!(range.verify_data_with_root_detailed(root, [vec[id]], &mut hasher), Ok(()));
+        assert_eq!(range.verify_data_with_root_detailed(&(root + 1), [vec[id]], &mut hasher), Err(ProofError::RootMismatch));
+    }
+}
+
+#[test]
+fn proof_with_root_test() {
+    use crate::ProofError;
+    type Hasher = UnsecureHasher;
+
+    let vec = to_vec_u64("0 1 2 | 3 4 5 | 6 7 8 || 9 10");
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), vec.clone());
+    let trusted_root = tree.root();
+
+    // attacker builds own tree with the leaf, so proof is consistent with its own root:
+    let forged = to_vec_u64("0 1 2 | 3 4 5 | 6 7 8 || 9 10 | 11");
+    let forged = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), forged);
+
+    let mut hasher = Hasher::new();
+    for id in 0..vec.len() {
+        let id = LeafId::new(id);
+        let data = vec[id.index()];
+
+        let proof = tree.proof_owned(id);
+        let proof_ref = tree.proof_ref(id);
+        let compact = tree.proof_compact(id);
+        let detached = tree.proof_detached(id);
+        assert!(proof.verify_data_with_root(&trusted_root, data, &mut hasher));
+        assert!(proof_ref.verify_data_with_root(&trusted_root, data, &mut hasher));
+        assert!(compact.verify_with_root(&trusted_root, hasher.hash_data(data), &mut hasher));
+        assert!(detached.verify_data_with_root(&trusted_root, data, &mut hasher));
+        assert_eq!(detached.clone().attach(trusted_root), proof);
+        assert_eq!(proof.clone().detach(), detached);
+
+        let forged_proof = forged.proof_owned(id);
+        // the embedded root is chosen by the attacker
+        assert!(forged_proof.verify_data(data, &mut hasher));
+        assert!(!forged_proof.verify_data_with_root(&trusted_root, data, &mut hasher));
+        assert!(!forged.proof_ref(id).verify_data_with_root(&trusted_root, data, &mut hasher));
+        assert!(!forged.proof_detached(id).verify_data_with_root(&trusted_root, data, &mut hasher));
+        assert_eq!(
+            forged.proof_compact(id).verify_with_root_detailed(&trusted_root, hasher.hash_data(data), &mut hasher),
+            Err(ProofError::RootMismatch),
+        );
+    }
+}
+
+#[test]
+fn proof_position_test() {
+    use crate::ProofError;
+    type Hasher = UnsecureHasher;
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let root = tree.root();
+        let size = vec.len();
+        let mut hasher = Hasher::new();
+
+        for (id, data) in vec.iter().copied().enumerate() {
+            let id = LeafId::new(id);
+            let hash = hasher.hash_data(data);
+            let proof_ref = tree.proof_ref(id);
+            let proof = tree.proof_owned(id);
+            let detached = tree.proof_detached(id);
+            let compact = tree.proof_compact(id);
+
+            assert_eq!(proof_ref.leaf_index(), id);
+            assert_eq!(proof.leaf_index(), Some(id));
+            assert_eq!(detached.leaf_index(), Some(id));
+            assert_eq!(compact.leaf_index(), id);
+            assert_eq!(proof_ref.tree_size(), size);
+            assert_eq!(proof.tree_size(), Some(size));
+            assert_eq!(detached.tree_size(), Some(size));
+            assert_eq!(compact.tree_size(), Some(size));
+
+            assert!(proof_ref.verify_position(&root, id, size, hash, &mut hasher));
+            assert!(proof.verify_position(&root, id, size, hash, &mut hasher));
+            assert!(detached.verify_position(&root, id, size, hash, &mut hasher));
+            assert!(compact.verify_position(&root, id, size, hash, &mut hasher));
+
+            let err = Err(ProofError::TreeSizeMismatch { expected: size + 1, actual: size });
+            assert_eq!(proof.verify_position_detailed(&root, id, size + 1, hash, &mut hasher), err);
+            assert_eq!(proof_ref.verify_position_detailed(&root, id, size + 1, hash, &mut hasher), err);
+
+            let other_id = LeafId::new((id.index() + 1) % size);
+            if other_id != id {
+                let err = Err(ProofError::LeafIndexMismatch { expected: other_id.index(), actual: Some(id.index()) });
+                assert_eq!(proof.verify_position_detailed(&root, other_id, size, hash, &mut hasher), err);
+                assert_eq!(compact.verify_position_detailed(&root, other_id, size, hash, &mut hasher), err);
+            }
+        }
+
+        let id = LeafId::new(size);
+        let hash = hasher.hash_data(vec[size - 1]);
+        let err = Err(ProofError::LeafOutOfTree { index: size, tree_size: size });
+        assert_eq!(tree.proof_ref(LeafId::new(size - 1)).verify_position_detailed(&root, id, size, hash, &mut hasher), err);
+    }
+
+    let vecs = vec![
+        to_vec_u64("0"),
+        to_vec_u64("0 1"),
+        (1u64..=4).collect::<Vec<_>>(),
+        (1u64..=9).collect::<Vec<_>>(),
+        (1u64..=12).collect::<Vec<_>>(),
+        (1u64..=24).collect::<Vec<_>>(),
+        (1u64..=39).collect::<Vec<_>>(),
+    ];
+    for vec in vecs {
+        test::<2>(&vec);
+        test::<3>(&vec);
+        test::<5>(&vec);
+    }
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn proof_position_padding_test() {
+    use crate::{MtProof, ProofError};
+    use serde_json::{json, Value};
+    type Hasher = UnsecureHasher;
+
+    // lvl 0: || 0 1 | 2 _ ||
+    let vec = to_vec_u64("0 1 | 2");
+    let tree = MerkleTree::<_, _, 2>::new_from_data(Hasher::new(), vec.clone());
+    let root = tree.root();
+    let hash = Hasher::hash_data_static(2u64);
+    let mut hasher = Hasher::new();
+
+    // proof through padding duplicate of leaf `2` for non-existent leaf `3`:
+    let mut forged = serde_json::to_value(tree.proof_owned(LeafId::new(2))).unwrap();
+    forged["tree_lvl_path"][0] = json!(1);
+    let with_size: MtProof<u64, 2> = serde_json::from_value(forged.clone()).unwrap();
+    forged["tree_lvl_path"] = json!([1, 1]);
+    forged["tree_size"] = Value::Null;
+    let without_size: MtProof<u64, 2> = serde_json::from_value(forged).unwrap();
+
+    assert_eq!(with_size.leaf_index(), Some(LeafId::new(3)));
+    assert_eq!(with_size.verify_with_root_detailed(&root, hash, &mut hasher), Ok(()));
+
+    let err = Err(ProofError::LeafOutOfTree { index: 3, tree_size: 3 });
+    assert_eq!(with_size.verify_position_detailed(&root, LeafId::new(3), 3, hash, &mut hasher), err);
+    let err = Err(ProofError::LeafIndexMismatch { expected: 2, actual: Some(3) });
+    assert_eq!(with_size.verify_position_detailed(&root, LeafId::new(2), 3, hash, &mut hasher), err);
+    let err = Err(ProofError::LeafIndexMismatch { expected: 2, actual: Some(3) });
+    assert_eq!(without_size.verify_position_detailed(&root, LeafId::new(2), 3, hash, &mut hasher), err);
+
+    // tree size is not committed by root, but it's checked with trusted one: 
+    let err = Err(ProofError::TreeSizeMismatch { expected: 4, actual: 3 });
+    assert_eq!(with_size.verify_position_detailed(&root, LeafId::new(3), 4, hash, &mut hasher), err);
+    
+    // old proofs (without tree size) are still deserializable:
+    let mut old = serde_json::to_value(tree.proof_owned(LeafId::new(1))).unwrap();
+    old.as_object_mut().unwrap().remove("tree_size");
+    let old: MtProof<u64, 2> = serde_json::from_value(old).unwrap();
+    assert_eq!(old.tree_size(), None);
+    assert!(old.verify_position(&root, LeafId::new(1), 3, Hasher::hash_data_static(1u64), &mut hasher));
+}
+
+#[test]
+fn proof_parts_test() {
+    use crate::{MtProof, ProofError};
+    type Hasher = UnsecureHasher;
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let mut hasher = Hasher::new();
+
+        for (id, data) in vec.iter().copied().enumerate() {
+            let id = LeafId::new(id);
+            let proof_ref = tree.proof_ref(id);
+            let proof = tree.proof_owned(id);
+            assert_eq!(proof_ref.root(), tree.root_ref());
+            assert_eq!(proof.root(), tree.root_ref());
+            assert_eq!(proof_ref.path(), proof.path());
+            assert_eq!(proof_ref.lvl_count(), tree.height() - 1);
+            assert_eq!(proof.lvl_count(), tree.height() - 1);
+
+            for ((lvl, position, group), (lvl_b, position_b, group_b)) in proof_ref.lvls().zip(proof.lvls()) {
+                assert_eq!((lvl, position), (lvl_b, position_b));
+                assert_eq!(group_b.len(), ARITY);
+                assert_eq!(group, &group_b[..group.len()]);
+                assert_eq!(proof_ref.lvl_nodes(lvl), Some(group));
+                assert_eq!(proof.lvl_nodes(lvl), Some(group_b));
+                let node = tree.get_node(tree.node_id_by_parent_of_leaf(id, lvl));
+                assert_eq!(group[position], node);
+            }
+            assert_eq!(proof.lvl_nodes(proof.lvl_count()), None);
+
+            // re-encode proof from parts:
+            let lvls = proof_ref.lvls().map(|(_, _, group)|group.to_vec()).collect();
+            let rebuilt = MtProof::<_, ARITY>::from_parts(lvls, proof_ref.path().to_vec(), *proof_ref.root()).unwrap();
+            assert_eq!(rebuilt.tree_size(), None);
+            assert_eq!(rebuilt.lvls().collect::<Vec<_>>(), proof.lvls().collect::<Vec<_>>());
+            assert!(rebuilt.verify_data_with_root(tree.root_ref(), data, &mut hasher));
+            assert!(rebuilt.verify_position(tree.root_ref(), id, vec.len(), hasher.hash_data(data), &mut hasher));
+        }
+    }
+
+    for vec in [to_vec_u64("0"), to_vec_u64("0 1"), (1u64..=12).collect(), (1u64..=39).collect()] {
+        test::<2>(&vec);
+        test::<3>(&vec);
+        test::<5>(&vec);
+    }
+
+    let err = MtProof::<u64, 3>::from_parts(vec![vec![1, 2]], vec![0, 0], 3);
+    assert_eq!(err, Err(ProofError::Truncated { path_lvls: 2, node_lvls: 1 }));
+    let err = MtProof::<u64, 3>::from_parts(vec![vec![1, 2], vec![3]], vec![0], 3);
+    assert_eq!(err, Err(ProofError::WrongNodeCount { expected: 1, actual: 2 }));
+    let err = MtProof::<u64, 3>::from_parts(vec![vec![1, 2, 3, 4]], vec![0], 3);
+    assert_eq!(err, Err(ProofError::WrongNodeCount { expected: 3, actual: 4 }));
+    let err = MtProof::<u64, 3>::from_parts(vec![vec![]], vec![0], 3);
+    assert_eq!(err, Err(ProofError::WrongNodeCount { expected: 3, actual: 0 }));
+    let err = MtProof::<u64, 3>::from_parts(vec![vec![1, 2]], vec![2], 3);
+    assert_eq!(err, Err(ProofError::PathIndexOutOfRange { lvl: 0, index: 2, len: 2 }));
+}
+
+#[test]
+fn range_proof_test() {
+    use crate::ProofError;
+    type Hasher = UnsecureHasher;
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let leafs = (0..vec.len()).map(|id|tree.get_node(NodeId { lvl: 0, index: id })).collect::<Vec<_>>();
+        let tree_size = vec.len();
+        let mut hasher = Hasher::new();
+
+        for start in 0..tree_size {
+            for end in (start + 1)..=tree_size {
+                let range = LeafId::new(start)..LeafId::new(end);
+                let proof = tree.range_proof(range.clone());
+                let range_leafs = &leafs[start..end];
+                assert_eq!(proof.range(), range);
+                assert_eq!(proof.tree_size(), tree_size);
+                assert_eq!(proof.root(), tree.root_ref());
+
+                assert!(proof.verify(range_leafs, &mut hasher));
+                assert!(proof.verify_with_root(tree.root_ref(), range_leafs, &mut hasher));
+                assert!(proof.verify_data_with_root(tree.root_ref(), vec[start..end].iter().copied(), &mut hasher));
+                assert!(proof.verify_range(tree.root_ref(), range.clone(), tree_size, range_leafs, &mut hasher));
+
+                // root of another tree
+                assert_eq!(
+                    proof.verify_with_root_detailed(&(tree.root() + 1), range_leafs, &mut hasher),
+                    Err(ProofError::RootMismatch)
+                );
+
+                // changed leaf
+                for changed in 0..range_leafs.len() {
+                    let mut range_leafs = range_leafs.to_vec();
+                    range_leafs[changed] += 1;
+                    assert_eq!(proof.verify_detailed(&range_leafs, &mut hasher), Err(ProofError::RootMismatch));
+                }
+
+                // wrong amount of leafs
+                assert_eq!(
+                    proof.verify_detailed(&leafs[start..end - 1], &mut hasher),
+                    Err(ProofError::WrongNodeCount { expected: end - start, actual: end - start - 1 })
+                );
+
+                // unexpected range or tree size
+                let expected = start..end + 1;
+                assert_eq!(
+                    proof.verify_range_detailed(tree.root_ref(), LeafId::new(start)..LeafId::new(end + 1), tree_size, range_leafs, &mut hasher),
+                    Err(ProofError::RangeMismatch { expected, actual: start..end })
+                );
+                assert_eq!(
+                    proof.verify_range_detailed(tree.root_ref(), range.clone(), tree_size + 1, range_leafs, &mut hasher),
+                    Err(ProofError::TreeSizeMismatch { expected: tree_size + 1, actual: tree_size })
+                );
+
+                // same leafs but in the middle of the bigger tree
+                let mut bigger = vec.to_vec();
+                bigger.push(99);
+                let bigger = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), bigger);
+                assert!(!proof.verify_with_root(bigger.root_ref(), range_leafs, &mut hasher));
+            }
+        }
+    }
+
+    for vec in [to_vec_u64("0"), to_vec_u64("0 1"), (1u64..=12).collect(), (1u64..=28).collect()] {
+        test::<2>(&vec);
+        test::<3>(&vec);
+        test::<5>(&vec);
+    }
+
+    // range proof of single leaf agrees with usual proof
+    let vec: Vec<u64> = (1..=39).collect();
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), vec.clone());
+    let mut hasher = Hasher::new();
+    for (id, data) in vec.into_iter().enumerate() {
+        let leaf = Hasher::hash_data_static(data);
+        let proof = tree.range_proof(LeafId::new(id)..LeafId::new(id + 1));
+        assert!(proof.verify_with_root(tree.root_ref(), &[leaf], &mut hasher));
+        assert!(tree.proof_owned(LeafId::new(id)).verify_with_root(tree.root_ref(), leaf, &mut hasher));
+    }
+}
+
+#[test]
+fn node_proof_test() {
+    use crate::ProofError;
+    type Hasher = UnsecureHasher;
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let tree_size = vec.len();
+        let mut hasher = Hasher::new();
+
+        for lvl in 0..tree.height() {
+            let shards = tree.split(lvl);
+            assert_eq!(shards.len(), tree.get_lvl(lvl).len());
+
+            for (index, shard) in shards.iter().enumerate() {
+                let node = NodeId { lvl, index };
+                let proof = tree.node_proof(node);
+                let node_hash = tree.get_node(node);
+                assert_eq!(proof.node(), node);
+                assert_eq!(proof.tree_size(), Some(tree_size));
+
+                assert!(proof.verify_with_root(tree.root_ref(), node_hash, &mut hasher));
+                assert!(proof.verify_position(tree.root_ref(), node, tree_size, node_hash, &mut hasher));
+                assert!(proof.verify_subtree(tree.root_ref(), shard, &mut hasher));
+                assert!(proof.verify_subtree_position(tree.root_ref(), node, tree_size, shard, &mut hasher));
+                assert!(!proof.verify_with_root(tree.root_ref(), node_hash + 1, &mut hasher));
+
+                // leaf proof is node proof of level 0
+                if lvl == 0 {
+                    assert_eq!(proof.proof(), &tree.proof_owned(LeafId::new(index)));
+                }
+
+                // another shard
+                if let Some(other) = shards.get(index + 1) {
+                    assert!(!proof.verify_subtree(tree.root_ref(), other, &mut hasher));
+                    let other_node = NodeId { lvl, index: index + 1 };
+                    assert_eq!(
+                        proof.verify_position_detailed(tree.root_ref(), other_node, tree_size, node_hash, &mut hasher),
+                        Err(ProofError::NodeMismatch { expected: other_node, actual: node })
+                    );
+                }
+
+                // subtree is higher than the node
+                if lvl + 1 < tree.height() {
+                    assert_eq!(
+                        proof.verify_subtree_detailed(tree.root_ref(), &tree, &mut hasher),
+                        Err(ProofError::SubtreeTooHigh { lvl, height: tree.height() })
+                    );
+                }
+            }
+        }
+
+        // node proof of root
+        let root_node = NodeId { lvl: tree.height() - 1, index: 0 };
+        let proof = tree.node_proof(root_node);
+        assert!(proof.proof().lvls().next().is_none());
+        assert!(proof.verify_subtree_position(tree.root_ref(), root_node, tree_size, &tree, &mut hasher));
+
+        // node out of the tree with claimed size
+        #[cfg(feature = "serde_json")]
+        {
+            let mut json = serde_json::to_value(tree.node_proof(root_node)).unwrap();
+            json["node"]["lvl"] = tree.height().into();
+            let proof: crate::MtNodeProof<u64, ARITY> = serde_json::from_value(json).unwrap();
+            assert_eq!(
+                proof.verify_position_detailed(tree.root_ref(), proof.node(), tree_size, tree.root(), &mut hasher),
+                Err(ProofError::NodeOutOfTree { lvl: tree.height(), index: 0, lvl_len: 0 })
+            );
+        }
+    }
+
+    for vec in [to_vec_u64("0"), to_vec_u64("0 1"), (1u64..=12).collect(), (1u64..=28).collect(), (1u64..=39).collect()] {
+        test::<2>(&vec);
+        test::<3>(&vec);
+        test::<5>(&vec);
+    }
+}
+
+#[test]
+fn compose_proof_test() {
+    use crate::{MtProof, ProofError};
+    type Hasher = UnsecureHasher;
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let tree_size = vec.len();
+        let mut hasher = Hasher::new();
+
+        for lvl in 0..tree.height() {
+            let shards = tree.split(lvl);
+            let merged = MerkleTree::new_merged(shards.clone()).unwrap();
+            assert!(merged.eq_full(&tree));
+
+            let shard_cap = ARITY.pow(lvl as u32);
+            for (id, data) in vec.iter().copied().enumerate() {
+                let (shard_index, local_id) = (id / shard_cap, id % shard_cap);
+                let node = NodeId { lvl, index: shard_index };
+                let global = tree.proof_owned(LeafId::new(id));
+                let local = shards[shard_index].proof_owned(LeafId::new(local_id));
+
+                assert_eq!(global.truncate(lvl), Ok(local.clone()));
+                let composed = local.clone().compose(&tree.node_proof(node), &mut hasher);
+                assert_eq!(composed, Ok(global.clone()));
+                let composed = composed.unwrap();
+                assert!(composed.verify_position(merged.root_ref(), LeafId::new(id), tree_size, Hasher::hash_data_static(data), &mut hasher));
+
+                // node proof of another shard
+                if let Some(other) = (shard_index + 1 < shards.len()).then(|| NodeId { lvl, index: shard_index + 1 }) {
+                    let err = local.clone().compose(&tree.node_proof(other), &mut hasher);
+                    let other_size = shards[shard_index + 1].leaf_count();
+                    if other_size == shards[shard_index].leaf_count() {
+                        assert_eq!(err, Err(ProofError::WrongNode(lvl)));
+                    } else {
+                        assert_eq!(err, Err(ProofError::TreeSizeMismatch { expected: other_size, actual: shard_cap }));
+                    }
+                }
+            }
+        }
+
+        // shard is higher than node
+        if tree.height() > 1 {
+            let proof = tree.proof_owned(LeafId::new(0));
+            let node_proof = tree.node_proof(NodeId { lvl: 0, index: 0 });
+            assert_eq!(
+                proof.clone().compose(&node_proof, &mut hasher),
+                Err(ProofError::SubtreeTooHigh { lvl: 0, height: tree.height() })
+            );
+            assert_eq!(
+                proof.truncate(tree.height()), 
+                Err(ProofError::NodeOutOfTree { lvl: tree.height(), index: 0, lvl_len: 0 })
+            );
+        }
+
+        // without tree size it's unknown how to truncate
+        let proof = tree.proof_owned(LeafId::new(0));
+        let lvls = proof.lvls().map(|(_, _, group)|group.to_vec()).collect();
+        let proof = MtProof::<_, ARITY>::from_parts(lvls, proof.path().to_vec(), *proof.root()).unwrap();
+        assert_eq!(proof.truncate(0), Err(ProofError::UnknownTreeSize));
+    }
+
+    for vec in [to_vec_u64("0"), to_vec_u64("0 1"), (1u64..=12).collect(), (1u64..=28).collect(), (1u64..=39).collect()] {
+        test::<2>(&vec);
+        test::<3>(&vec);
+        test::<5>(&vec);
+    }
+}
+
+#[test]
+fn all_proofs_test() {
+    use crate::MtProofSet;
+    type Hasher = UnsecureHasher;
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let proof_set = tree.all_proofs();
+        let mut hasher = Hasher::new();
+        assert_eq!(proof_set.tree_size(), vec.len());
+        assert_eq!(tree.proofs_iter().len(), vec.len());
+
+        let proofs = tree.proofs_iter().zip(proof_set.iter()).zip(vec.iter().copied());
+        for (id, ((proof, set_proof), data)) in proofs.enumerate() {
+            let id = LeafId::new(id);
+            let expected = tree.proof_owned(id);
+            assert_eq!(proof.clone().to_owned(), expected);
+            assert_eq!(set_proof.to_owned(), expected);
+            assert_eq!(proof_set.proof_owned(id), expected);
+            assert!(proof.verify_position(tree.root_ref(), id, vec.len(), Hasher::hash_data_static(data), &mut hasher));
+        }
+
+        #[cfg(feature = "serde_json")]
+        {
+            let json = serde_json::to_string(&proof_set).unwrap();
+            let proof_set_de: MtProofSet<u64, ARITY> = serde_json::from_str(&json).unwrap();
+            assert_eq!(proof_set, proof_set_de);
+        }
+    }
+
+    for vec in [to_vec_u64("0"), to_vec_u64("0 1"), (1u64..=12).collect(), (1u64..=28).collect(), (1u64..=125).collect()] {
+        test::<2>(&vec);
+        test::<3>(&vec);
+        test::<5>(&vec);
+    }
+
+    let tree = MerkleTree::<u64, _, 3>::new_minimal(Hasher::new());
+    assert!(tree.proofs_iter().next().is_none());
+    let proof_set: MtProofSet<_, 3> = tree.all_proofs();
+    assert!(proof_set.is_empty());
+    assert_eq!(proof_set.iter().len(), 0);
+}
+
+#[test]
+fn verify_batch_test() {
+    use crate::{MtProof, MtHasher, MtBatchVerifier, ProofError};
+    type Hasher = UnsecureHasher;
+
+    /// Hasher that counts amount of calculated hashes.
+    struct CountingHasher {
+        inner: Hasher,
+        finished: usize,
+    }
+    impl MtHasher<u64> for CountingHasher {
+        fn hash_one_ref(&mut self, hash: &u64) {
+            self.inner.hash_one_ref(hash);
+        }
+        fn finish(&mut self) -> u64 {
+            self.finished += 1;
+            self.inner.finish()
+        }
+        fn is_the_same(&self, _: &Self) -> bool {
+            true
+        }
+    }
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let leafs: Vec<_> = vec.iter().map(|&x|Hasher::hash_data_static(x)).collect();
+        let proofs: Vec<_> = tree.proofs_iter().map(|proof|proof.to_owned()).collect();
+        let mut hasher = CountingHasher { inner: Hasher::new(), finished: 0 };
+
+        let results = MtProof::verify_batch(tree.root_ref(), proofs.iter().zip(leafs.iter().copied()), &mut hasher);
+        assert!(results.iter().all(Result::is_ok));
+        // each group is hashed only once
+        let group_count: usize = (1..tree.height()).map(|lvl|tree.get_lvl(lvl).len()).sum();
+        assert_eq!(hasher.finished, group_count);
+
+        // the same proofs again: all nodes are already authenticated
+        let mut verifier = MtBatchVerifier::new(tree.root());
+        for (proof, leaf) in proofs.iter().zip(leafs.iter().copied()) {
+            assert!(verifier.verify(proof, leaf, &mut hasher));
+        }
+        hasher.finished = 0;
+        for (proof, leaf) in proofs.iter().zip(leafs.iter().copied()) {
+            assert!(verifier.verify(proof, leaf, &mut hasher));
+        }
+        assert_eq!(hasher.finished, 0);
+
+        // valid & invalid proofs are mixed
+        let mut wrong_leafs = leafs.clone();
+        for leaf in wrong_leafs.iter_mut().step_by(3) {
+            *leaf += 1;
+        }
+        let results = MtProof::verify_batch(tree.root_ref(), proofs.iter().zip(wrong_leafs.iter().copied()), &mut hasher);
+        for ((proof, leaf), result) in proofs.iter().zip(wrong_leafs.iter().copied()).zip(results) {
+            assert_eq!(result.is_ok(), proof.verify_with_root(tree.root_ref(), leaf, &mut hasher));
+        }
+
+        // proofs of another tree 
+        let other = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.iter().map(|x|x + 1));
+        let other_leafs = vec.iter().map(|&x|Hasher::hash_data_static(x + 1));
+        let results = MtProof::verify_batch(tree.root_ref(), other.proofs_iter().map(|proof|proof.to_owned()).zip(other_leafs), &mut hasher);
+        assert_eq!(results[0], Err(ProofError::RootMismatch));
+        assert!(results.iter().all(Result::is_err));
+
+        // short proof (of the tree of root's children) before proofs of leafs: results don't depend on the order
+        if tree.height() > 2 {
+            let short = MerkleTree::<_, _, ARITY>::new_from_leafs(Hasher::new(), tree.get_lvl(tree.height() - 2).to_vec().clone());
+            assert_eq!(short.root(), tree.root());
+            let short_proof = short.proof_owned(LeafId::new(0));
+            let short_leaf = short.get_lvl(0).to_vec()[0];
+            let batch = std::iter::once((&short_proof, short_leaf)).chain(proofs.iter().zip(leafs.iter().copied()));
+            let results = MtProof::verify_batch(tree.root_ref(), batch, &mut hasher);
+            assert!(results.iter().all(Result::is_ok));
+
+            let mut verifier = MtBatchVerifier::with_tree_size(tree.root(), tree.leaf_count());
+            assert!(verifier.verify(&short_proof, short_leaf, &mut hasher));
+            assert_eq!(verifier.authenticated_count(), 0);
+            for (proof, leaf) in proofs.iter().zip(leafs.iter().copied()) {
+                assert!(verifier.verify(proof, leaf, &mut hasher));
+            }
+            // padding is not remembered
+            assert_eq!(verifier.authenticated_count(), (0..tree.height() - 1).map(|lvl|tree.get_lvl(lvl).len()).sum::<usize>());
+        }
+    }
+
+    for vec in [to_vec_u64("0"), to_vec_u64("0 1"), (1u64..=12).collect(), (1u64..=28).collect(), (1u64..=125).collect()] {
+        test::<2>(&vec);
+        test::<3>(&vec);
+        test::<5>(&vec);
+    }
+}
+
+#[test]
+fn proof_update_test() {
+    use crate::ProofError;
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    fn test<const ARITY: usize>(vec: &[u64], rng: &mut impl Rng) {
+        let mut tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let mut proofs: Vec<_> = tree.proofs_iter().map(|proof|proof.to_owned()).collect();
+
+        for _ in 0..40 {
+            let changed = match rng.random_range(0..5) {
+                0 => {
+                    let id = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                    tree.replace(rng.next_u64(), id);
+                    id..LeafId::new(id.index() + 1)
+                }
+                1 => {
+                    let id = tree.push(rng.next_u64());
+                    id..LeafId::new(id.index() + 1)
+                }
+                2 => {
+                    let batch: Vec<_> = (0..rng.random_range(1..20)).map(|_|rng.next_u64()).collect();
+                    tree.push_batched(batch)
+                }
+                3 => {
+                    let start = rng.random_range(0..tree.leaf_count());
+                    let batch: Vec<_> = (0..rng.random_range(1..20)).map(|_|rng.next_u64()).collect();
+                    tree.replace_batched(batch, LeafId::new(start))
+                }
+                _ if tree.leaf_count() > 1 => {
+                    tree.pop();
+                    let last = tree.leaf_count() - 1;
+                    LeafId::new(last)..LeafId::new(last + 1)
+                }
+                _ => continue,
+            };
+
+            let change = tree.change_record(changed);
+            assert_eq!(change.root(), tree.root_ref());
+            assert_eq!(change.tree_size(), tree.leaf_count());
+
+            for (id, proof) in proofs.iter_mut().enumerate() {
+                let result = proof.update(&change);
+                if id < tree.leaf_count() {
+                    assert_eq!(result, Ok(()));
+                    assert_eq!(proof, &tree.proof_owned(LeafId::new(id)));
+                } else {
+                    assert_eq!(result, Err(ProofError::LeafOutOfTree { index: id, tree_size: tree.leaf_count() }));
+                }
+            }
+            proofs.truncate(tree.leaf_count());
+            proofs.extend((proofs.len()..tree.leaf_count()).map(|id|tree.proof_owned(LeafId::new(id))));
+        }
+    }
+
+    for vec in [to_vec_u64("0"), to_vec_u64("0 1"), (1u64..=12).collect(), (1u64..=28).collect()] {
+        test::<2>(&vec, &mut rng);
+        test::<3>(&vec, &mut rng);
+        test::<5>(&vec, &mut rng);
+    }
+
+    // change record doesn't contain all changed nodes
+    let mut tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), 1u64..=8);
+    let mut proof = tree.proof_owned(LeafId::new(6));
+    let stale_proof = proof.clone();
+    tree.push_batched([9, 10]);
+    let change = tree.change_record(LeafId::new(9)..LeafId::new(10));
+    assert_eq!(proof.update(&change), Err(ProofError::ChangeMissingNode { lvl: 0, index: 8 }));
+    assert_eq!(proof, stale_proof);
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn range_proof_serde_test() {
+    use crate::{MtRangeProof, ProofError};
+    type Hasher = UnsecureHasher;
+
+    let vec: Vec<u64> = (1..=23).collect();
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), vec.clone());
+    let leafs: Vec<_> = vec[4..17].iter().map(|&x|Hasher::hash_data_static(x)).collect();
+    let mut hasher = Hasher::new();
+
+    let proof = tree.range_proof(LeafId::new(4)..LeafId::new(17));
+    let json = serde_json::to_value(&proof).unwrap();
+    let proof: MtRangeProof<u64, 3> = serde_json::from_value(json.clone()).unwrap();
+    assert!(proof.verify_with_root(tree.root_ref(), &leafs, &mut hasher));
+
+    let tampered = |field: &str, value: serde_json::Value|{
+        let mut json = json.clone();
+        json[field] = value;
+        serde_json::from_value::<MtRangeProof<u64, 3>>(json).unwrap()
+    };
+
+    let proof = tampered("end", 4.into());
+    assert_eq!(proof.verify_with_root_detailed(tree.root_ref(), &leafs, &mut hasher), Err(ProofError::EmptyRange));
+
+    let proof = tampered("tree_size", 16.into());
+    assert_eq!(
+        proof.verify_with_root_detailed(tree.root_ref(), &leafs, &mut hasher), 
+        Err(ProofError::LeafOutOfTree { index: 16, tree_size: 16 })
+    );
+
+    // claimed tree is higher than the proof
+    let proof = tampered("tree_size", 100.into());
+    assert_eq!(
+        proof.verify_with_root_detailed(tree.root_ref(), &leafs, &mut hasher), 
+        Err(ProofError::WrongLvlCount { expected: 5, actual: 3 })
+    );
+
+    let mut right = json["right"].clone();
+    right[0].as_array_mut().unwrap().pop();
+    let proof = tampered("right", right);
+    assert_eq!(
+        proof.verify_with_root_detailed(tree.root_ref(), &leafs, &mut hasher), 
+        Err(ProofError::WrongNodeCount { expected: 1, actual: 0 })
+    );
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn proof_detailed_serde_test() {
+    use crate::{MtProof, ProofError};
+    use serde_json::{json, Value};
+    type Hasher = UnsecureHasher;
+
+    let vec = to_vec_u64("0 1 2 | 3 4 5 | 6 7 8 || 9 10");
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), vec.clone());
+    let proof = tree.proof_owned(LeafId::new(4));
+    let proof = serde_json::to_value(&proof).unwrap();
+
+    let verify = |f: &dyn Fn(&mut Value)| {
+        let mut proof = proof.clone();
+        f(&mut proof);
+        let proof: MtProof<u64, 3> = serde_json::from_value(proof).unwrap();
+        proof.verify_data_detailed(vec[4], &mut Hasher::new())
+    };
+
+    assert_eq!(verify(&|_|()), Ok(()));
+    assert_eq!(verify(&|p|p["root"] = json!(7)), Err(ProofError::RootMismatch));
+    assert_eq!(verify(&|p|p["tree_lvl_nodes"][4] = json!(7)), Err(ProofError::WrongNode(1)));
+    assert_eq!(verify(&|p|p["tree_lvl_nodes"][3] = json!(7)), Err(ProofError::WrongNode(2)));
+    assert_eq!(verify(&|p|p["tree_lvl_nodes"][0] = json!(7)), Err(ProofError::WrongNode(1)));
+    assert_eq!(
+        verify(&|p|p["tree_lvl_path"][1] = json!(3)), 
+        Err(ProofError::PathIndexOutOfRange { lvl: 1, index: 3, len: 3 })
+    );
+    assert_eq!(
+        verify(&|p|p["tree_lvl_path"][0] = json!(usize::MAX)), 
+        Err(ProofError::PathIndexOutOfRange { lvl: 0, index: usize::MAX, len: 3 })
+    );
+    assert_eq!(
+        verify(&|p|p["tree_lvl_nodes"].as_array_mut().unwrap().truncate(3)), 
+        Err(ProofError::Truncated { path_lvls: 3, node_lvls: 1 })
+    );
+    assert_eq!(
+        verify(&|p|p["tree_lvl_nodes"].as_array_mut().unwrap().push(json!(1))), 
+        Err(ProofError::WrongNodeCount { expected: 9, actual: 10 })
+    );
+    assert_eq!(
+        verify(&|p|p["tree_lvl_path"].as_array_mut().unwrap().push(json!(1))), 
+        Err(ProofError::Truncated { path_lvls: 4, node_lvls: 3 })
+    );
+}
+
+#[test]
+fn codec_test() {
+    use crate::{MtProof, MtCompactProof, MtDecodeError, ProofError};
+    type Hasher = UnsecureHasher;
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        for proof in tree.proofs_iter().map(|proof|proof.to_owned()) {
+            let bytes = proof.to_bytes();
+            assert_eq!(MtProof::<_, ARITY>::from_bytes(&bytes), Ok(proof.clone()));
+            for len in 0..bytes.len() {
+                assert!(MtProof::<u64, ARITY>::from_bytes(&bytes[..len]).is_err());
+            }
+            let mut trailing = bytes.clone();
+            trailing.push(0);
+            assert_eq!(MtProof::<u64, ARITY>::from_bytes(&trailing), Err(MtDecodeError::TrailingBytes(1)));
+
+            let compact = proof.to_compact().unwrap();
+            let bytes = compact.to_bytes();
+            assert_eq!(MtCompactProof::<_, ARITY>::from_bytes(&bytes), Ok(compact));
+            for len in 0..bytes.len() {
+                assert!(MtCompactProof::<u64, ARITY>::from_bytes(&bytes[..len]).is_err());
+            }
+            // amount of siblings is known from `lvls` & `padding`
+            let mut trailing = bytes.clone();
+            trailing.push(0);
+            assert_eq!(MtCompactProof::<u64, ARITY>::from_bytes(&trailing), Err(MtDecodeError::TrailingBytes(1)));
+            let huge_lvls = [0x02, ARITY as u8, 0xff, 0xff, 0xff, 0xff, 0x0f, 0, 0, 0];
+            assert_eq!(MtCompactProof::<u64, ARITY>::from_bytes(&huge_lvls), Err(MtDecodeError::UnexpectedEnd));
+            assert_eq!(
+                MtProof::<u64, ARITY>::from_bytes(&bytes), 
+                Err(MtDecodeError::WrongTag { expected: 0x01, actual: 0x02 })
+            );
+
+            // proof without tree size
+            let lvls = proof.lvls().map(|(_, _, group)|group.to_vec()).collect();
+            let proof = MtProof::<_, ARITY>::from_parts(lvls, proof.path().to_vec(), *proof.root()).unwrap();
+            assert_eq!(MtProof::<_, ARITY>::from_bytes(&proof.to_bytes()), Ok(proof));
+        }
+
+        let bytes = tree.to_bytes();
+        assert!(tree.eq_full(&MerkleTree::<_, _, ARITY>::from_bytes(&bytes, Hasher::new()).unwrap()));
+        for len in 0..bytes.len() {
+            assert!(MerkleTree::<_, _, ARITY>::from_bytes(&bytes[..len], Hasher::new()).is_err());
+        }
+        let mut tampered = bytes.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(MerkleTree::<_, _, ARITY>::from_bytes(&tampered, Hasher::new()).err(), Some(MtDecodeError::WrongRoot));
+
+        #[cfg(feature = "serde")]
+        {
+            use crate::{MtSerde, MtEncodeError};
+            let tree_serde = tree.serializable();
+            assert_eq!(tree_serde.to_bytes(), Ok(bytes.clone()));
+            let tree_de = MtSerde::<u64, ARITY>::from_bytes(&bytes).unwrap().to_merkle_tree(Hasher::new()).unwrap();
+            assert!(tree.eq_full(&tree_de));
+            for len in 0..bytes.len() {
+                assert!(MtSerde::<u64, ARITY>::from_bytes(&bytes[..len]).is_err());
+            }
+            if tree.height() > 1 {
+                assert_eq!(tree.serializable_full().to_bytes(), Err(MtEncodeError::UnsupportedLvls));
+            }
+            assert_eq!(tree.serializable_with_hasher().to_bytes(), Err(MtEncodeError::UnsupportedHasherConfig));
+
+            #[cfg(feature = "serde_json")]
+            {
+                use serde::de::DeserializeSeed;
+                let limits = crate::MtSerdeLimits { early_arity_check: false, ..crate::MtSerdeLimits::unlimited() };
+                let json = serde_json::to_string(&tree).unwrap();
+                let other_arity = crate::MtSerdeSeed::<u64, 7>::new(limits).deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+                assert_eq!(other_arity.to_bytes(), Err(MtEncodeError::ArityMismatch { expected: 7, actual: ARITY }));
+            }
+        }
+    }
+
+    for vec in [to_vec_u64("0"), to_vec_u64("0 1"), (1u64..=12).collect(), (1u64..=28).collect()] {
+        test::<2>(&vec);
+        test::<3>(&vec);
+        test::<5>(&vec);
+    }
+
+    // layout is fixed
+    let tree = MerkleTree::<u64, _, 2>::new_from_leafs(Hasher::new(), [7, 9]);
+    let mut expected = vec![0x01, 2, 1, 1, 1, 2];
+    for hash in [7, 9, tree.root()] {
+        expected.extend_from_slice(&u64::to_be_bytes(hash));
+    }
+    let bytes = tree.proof_owned(LeafId::new(1)).to_bytes();
+    assert_eq!(bytes, expected);
+
+    // non-canonical & malformed encodings
+    let decode = |bytes: &[u8]|MtProof::<u64, 2>::from_bytes(bytes);
+    let mut non_canonical = vec![0x01, 0x82, 0x00];
+    non_canonical.extend_from_slice(&bytes[2..]);
+    assert_eq!(decode(&non_canonical), Err(MtDecodeError::NonCanonicalVarint));
+
+    let mut overflow = vec![0x01];
+    overflow.extend([0xFF; 10]);
+    overflow.push(0x01);
+    assert_eq!(decode(&overflow), Err(MtDecodeError::VarintOverflow));
+
+    let mut invalid_flag = bytes.clone();
+    invalid_flag[4] = 2;
+    assert_eq!(decode(&invalid_flag), Err(MtDecodeError::InvalidFlag(2)));
+
+    let mut invalid_path = bytes.clone();
+    invalid_path[3] = 2;
+    assert_eq!(decode(&invalid_path), Err(MtDecodeError::Malformed(ProofError::PathIndexOutOfRange { lvl: 0, index: 2, len: 2 })));
+
+    assert_eq!(MtProof::<u64, 3>::from_bytes(&bytes), Err(MtDecodeError::ArityMismatch { expected: 3, actual: 2 }));
+
+    let mut huge_lvls = vec![0x01, 2, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F];
+    huge_lvls.extend_from_slice(&bytes[3..]);
+    assert_eq!(decode(&huge_lvls), Err(MtDecodeError::UnexpectedEnd));
+}
+
+#[test]
+fn node_store_test() {
+    use crate::{MtFileStore, MtLightStore, MtMemStore, MtNodeStore, MtStoreError, StoredMerkleTree};
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    fn test<const ARITY: usize, Store: MtNodeStore<u64>>(vec: &[u64], store: Store, rng: &mut impl Rng) -> Store {
+        let mut tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        let mut stored = StoredMerkleTree::from_merkle_tree(tree.clone(), store).unwrap();
+
+        for _ in 0..40 {
+            match rng.random_range(0..8) {
+                0 if !tree.is_empty() => {
+                    let id = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                    let hash = rng.next_u64();
+                    assert_eq!(stored.replace(hash, id).unwrap(), tree.replace(hash, id));
+                }
+                3 => {
+                    let start_id = LeafId::new(rng.random_range(0..=tree.leaf_count()));
+                    let batch: Vec<_> = (0..rng.random_range(0..20)).map(|_|rng.next_u64()).collect();
+                    assert_eq!(stored.replace_batched(batch.clone(), start_id).unwrap(), tree.replace_batched(batch, start_id));
+                }
+                4 if !tree.is_empty() => {
+                    let id_a = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                    let id_b = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                    stored.swap(id_a, id_b).unwrap();
+                    tree.swap(id_a, id_b);
+                }
+                5 if !tree.is_empty() => {
+                    let id = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                    assert_eq!(stored.swap_remove(id).unwrap(), tree.swap_remove(id));
+                }
+                6 => {
+                    let others: Vec<_> = [1, ARITY, ARITY * ARITY, rng.random_range(0..20)].into_iter()
+                        .map(|len| MerkleTree::<_, _, ARITY>::new_from_leafs(Hasher::new(), (0..len).map(|_|rng.next_u64())))
+                        .collect();
+                    stored.merge(others.clone()).unwrap();
+                    tree.merge(others);
+                }
+                1 => {
+                    let hash = rng.next_u64();
+                    assert_eq!(stored.push(hash).unwrap(), tree.push(hash));
+                }
+                2 => {
+                    let batch: Vec<_> = (0..rng.random_range(0..20)).map(|_|rng.next_u64()).collect();
+                    let start = tree.leaf_count();
+                    tree.push_batched(batch.clone());
+                    assert_eq!(stored.push_batched(batch).unwrap(), LeafId::new(start)..LeafId::new(tree.leaf_count()));
+                }
+                _ => assert_eq!(stored.pop().unwrap(), tree.pop()),
+            }
+
+            assert_eq!(stored.leaf_count(), tree.leaf_count());
+            assert_eq!(stored.height(), tree.height());
+            if !tree.is_empty() {
+                assert_eq!(&stored.root().unwrap(), tree.root_ref());
+                let id = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                assert_eq!(stored.proof_owned(id).unwrap(), tree.proof_owned(id));
+            }
+        }
+        assert!(tree.eq_full(&stored.to_merkle_tree(Hasher::new()).unwrap()));
+        assert!(stored.audit().is_ok());
+
+        // the store contains the whole tree
+        let mut stored = StoredMerkleTree::<u64, _, _, ARITY>::new(Hasher::new(), stored.into_store()).unwrap();
+        assert!(tree.eq_full(&stored.to_merkle_tree(Hasher::new()).unwrap()));
+        stored.flush().unwrap();
+        stored.into_store()
+    }
+
+    let vecs = vec![
+        vec![],
+        vec![1],
+        (1u64..=9).collect::<Vec<_>>(),
+        (1u64..=27).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+    ];
+    let dir = std::env::temp_dir().join(format!("mt_store_{}", std::process::id()));
+    for vec in &vecs {
+        test::<2, _>(vec, MtMemStore::new(), &mut rng);
+        test::<3, _>(vec, MtMemStore::new(), &mut rng);
+        test::<5, _>(vec, MtMemStore::new(), &mut rng);
+        test::<2, _>(vec, MtFileStore::open(&dir).unwrap(), &mut rng);
+        test::<3, _>(vec, MtFileStore::open(&dir).unwrap(), &mut rng);
+        test::<5, _>(vec, MtFileStore::open(&dir).unwrap(), &mut rng);
+        for cached_lvls in 1..=3 {
+            test::<2, _>(vec, MtLightStore::<_, _, 2>::new(Hasher::new(), cached_lvls), &mut rng);
+            test::<3, _>(vec, MtLightStore::<_, _, 3>::new(Hasher::new(), cached_lvls), &mut rng);
+        }
+    }
+
+    // persisted across reopening
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), 1u64..=30);
+    drop(StoredMerkleTree::from_merkle_tree(tree.clone(), MtFileStore::open(&dir).unwrap()).unwrap());
+    let stored = StoredMerkleTree::<u64, _, _, 3>::new(Hasher::new(), MtFileStore::open(&dir).unwrap()).unwrap();
+    assert!(tree.eq_full(&stored.to_merkle_tree(Hasher::new()).unwrap()));
+
+    // concurrent readers don't move the position of each other
+    let big_dir = dir.join("big");
+    let big_tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), 0..5000u64);
+    let big = StoredMerkleTree::from_merkle_tree(big_tree.clone(), MtFileStore::open(&big_dir).unwrap()).unwrap();
+    std::thread::scope(|scope| {
+        for first in 0..4 {
+            let (big, big_tree) = (&big, &big_tree);
+            scope.spawn(move || {
+                for id in (first..big_tree.leaf_count()).step_by(4).map(LeafId::new) {
+                    assert_eq!(big.proof_owned(id).unwrap(), big_tree.proof_owned(id));
+                }
+            });
+        }
+    });
+    drop(big);
+    std::fs::remove_dir_all(&big_dir).unwrap();
+
+    // batches longer than a chunk
+    let mut chunked = StoredMerkleTree::<u64, _, _, 3>::new(Hasher::new(), MtMemStore::new()).unwrap();
+    chunked.push_batched(0..3000).unwrap();
+    let range = chunked.replace_batched((0..9000).map(|x| x * 7), LeafId::new(1000)).unwrap();
+    assert_eq!(range, LeafId::new(1000)..LeafId::new(10_000));
+    let chunked_tree = MerkleTree::<u64, _, 3>::new_from_leafs(Hasher::new(), (0..1000).chain((0..9000).map(|x| x * 7)));
+    assert!(chunked_tree.eq_full(&chunked.to_merkle_tree(Hasher::new()).unwrap()));
+
+    // tampered & malformed stores are rejected
+    let node = NodeId { lvl: 1, index: 4 };
+    let hash = stored.get_node(node).unwrap();
+    let mut store = stored.into_store();
+    store.put(node, hash ^ 1).unwrap();
+    let mut stored = StoredMerkleTree::<u64, _, _, 3>::new(Hasher::new(), store).unwrap();
+    assert!(matches!(stored.audit(), Err(MtStoreError::TamperedNode(x)) if x == node));
+
+    let mut store = stored.into_store();
+    store.truncate(1, 3).unwrap();
+    let err = StoredMerkleTree::<u64, _, _, 3>::new(Hasher::new(), store).err().unwrap();
+    assert!(matches!(err, MtStoreError::WrongLvlLen { lvl: 1, expected: 10, actual: 3 }));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn stream_builder_test() {
+    use crate::{MtFileStore, MtMemStore, MtNodeStore, MtStreamBuilder, StoredMerkleTree};
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    fn test<const ARITY: usize, Store: MtNodeStore<u64>>(vec: &[u64], store: Store, buffer: usize) -> Store {
+        let tree = MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), vec.iter().copied());
+
+        let mut builder = MtStreamBuilder::<_, _, _, ARITY>::new(Hasher::new(), store).unwrap().with_buffer(buffer);
+        let (head, tail) = vec.split_at(vec.len() / 2);
+        for &hash in head {
+            let expected = LeafId::new(builder.leaf_count());
+            assert_eq!(builder.push(hash).unwrap(), expected);
+        }
+        builder.extend(tail.iter().copied()).unwrap();
+        assert_eq!(builder.leaf_count(), vec.len());
+
+        let mut stored = builder.finish().unwrap();
+        assert!(tree.eq_full(&stored.to_merkle_tree(Hasher::new()).unwrap()));
+        assert!(stored.audit().is_ok());
+        if !tree.is_empty() {
+            assert_eq!(&stored.root().unwrap(), tree.root_ref());
+            for id in (0..tree.leaf_count()).step_by(7).map(LeafId::new) {
+                assert_eq!(stored.proof_owned(id).unwrap(), tree.proof_owned(id));
+            }
+        }
+        stored.into_store()
+    }
+
+    let vecs = vec![
+        vec![],
+        vec![1],
+        vec![1, 2],
+        (1u64..=9).collect::<Vec<_>>(),
+        (1u64..=25).collect::<Vec<_>>(),
+        (1u64..=27).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+        (1u64..=126).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+        (1u64..=1000).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+    ];
+    let dir = std::env::temp_dir().join(format!("mt_stream_{}", std::process::id()));
+    for vec in &vecs {
+        for buffer in [1, 3, MtStreamBuilder::<u64, Hasher, MtMemStore<u64>, 2>::DEFAULT_BUFFER] {
+            test::<2, _>(vec, MtMemStore::new(), buffer);
+            test::<3, _>(vec, MtMemStore::new(), buffer);
+            test::<5, _>(vec, MtMemStore::new(), buffer);
+        }
+        // the store is reused: its previous content is removed
+        let store = test::<2, _>(vec, MtFileStore::open(&dir).unwrap(), 4);
+        let store = test::<3, _>(vec, store, 4);
+        test::<5, _>(vec, store, 4);
+    }
+
+    let leafs = (1u64..=100).map(|_|rng.next_u64()).collect::<Vec<_>>();
+    let tree = MerkleTree::<u64, _, 3>::new_from_leafs(Hasher::new(), leafs.iter().copied());
+    let stored = StoredMerkleTree::<_, _, _, 3>::from_leafs(Hasher::new(), MtFileStore::open(&dir).unwrap(), leafs).unwrap();
+    assert_eq!(&stored.root().unwrap(), tree.root_ref());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn light_tree_test() {
+    use crate::LightMerkleTree;
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    fn check<const ARITY: usize>(tree: &MerkleTree<u64, Hasher, ARITY>, light: &mut LightMerkleTree<u64, Hasher, ARITY>) {
+        assert_eq!(light.leaf_count(), tree.leaf_count());
+        assert_eq!(light.height(), tree.height());
+        if tree.is_empty() { return }
+        assert_eq!(light.root_ref(), tree.root_ref());
+        for lvl in 0..tree.height() {
+            for index in 0..tree.get_lvl(lvl).len() {
+                let node = NodeId { lvl, index };
+                assert_eq!(light.get_node(node), tree.get_node(node));
+            }
+        }
+        for id in (0..tree.leaf_count()).map(LeafId::new) {
+            assert_eq!(light.proof_owned(id), tree.proof_owned(id));
+        }
+    }
+
+    fn test<const ARITY: usize>(vec: &[u64], rng: &mut impl Rng) {
+        for cached_lvls in 1..=5 {
+            let mut tree = MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), vec.iter().copied());
+            let mut light = LightMerkleTree::from_leafs(Hasher::new(), cached_lvls, vec.iter().copied());
+            check(&tree, &mut light);
+
+            for _ in 0..20 {
+                match rng.random_range(0..8) {
+                    0 if !tree.is_empty() => {
+                        let id = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                        let hash = rng.next_u64();
+                        assert_eq!(light.replace(hash, id), tree.replace(hash, id));
+                    }
+                    3 if !tree.is_empty() => {
+                        let id_a = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                        let id_b = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                        light.swap(id_a, id_b);
+                        tree.swap(id_a, id_b);
+                        assert_eq!(light.swap_remove(id_b), tree.swap_remove(id_b));
+                    }
+                    4 => {
+                        let other = MerkleTree::<_, _, ARITY>::new_from_leafs(Hasher::new(), (0..rng.random_range(0..30)).map(|_|rng.next_u64()));
+                        light.merge([other.clone()]);
+                        tree.merge([other]);
+                    }
+                    1 => {
+                        let hash = rng.next_u64();
+                        assert_eq!(light.push(hash), tree.push(hash));
+                    }
+                    2 => {
+                        let batch: Vec<_> = (0..rng.random_range(0..30)).map(|_|rng.next_u64()).collect();
+                        assert_eq!(light.push_batched(batch.iter().copied()), tree.push_batched(batch));
+                    }
+                    _ => assert_eq!(light.pop(), tree.pop()),
+                }
+                check(&tree, &mut light);
+            }
+
+            light.set_cached_lvls(cached_lvls % 3 + 1);
+            check(&tree, &mut light);
+            let mut from_tree = LightMerkleTree::from_merkle_tree(tree.clone(), cached_lvls);
+            check(&tree, &mut from_tree);
+            assert!(light.to_merkle_tree().eq_full(&tree));
+        }
+    }
+
+    let vecs = vec![
+        vec![],
+        vec![1],
+        (1u64..=9).collect::<Vec<_>>(),
+        (1u64..=27).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+        (1u64..=70).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+    ];
+    for vec in &vecs {
+        test::<2>(vec, &mut rng);
+        test::<3>(vec, &mut rng);
+        test::<5>(vec, &mut rng);
+    }
+
+    // only the top levels are kept
+    let mut light = LightMerkleTree::<_, _, 2>::from_leafs(Hasher::new(), 2, 0..1024u64);
+    assert_eq!(light.height(), 11);
+    assert_eq!(light.cached_nodes(), 1 + 2);
+    light.set_cached_lvls(10);
+    assert_eq!(light.cached_nodes(), 1023);
+}
+
+#[test]
+fn consistency_proof_test() {
+    use crate::ProofError;
+    type Hasher = UnsecureHasher;
+
+    fn test<const ARITY: usize>(leafs: &[u64]) {
+        let trees: Vec<_> = (1..=leafs.len())
+            .map(|size| MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), leafs[..size].iter().copied()))
+            .collect();
+        for new in &trees {
+            let (new_size, new_root) = (new.leaf_count(), new.root());
+            for old in &trees[..new_size] {
+                let (old_size, old_root) = (old.leaf_count(), old.root());
+                let proof = new.consistency_proof(old_size);
+                assert_eq!(proof.old_size(), old_size);
+                assert_eq!(proof.new_size(), Some(new_size));
+                assert_eq!(proof.verify_detailed(&old_root, old_size, &new_root, new_size, &mut Hasher::new()), Ok(()));
+
+                let wrong_root = old_root.wrapping_add(1);
+                let wrong = proof.verify_detailed(&wrong_root, old_size, &new_root, new_size, &mut Hasher::new());
+                assert_eq!(wrong, Err(ProofError::OldRootMismatch));
+                assert!(!proof.verify(&old_root, old_size, &wrong_root, new_size, &mut Hasher::new()));
+                if new_size > old_size {
+                    assert!(!proof.verify(&old_root, old_size, &new_root, new_size - 1, &mut Hasher::new()));
+                }
+            }
+        }
+    }
+
+    let leafs: Vec<_> = (1u64..=30).map(unsecure_hash).collect();
+    test::<2>(&leafs);
+    test::<3>(&leafs);
+    test::<5>(&leafs);
+}
+
+#[test]
+fn merkle_log_test() {
+    use crate::{MerkleLog, MtLogConfig, MtLogError, ProofError};
+    type Hasher = UnsecureHasher;
+    type Log = MerkleLog<u64, Hasher, 3>;
+    let mut rng = rand::rng();
+    let dir = std::env::temp_dir().join(format!("mt_log_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let wal = dir.join("wal.bin");
+    let config = MtLogConfig { sync_batch: 5, compact_after: Some(40) };
+
+    let mut leafs = vec![];
+    let mut roots = vec![];
+    let mut log = Log::open(&dir, Hasher::new(), config).unwrap();
+    for _ in 0..30 {
+        let batch: Vec<_> = (0..rng.random_range(1..6)).map(|_|rng.next_u64()).collect();
+        let range = log.append_batch(batch.iter().copied()).unwrap();
+        assert_eq!(range, LeafId::new(leafs.len())..LeafId::new(leafs.len() + batch.len()));
+        leafs.extend(batch);
+        if !leafs.is_empty() {
+            roots.push((leafs.len(), log.root()));
+        }
+        assert!(log.len() - log.synced_len() < config.sync_batch);
+        assert!(log.len() - log.snapshot_len() < config.compact_after.unwrap() + 6);
+    }
+    // the last leaf stays in the journal
+    log.compact().unwrap();
+    log.append(7).unwrap();
+    leafs.push(7);
+    log.sync().unwrap();
+    assert_eq!(log.synced_len(), leafs.len());
+
+    let tree = MerkleTree::<u64, _, 3>::new_from_leafs(Hasher::new(), leafs.iter().copied());
+    assert!(tree.eq_full(log.tree()));
+    let (new_size, new_root) = (log.len(), log.root());
+    for (old_size, old_root) in &roots {
+        let proof = log.consistency_proof(*old_size);
+        assert!(proof.verify(old_root, *old_size, &new_root, new_size, &mut Hasher::new()));
+        assert!(!proof.verify(&new_root, *old_size, &new_root, new_size, &mut Hasher::new()) || old_root == &new_root);
+        if *old_size > 1 {
+            let wrong = proof.verify_detailed(old_root, *old_size - 1, &new_root, new_size, &mut Hasher::new());
+            assert!(matches!(wrong, Err(ProofError::OldSizeMismatch { .. })));
+        }
+    }
+    for id in (0..leafs.len()).map(LeafId::new) {
+        let proof = log.inclusion_proof(id);
+        assert!(proof.verify_position(&new_root, id, new_size, leafs[id.index()], &mut Hasher::new()));
+    }
+    drop(log);
+
+    // recovery: snapshot + journal
+    let mut log = Log::open(&dir, Hasher::new(), config).unwrap();
+    assert!(tree.eq_full(log.tree()));
+    assert!(log.snapshot_len() > 0 && log.snapshot_len() < log.len());
+
+    // unsynced appends are written on drop
+    log.append(8).unwrap();
+    leafs.push(8);
+    drop(log);
+    let log = Log::open(&dir, Hasher::new(), MtLogConfig { compact_after: None, ..config }).unwrap();
+    assert_eq!(log.len(), leafs.len());
+    drop(log);
+
+    // torn last record is cut off
+    let wal_len = std::fs::metadata(&wal).unwrap().len();
+    let mut log = Log::open(&dir, Hasher::new(), MtLogConfig { sync_batch: 1, compact_after: None }).unwrap();
+    log.append_batch([1, 2, 3]).unwrap();
+    drop(log);
+    let full_len = std::fs::metadata(&wal).unwrap().len();
+    for cut in [1, 8, 20, 40] {
+        let file = std::fs::OpenOptions::new().write(true).open(&wal).unwrap();
+        file.set_len(full_len - cut).unwrap();
+        drop(file);
+        let log = Log::open(&dir, Hasher::new(), config).unwrap();
+        assert_eq!(log.len(), leafs.len());
+        assert_eq!(std::fs::metadata(&wal).unwrap().len(), wal_len);
+        assert_eq!(log.root(), MerkleTree::<u64, _, 3>::new_from_leafs(Hasher::new(), leafs.iter().copied()).root());
+    }
+
+    // tampered record in the middle of the journal is an error
+    let mut log = Log::open(&dir, Hasher::new(), MtLogConfig { sync_batch: 1, compact_after: None }).unwrap();
+    log.append_batch([1, 2, 3]).unwrap();
+    log.append_batch([4, 5]).unwrap();
+    log.append(6).unwrap();
+    drop(log);
+    let mut bytes = std::fs::read(&wal).unwrap();
+    // frame len, start, count, 3 leafs & root
+    let (wal_len, frame_len) = (wal_len as usize, 8 + 16 + 4 * 8);
+    bytes[wal_len + 24] ^= 1;
+    std::fs::write(&wal, &bytes).unwrap();
+    let err = Log::open(&dir, Hasher::new(), config).err().unwrap();
+    assert!(matches!(err, MtLogError::JournalRootMismatch { .. }), "{err}");
+    bytes[wal_len + 24] ^= 1;
+    bytes[wal_len + frame_len + 8] ^= 1;
+    std::fs::write(&wal, &bytes).unwrap();
+    let err = Log::open(&dir, Hasher::new(), config).err().unwrap();
+    assert!(matches!(err, MtLogError::JournalGap { .. }), "{err}");
+    bytes[wal_len + frame_len + 8] ^= 1;
+    std::fs::write(&wal, &bytes).unwrap();
+    leafs.extend([1, 2, 3, 4, 5, 6]);
+
+    // records of the last write are torn together: any invalid one cuts off all of them
+    let mut log = Log::open(&dir, Hasher::new(), MtLogConfig { sync_batch: 5, compact_after: None }).unwrap();
+    log.append_batch([9, 9]).unwrap();
+    log.append(9).unwrap();
+    log.append(9).unwrap();
+    assert_eq!(log.synced_len(), leafs.len());
+    drop(log);
+    let bytes = std::fs::read(&wal).unwrap();
+    let wal_len = bytes.len() - (8 + 3 * 16 + 7 * 8);
+    // the first leaf of the first record, the frame len, the root of the last record
+    for (index, mask) in [(wal_len + 24, 1), (wal_len, 0xff), (bytes.len() - 1, 1)] {
+        let mut torn = bytes.clone();
+        torn[index] ^= mask;
+        std::fs::write(&wal, &torn).unwrap();
+        let log = Log::open(&dir, Hasher::new(), config).unwrap();
+        assert_eq!(log.len(), leafs.len());
+        assert_eq!(std::fs::metadata(&wal).unwrap().len(), wal_len as u64);
+    }
+
+    // explicit compaction & another arity
+    let mut log = Log::open(&dir, Hasher::new(), config).unwrap();
+    assert_eq!(log.len(), leafs.len());
+    log.compact().unwrap();
+    assert_eq!(log.snapshot_len(), leafs.len());
+    drop(log);
+    let log = Log::open(&dir, Hasher::new(), config).unwrap();
+    assert_eq!(log.len(), leafs.len());
+    drop(log);
+    let err = MerkleLog::<u64, Hasher, 2>::open(&dir, Hasher::new(), config).err().unwrap();
+    assert!(matches!(err, MtLogError::ArityMismatch { expected: 2, actual: 3 }), "{err}");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn sync_test() {
+    use crate::{MtSyncChannel, MtSyncError, MtSyncRequest, MtSyncResponse, MtSyncTransport};
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    fn sync<const ARITY: usize>(local: &mut MerkleTree<u64, Hasher, ARITY>, remote: &MerkleTree<u64, Hasher, ARITY>) -> crate::MtSyncReport {
+        let (mut client, server) = MtSyncChannel::new();
+        std::thread::scope(|scope| {
+            scope.spawn(move || server.serve(remote));
+            let report = local.sync_with(&mut client).unwrap();
+            // the server stops when the client is dropped
+            drop(client);
+            report
+        })
+    }
+
+    fn test<const ARITY: usize>(rng: &mut impl Rng) {
+        let leafs: Vec<_> = (0..rng.random_range(1..300)).map(|_|rng.next_u64()).collect();
+        let remote = MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), leafs.iter().copied());
+
+        // the same tree
+        let mut local = remote.clone();
+        let report = sync(&mut local, &remote);
+        assert_eq!((report.requests, report.received_hashes), (1, 1));
+
+        // a few divergent leafs
+        let mut changed: Vec<_> = (0..rng.random_range(1..=5)).map(|_|rng.random_range(0..leafs.len())).collect();
+        for &id in &changed {
+            local.replace(rng.next_u64(), LeafId::new(id));
+        }
+        let report = sync(&mut local, &remote);
+        assert!(local.eq_full(&remote));
+        changed.sort();
+        changed.dedup();
+        assert_eq!(report.replaced, changed.into_iter().map(LeafId::new).collect::<Vec<_>>());
+        assert_eq!((report.appended, report.removed), (0, 0));
+        assert!(report.received_hashes <= 1 + report.replaced.len() * ARITY * remote.height());
+
+        // different leaf count
+        let mut local = MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), leafs[..leafs.len() / 2].iter().copied());
+        if !local.is_empty() {
+            local.replace(0, LeafId::new(0));
+        }
+        let report = sync(&mut local, &remote);
+        assert!(local.eq_full(&remote));
+        assert_eq!(report.appended, leafs.len() - leafs.len() / 2);
+        let mut local = remote.clone();
+        local.push_batched([1, 2, 3]);
+        let report = sync(&mut local, &remote);
+        assert!(local.eq_full(&remote));
+        assert_eq!((report.removed, report.received_hashes), (3, 1));
+
+        let single = MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), [leafs[0]]);
+        let mut local = MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), [!leafs[0]]);
+        let report = sync(&mut local, &single);
+        assert!(local.eq_full(&single));
+        assert_eq!(report.replaced, vec![LeafId::new(0)]);
+
+        let empty = MerkleTree::<u64, _, ARITY>::new_minimal(Hasher::new());
+        sync(&mut local, &empty);
+        assert!(local.is_empty());
+    }
+    for _ in 0..10 {
+        test::<2>(&mut rng);
+        test::<3>(&mut rng);
+        test::<5>(&mut rng);
+    }
+
+    // invalid requests are rejected
+    let tree = MerkleTree::<u64, _, 2>::new_from_leafs(Hasher::new(), 0..10);
+    assert_eq!(tree.sync_respond(&MtSyncRequest::Nodes { lvl: 1, indexes: vec![5] }), MtSyncResponse::Invalid);
+    assert_eq!(tree.sync_respond(&MtSyncRequest::Nodes { lvl: 7, indexes: vec![0] }), MtSyncResponse::Invalid);
+    assert_eq!(tree.sync_respond(&MtSyncRequest::Leafs { range: 8..11 }), MtSyncResponse::Invalid);
+    assert_eq!(tree.sync_respond(&MtSyncRequest::Leafs { range: 8..10 }), MtSyncResponse::Hashes(vec![8, 9]));
+    let empty = MerkleTree::<u64, _, 2>::new_from_leafs(Hasher::new(), 0..0);
+    assert_eq!(empty.sync_respond(&MtSyncRequest::Leafs { range: 0..1 }), MtSyncResponse::Invalid);
+    assert_eq!(empty.sync_respond(&MtSyncRequest::Leafs { range: 0..0 }), MtSyncResponse::Hashes(vec![]));
+
+    // remote that lies about its root
+    struct Liar(MerkleTree<u64, Hasher, 2>);
+    impl MtSyncTransport<u64> for Liar {
+        type Error = std::convert::Infallible;
+        fn request(&mut self, request: MtSyncRequest) -> Result<MtSyncResponse<u64>, Self::Error> {
+            Ok(match self.0.sync_respond(&request) {
+                MtSyncResponse::Summary { leaf_count, root } => MtSyncResponse::Summary { leaf_count, root: root.map(|x| x ^ 1) },
+                response => response,
+            })
+        }
+    }
+    let mut local = tree.clone();
+    let err = local.sync_with(&mut Liar(tree)).unwrap_err();
+    assert!(matches!(err, MtSyncError::RootMismatch), "{err}");
+}
+
+#[test]
+fn observer_test() {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    type Index = Arc<Mutex<HashMap<NodeId, u64>>>;
+    fn mirror(index: &Index) -> impl FnMut(NodeId, Option<&u64>, Option<&u64>) + Send + Sync + 'static {
+        let index = index.clone();
+        move |node, old, new| {
+            let mut index = index.lock().unwrap();
+            let prev = match new {
+                Some(new) => index.insert(node, *new),
+                None => index.remove(&node),
+            };
+            assert_eq!(prev.as_ref(), old, "`old` must be the previous value of {node:?}");
+        }
+    }
+    fn check<const ARITY: usize, Observer>(tree: &MerkleTree<u64, Hasher, ARITY, Observer>, index: &Index) {
+        let index = index.lock().unwrap();
+        let mut count = 0;
+        for lvl in 0..tree.height() {
+            for (i, hash) in tree.get_lvl(lvl).to_vec().iter().enumerate() {
+                assert_eq!(index.get(&NodeId { lvl, index: i }), Some(hash));
+                count += 1;
+            }
+        }
+        assert_eq!(index.len(), count);
+    }
+
+    fn test<const ARITY: usize>(rng: &mut impl Rng) {
+        let index = Index::default();
+        let mut tree = MerkleTree::<u64, _, ARITY>::new_minimal(Hasher::new()).with_observer(mirror(&index));
+
+        for _ in 0..100 {
+            match rng.random_range(0..8) {
+                0 => { tree.push(rng.next_u64()); }
+                1 => {
+                    let batch: Vec<_> = (0..rng.random_range(0..20)).map(|_|rng.next_u64()).collect();
+                    tree.push_batched(batch);
+                }
+                2 if !tree.is_empty() => {
+                    let id = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                    tree.replace(rng.next_u64(), id);
+                }
+                3 => {
+                    let start = LeafId::new(rng.random_range(0..=tree.leaf_count()));
+                    let batch: Vec<_> = (0..rng.random_range(0..20)).map(|_|rng.next_u64()).collect();
+                    tree.replace_batched(batch, start);
+                }
+                4 if !tree.is_empty() => {
+                    let id = LeafId::new(rng.random_range(0..tree.leaf_count()));
+                    tree.swap_remove(id);
+                }
+                5 => {
+                    let others = (0..rng.random_range(1..3)).map(|_| {
+                        let leafs: Vec<_> = (0..ARITY.pow(rng.random_range(0..3))).map(|_|rng.next_u64()).collect();
+                        MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), leafs)
+                    });
+                    tree.merge(others.collect::<Vec<_>>());
+                }
+                _ => { tree.pop(); }
+            }
+            check(&tree, &index);
+        }
+
+        // detached observer isn't called
+        let (mut tree, _) = tree.detach_observer();
+        let len = index.lock().unwrap().len();
+        tree.push_batched([1, 2, 3]);
+        assert_eq!(index.lock().unwrap().len(), len);
+
+        // boxed observer is replaced in place
+        type Boxed = Box<dyn FnMut(NodeId, Option<&u64>, Option<&u64>)>;
+        let mut tree = tree.with_observer::<Boxed>(Box::new(|_, _, _| {}));
+        tree.push(4);
+        let index = Index::default();
+        let mut fresh = mirror(&index);
+        for lvl in 0..tree.height() {
+            for (i, hash) in tree.get_lvl(lvl).to_vec().iter().enumerate() {
+                fresh(NodeId { lvl, index: i }, None, Some(hash));
+            }
+        }
+        let _ = tree.set_observer(Box::new(fresh));
+        tree.replace(5, LeafId::new(0));
+        check(&tree, &index);
+        let _ = tree.set_observer(Box::new(|_, _, _| {}));
+        tree.replace(6, LeafId::new(0));
+        assert_eq!(index.lock().unwrap().get(&NodeId { lvl: 0, index: 0 }), Some(&5));
+    }
+    for _ in 0..5 {
+        test::<2>(&mut rng);
+        test::<3>(&mut rng);
+        test::<5>(&mut rng);
+    }
+}
+
+#[test]
+fn journal_test() {
+    use crate::{MtOp, MtJournal, MtJournalError};
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    fn test<const ARITY: usize>(rng: &mut impl Rng) {
+        let mut leader = MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), (0..rng.random_range(0..20)).map(|_|rng.next_u64()));
+        // the same operations by the methods of the tree
+        let mut direct = leader.clone();
+        let mut journal = MtJournal::new(&leader);
+        let mut follower = leader.clone();
+        let mut follower_seq = 0;
+
+        for step in 0..60 {
+            let len = leader.leaf_count();
+            let op = match rng.random_range(0..7) {
+                0 => MtOp::Push(rng.next_u64()),
+                1 => MtOp::PushBatched((0..rng.random_range(0..10)).map(|_|rng.next_u64()).collect()),
+                2 if len > 0 => MtOp::Replace { index: rng.random_range(0..len), hash: rng.next_u64() },
+                3 => MtOp::Pop,
+                4 if len > 0 => MtOp::Swap { a: rng.random_range(0..len), b: rng.random_range(0..len) },
+                5 if len > 0 => MtOp::SwapRemove(rng.random_range(0..len)),
+                _ => MtOp::Merge((0..rng.random_range(0..3))
+                    .map(|_|(0..rng.random_range(0..10)).map(|_|rng.next_u64()).collect())
+                    .collect()),
+            };
+            let expected = match op.clone() {
+                MtOp::Push(hash) => { direct.push(hash); None }
+                MtOp::PushBatched(hashes) => { direct.push_batched(hashes); None }
+                MtOp::Replace { index, hash } => Some(direct.replace(hash, LeafId::new(index))),
+                MtOp::Pop => direct.pop(),
+                MtOp::Swap { a, b } => { direct.swap(LeafId::new(a), LeafId::new(b)); None }
+                MtOp::SwapRemove(index) => Some(direct.swap_remove(LeafId::new(index))),
+                MtOp::Merge(trees) => {
+                    direct.merge(trees.into_iter().map(|leafs| MerkleTree::new_from_leafs(Hasher::new(), leafs)));
+                    None
+                }
+            };
+            assert_eq!(journal.record(&mut leader, op).unwrap(), expected);
+            assert!(leader.eq_full(&direct));
+
+            if step % 7 == 0 {
+                follower_seq = follower.apply_journal(&journal, follower_seq).unwrap();
+                assert!(follower.eq_full(&leader));
+            }
+        }
+        follower_seq = follower.apply_journal(&journal, follower_seq).unwrap();
+        assert_eq!(follower_seq, journal.end());
+        assert!(follower.eq_full(&leader));
+
+        // a new follower starts from the tree at the checkpoint
+        let checkpoint = journal.checkpoint();
+        let snapshot = leader.clone();
+        for _ in 0..10 {
+            journal.record(&mut leader, MtOp::Push(rng.next_u64())).unwrap();
+        }
+        journal.truncate(checkpoint.seq).unwrap();
+        assert_eq!((journal.start(), journal.len()), (checkpoint.seq, 10));
+        assert_eq!(journal.state_at(checkpoint.seq - 1), Err(MtJournalError::Truncated { seq: checkpoint.seq - 1, start: checkpoint.seq }));
+        assert!(follower.apply_journal(&journal, 0).is_err());
+
+        let mut new_follower = snapshot;
+        checkpoint.check(&new_follower).unwrap();
+        new_follower.apply_journal(&journal, checkpoint.seq).unwrap();
+        assert!(new_follower.eq_full(&leader));
+
+        // follower detects the divergence
+        let mut follower = new_follower.clone();
+        follower.pop();
+        let seq = journal.end();
+        assert_eq!(follower.apply_journal(&journal, seq), Err(MtJournalError::LeafCountMismatch { seq, expected: follower.leaf_count() + 1, actual: follower.leaf_count() }));
+        follower.push(!leader.get_lvl(0).to_vec().last().unwrap());
+        assert_eq!(follower.apply_journal(&journal, seq), Err(MtJournalError::RootMismatch { seq }));
+        let len = follower.leaf_count();
+        assert_eq!(follower.apply(MtOp::SwapRemove(len)), Err(MtJournalError::LeafOutOfTree { index: len, leaf_count: len }));
+        follower.pop();
+        assert_eq!(journal.record(&mut follower, MtOp::Pop), Err(MtJournalError::LeafCountMismatch { seq, expected: leader.leaf_count(), actual: follower.leaf_count() }));
+        assert_eq!(journal.end(), seq);
+    }
+    for _ in 0..10 {
+        test::<2>(&mut rng);
+        test::<3>(&mut rng);
+        test::<5>(&mut rng);
+    }
+
+    // journal is shipped to the followers
+    #[cfg(feature = "serde_json")]
+    {
+        let mut leader = MerkleTree::<u64, _, 2>::new_minimal(Hasher::new());
+        let mut journal = MtJournal::new(&leader);
+        journal.record(&mut leader, MtOp::PushBatched(vec![1, 2, 3])).unwrap();
+        journal.record(&mut leader, MtOp::Swap { a: 0, b: 2 }).unwrap();
+        let json = serde_json::to_value(&journal).unwrap();
+        let shipped: MtJournal<u64> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(shipped, journal);
+
+        let mut forged = json;
+        forged["entries"][1]["root"] = serde_json::json!(0);
+        let forged: MtJournal<u64> = serde_json::from_value(forged).unwrap();
+        let mut follower = MerkleTree::<u64, _, 2>::new_minimal(Hasher::new());
+        assert_eq!(follower.apply_journal(&forged, 0), Err(MtJournalError::RootMismatch { seq: 2 }));
+
+        // the end of the journal must fit into `usize`
+        let mut far = serde_json::to_value(&journal).unwrap();
+        far["base"]["seq"] = serde_json::json!(usize::MAX - 1);
+        let err = serde_json::from_value::<MtJournal<u64>>(far.clone()).err().unwrap();
+        assert!(err.to_string().contains("out of `usize`"), "{err}");
+        far["entries"].as_array_mut().unwrap().pop();
+        let mut far: MtJournal<u64> = serde_json::from_value(far).unwrap();
+        assert_eq!(far.end(), usize::MAX);
+        let mut leader = MerkleTree::<u64, _, 2>::new_from_leafs(Hasher::new(), [1, 2, 3]);
+        assert_eq!(far.record(&mut leader, MtOp::Pop), Err(MtJournalError::PositionOverflow { start: usize::MAX - 1, len: 2 }));
+        assert_eq!(leader.leaf_count(), 3);
+    }
+}
+
+#[test]
+fn concurrent_tree_test() {
+    use crate::{ConcurrentMerkleTree, MtOp, MtJournalError};
+    type Hasher = UnsecureHasher;
+    let leafs: Vec<u64> = (0..2000).collect();
+
+    let tree = ConcurrentMerkleTree::new(MerkleTree::<u64, _, 3>::new_minimal(Hasher::new()));
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                let mut rng = rand::rng();
+                let mut last_len = 0;
+                while last_len < leafs.len() {
+                    let snapshot = tree.snapshot();
+                    // published trees are never half-updated & only grow
+                    assert!(snapshot.leaf_count() >= last_len);
+                    last_len = snapshot.leaf_count();
+                    if last_len == 0 { continue }
+                    let expected = MerkleTree::<u64, _, 3>::new_from_leafs(Hasher::new(), leafs[..last_len].iter().copied());
+                    assert!(snapshot.eq_full(&expected));
+                    let id = rng.random_range(0..last_len);
+                    assert!(snapshot.proof_owned(LeafId::new(id)).verify_with_root(snapshot.root_ref(), leafs[id], &mut Hasher::new()));
+                }
+            });
+        }
+        for batch in leafs.chunks(50) {
+            let range = tree.push_batched(batch.iter().copied());
+            assert_eq!(range.start, LeafId::new(batch[0] as usize));
+        }
+    });
+
+    // invalid batch publishes nothing
+    let before = tree.snapshot();
+    let result = tree.apply_batch([MtOp::Replace { index: 0, hash: 7 }, MtOp::SwapRemove(leafs.len())]);
+    assert_eq!(result.err(), Some(MtJournalError::LeafOutOfTree { index: leafs.len(), leaf_count: leafs.len() }));
+    assert!(std::sync::Arc::ptr_eq(&before, &tree.snapshot()));
+
+    let mut expected = (*before).clone();
+    let ops = [MtOp::Swap { a: 1, b: 2 }, MtOp::Pop, MtOp::Replace { index: 3, hash: 7 }];
+    for round in 0..3 {
+        let published = tree.apply_batch(ops.clone()).unwrap();
+        for op in ops.clone() {
+            expected.apply(op).unwrap();
+        }
+        assert!(published.eq_full(&expected), "round {round}");
+    }
+    drop(before);
+    assert!(tree.into_inner().eq_full(&expected));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_test() {
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    fn test<const ARITY: usize>(rng: &mut impl Rng) {
+        let leafs: Vec<u64> = (0..rng.random_range(1..20_000)).map(|_|rng.next_u64()).collect();
+        let mut tree = MerkleTree::<u64, _, ARITY>::new_from_leafs(Hasher::new(), leafs.iter().copied());
+        let mut par_tree = MerkleTree::<u64, _, ARITY>::new_from_leafs_par(Hasher::new(), leafs.iter().copied());
+        assert!(par_tree.eq_full(&tree));
+
+        let batch: Vec<u64> = (0..rng.random_range(0..10_000)).map(|_|rng.next_u64()).collect();
+        let start = LeafId::new(rng.random_range(0..=leafs.len()));
+        assert_eq!(par_tree.replace_batched_par(batch.iter().copied(), start), tree.replace_batched(batch.iter().copied(), start));
+        assert!(par_tree.eq_full(&tree));
+
+        let range = par_tree.replace_batched_par_with(batch.iter().rev().copied(), LeafId::new(0), Hasher::new);
+        assert_eq!(range, tree.replace_batched(batch.iter().rev().copied(), LeafId::new(0)));
+        assert!(par_tree.eq_full(&tree));
+
+        assert_eq!(par_tree.push_batched_par(leafs.iter().copied()), tree.push_batched(leafs.iter().copied()));
+        assert!(par_tree.eq_full(&tree));
+    }
+    for _ in 0..3 {
+        test::<2>(&mut rng);
+        test::<3>(&mut rng);
+        test::<5>(&mut rng);
+    }
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mapped_test() {
+    use crate::{MappedMerkleTree, MtMapError};
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    fn test<const ARITY: usize>(vec: &[u64], pop: usize) {
+        let mut tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        for _ in 0..pop { tree.pop(); }
+        let path = std::env::temp_dir().join(format!("mt_mapped_{}_{ARITY}_{}_{pop}.bin", std::process::id(), vec.len()));
+        tree.write_mapped_file(&path).unwrap();
+
+        let mut bytes = vec![];
+        tree.write_mapped(&mut bytes).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        assert_eq!(u64::from_le_bytes(bytes[32..40].try_into().unwrap()), tree.leaf_count() as u64);
+
+        let mapped = unsafe { MappedMerkleTree::<u64, ARITY>::open(&path) }.unwrap();
+        assert_eq!(mapped.leaf_count(), tree.leaf_count());
+        assert_eq!(mapped.height(), tree.height());
+        assert!(tree.eq_full(&mapped.to_merkle_tree(Hasher::new())));
+        assert_eq!(mapped.audit(&mut Hasher::new()), Ok(()));
+        if !tree.is_empty() {
+            assert_eq!(mapped.root_ref(), tree.root_ref());
+            for lvl in 0..tree.height() {
+                let node = NodeId { lvl, index: tree.get_lvl(lvl).len() - 1 };
+                assert_eq!(mapped.get_node(node), tree.get_node(node));
+            }
+            for id in (0..tree.leaf_count()).map(LeafId::new) {
+                assert_eq!(mapped.proof_owned(id), tree.proof_owned(id));
+            }
+        }
+        drop(mapped);
+
+        // another arity
+        let err = unsafe { MappedMerkleTree::<u64, 4>::open(&path) }.err().unwrap();
+        assert!(matches!(err, MtMapError::ArityMismatch { expected: 4, actual } if actual == ARITY));
+
+        // truncated file
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        let err = unsafe { MappedMerkleTree::<u64, ARITY>::open(&path) }.err().unwrap();
+        assert!(matches!(err, MtMapError::Malformed(_)));
+
+        // tampered node is found by audit
+        if tree.height() > 1 {
+            let mut bytes = bytes.clone();
+            let at = bytes.len() - 1;
+            bytes[at] ^= 1;
+            std::fs::write(&path, &bytes).unwrap();
+            let mapped = unsafe { MappedMerkleTree::<u64, ARITY>::open(&path) }.unwrap();
+            let root = NodeId { lvl: tree.height() - 1, index: 0 };
+            assert_eq!(mapped.audit(&mut Hasher::new()), Err(root));
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    let vecs = vec![
+        vec![],
+        vec![1],
+        vec![1, 2],
+        (1u64..=9).collect::<Vec<_>>(),
+        (1u64..=26).collect::<Vec<_>>(),
+        (1u64..=27).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+        (1u64..=58).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+    ];
+    for vec in &vecs {
+        for pop in [0, 1, 3].into_iter().filter(|&pop| pop <= vec.len()) {
+            test::<2>(vec, pop);
+            test::<3>(vec, pop);
+            test::<5>(vec, pop);
+        }
+    }
+
+    let path = std::env::temp_dir().join(format!("mt_mapped_{}_magic.bin", std::process::id()));
+    std::fs::write(&path, [0; 64]).unwrap();
+    let err = unsafe { MappedMerkleTree::<u64, 2>::open(&path) }.err().unwrap();
+    assert!(matches!(err, MtMapError::WrongMagic));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn serde_hardened_test() {
+    use serde::de::DeserializeSeed;
+    use crate::{MerkleTreeSerdeError, MtSerde, MtSerdeLimits, MtSerdeSeed};
+    type Hasher = UnsecureHasher;
+    let mut rng = rand::rng();
+
+    fn test<const ARITY: usize>(vec: &[u64]) {
+        let tree = MerkleTree::<_, _, ARITY>::new_from_data(Hasher::new(), vec.to_vec());
+        assert!(serde_json::to_value(&tree).unwrap()["lvls"].is_null());
+
+        let json = serde_json::to_string(&tree.serializable_full()).unwrap();
+        let full: MtSerde<u64, ARITY> = serde_json::from_str(&json).unwrap();
+        assert!(full.has_lvls());
+        assert!(tree.eq_full(&full.to_merkle_tree(Hasher::new()).unwrap()));
+        let full: MtSerde<u64, ARITY> = serde_json::from_str(&json).unwrap();
+        let mut lazy = full.to_merkle_tree_lazy(Hasher::new()).unwrap();
+        assert!(tree.eq_full(&lazy));
+        assert_eq!(lazy.audit(), Ok(()));
+
+        // limits
+        let limits = |max_leafs| MtSerdeLimits { max_leafs, ..MtSerdeLimits::default() };
+        let seed = MtSerdeSeed::<u64, ARITY>::new(limits(vec.len()));
+        assert!(seed.deserialize(&mut serde_json::Deserializer::from_str(&json)).is_ok());
+        if !vec.is_empty() {
+            let seed = MtSerdeSeed::<u64, ARITY>::new(limits(vec.len() - 1));
+            let err = seed.deserialize(&mut serde_json::Deserializer::from_str(&json)).err().unwrap();
+            assert!(err.to_string().contains("exceeds the limit"));
+        }
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let load = |value: &serde_json::Value| serde_json::from_value::<MtSerde<u64, ARITY>>(value.clone()).unwrap();
+
+        // tampered root
+        if !vec.is_empty() {
+            let mut tampered = value.clone();
+            tampered["root"] = (tree.root() ^ 1).into();
+            let err = load(&tampered).to_merkle_tree(Hasher::new()).err().unwrap();
+            assert!(matches!(err, MerkleTreeSerdeError::WrongRoot(..)));
+            assert!(err.to_string().contains("tampered"));
+        }
+
+        // tampered shape
+        if tree.height() > 1 {
+            let mut tampered = value.clone();
+            tampered["lvls"][0].as_array_mut().unwrap().pop();
+            let err = load(&tampered).to_merkle_tree_lazy(Hasher::new()).err().unwrap();
+            assert!(matches!(err, MerkleTreeSerdeError::WrongLvlLen { lvl: 1, .. } | MerkleTreeSerdeError::WrongRoot(..)));
+
+            let mut tampered = value.clone();
+            tampered["lvls"].as_array_mut().unwrap().pop();
+            let err = load(&tampered).to_merkle_tree_lazy(Hasher::new()).err().unwrap();
+            assert!(matches!(err, MerkleTreeSerdeError::WrongLvlCount { .. }));
+        }
+
+        // tampered node: lazy loading doesn't notice it, audit & eager loading do
+        if tree.height() > 2 {
+            let last = tree.get_lvl(1).len() - 1;
+            let mut tampered = value.clone();
+            tampered["lvls"][0][last] = (value["lvls"][0][last].as_u64().unwrap() ^ 1).into();
+            let mut lazy = load(&tampered).to_merkle_tree_lazy(Hasher::new()).unwrap();
+            let node = NodeId { lvl: 1, index: last };
+            assert_eq!(lazy.audit(), Err(node));
+            let err = load(&tampered).to_merkle_tree(Hasher::new()).err().unwrap();
+            assert!(matches!(err, MerkleTreeSerdeError::TamperedNode(x) if x == node));
+        }
+    }
+
+    let vecs = vec![
+        vec![],
+        vec![1],
+        vec![1, 2],
+        (1u64..=9).collect::<Vec<_>>(),
+        (1u64..=26).collect::<Vec<_>>(),
+        (1u64..=27).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+        (1u64..=58).map(|_|rng.next_u64()).collect::<Vec<_>>(),
+    ];
+    for vec in &vecs {
+        test::<2>(vec);
+        test::<3>(vec);
+        test::<5>(vec);
+    }
+
+    // arity is rejected before the levels are read
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), 1u64..=10);
+    let json = serde_json::to_string(&tree.serializable_full()).unwrap();
+    let err = serde_json::from_str::<MtSerde<u64, 2>>(&json).err().unwrap();
+    assert!(err.to_string().contains("Invalid arity"));
+    assert_eq!(err.column(), json.find(",\"lvls\"").unwrap());
+
+    let limits = MtSerdeLimits { early_arity_check: false, ..MtSerdeLimits::unlimited() };
+    let tree_de = MtSerdeSeed::<u64, 2>::new(limits).deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    let err = tree_de.to_merkle_tree(Hasher::new()).err().unwrap();
+    assert!(matches!(err, MerkleTreeSerdeError::InvalidArity(2, 3)));
+
+    // plain deserialization applies the default limits, the seed can lift them
+    use serde::de::value::{Error as ValueError, SeqDeserializer};
+    let huge = || {
+        let leafs = SeqDeserializer::<_, ValueError>::new(std::iter::repeat_n(0u64, MtSerdeLimits::DEFAULT_MAX_LEAFS + 1));
+        SeqDeserializer::<_, ValueError>::new(std::iter::once(leafs))
+    };
+    let err = <MtSerde<u64, 2> as serde::Deserialize>::deserialize(huge()).err().unwrap();
+    assert!(err.to_string().contains("leafs"));
+    let err = MtSerdeSeed::<u64, 2>::new(MtSerdeLimits::unlimited()).deserialize(huge()).err().unwrap();
+    assert!(!err.to_string().contains("leafs"));
+
+    let tree = MerkleTree::<_, _, 2>::new_from_data(Hasher::new(), 1u64..=10);
+    let json = serde_json::to_string(&tree).unwrap();
+    let limits = MtSerdeLimits { max_leafs: 9, ..MtSerdeLimits::default() };
+    assert!(MtSerdeSeed::<u64, 2>::new(limits).deserialize(&mut serde_json::Deserializer::from_str(&json)).is_err());
+    assert!(tree.eq_full(&serde_json::from_str(&json).unwrap()));
+
+    // sequence without the optional fields
+    let value = serde_json::to_value(tree.serializable()).unwrap();
+    let value = serde_json::json!([value["leafs"], value["root"], value["arity"]]);
+    let tree_de = serde_json::from_value::<MtSerde<u64, 2>>(value).unwrap();
+    assert!(tree.eq_full(&tree_de.to_merkle_tree(Hasher::new()).unwrap()));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn serde_hasher_config_test() {
+    use crate::{MerkleTreeSerdeError, MtHasher, MtHasherDescriptor, MtHasherId, MtSerde};
+
+    #[derive(Debug)]
+    struct KeyedHasher<const ALT: bool> {
+        key: u64,
+        inner: UnsecureHasher,
+    }
+    impl<const ALT: bool> MtHasher<u64> for KeyedHasher<ALT> {
+        fn hash_one_ref(&mut self, hash: &u64) {
+            self.inner.hash_one_ref(hash);
+        }
+        fn finish(&mut self) -> u64 {
+            self.inner.hash_one_ref(&self.key);
+            self.inner.finish()
+        }
+        fn is_the_same(&self, other: &Self) -> bool {
+            self.key == other.key
+        }
+    }
+    impl<const ALT: bool> MtHasherId for KeyedHasher<ALT> {
+        const ALGORITHM: &'static str = if ALT { "keyed-alt" } else { "keyed" };
+    }
+    impl<const ALT: bool> MtHasherDescriptor for KeyedHasher<ALT> {
+        type Params = u64;
+        fn params(&self) -> u64 {
+            self.key
+        }
+        fn from_params(key: u64) -> Self {
+            Self { key, inner: UnsecureHasher::new() }
+        }
+    }
+    impl<const ALT: bool> Default for KeyedHasher<ALT> {
+        fn default() -> Self {
+            Self::from_params(0)
+        }
+    }
+    type Tree<const ALT: bool> = MerkleTree<u64, KeyedHasher<ALT>, 3>;
+    type TreeSerde = MtSerde<u64, 3, u64>;
+    let load = |json: &str|serde_json::from_str::<TreeSerde>(json).unwrap();
+
+    let tree = Tree::<false>::new_from_leafs(KeyedHasher::from_params(7), 1..=20);
+    let json = serde_json::to_string(&tree.serializable_with_hasher()).unwrap();
+    let tree_de: Tree<false> = load(&json).to_merkle_tree_restored().unwrap();
+    assert!(tree.eq_full(&tree_de));
+    assert_eq!(tree_de.hasher().key, 7);
+    assert!(tree.ne_weak(&Tree::<false>::new_from_leafs(KeyedHasher::from_params(8), 1..=20)));
+
+    // another configuration of the same algorithm
+    let err = load(&json).to_merkle_tree_checked(KeyedHasher::<false>::from_params(8)).err().unwrap();
+    assert!(matches!(err, MerkleTreeSerdeError::HasherParamsMismatch(_)));
+    assert!(load(&json).to_merkle_tree_checked(KeyedHasher::<false>::from_params(7)).is_ok());
+
+    // tampered parameters
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["hasher"]["params"] = 8.into();
+    let err = serde_json::from_value::<TreeSerde>(value).unwrap().to_merkle_tree_restored::<KeyedHasher<false>>().err().unwrap();
+    assert!(err.to_string().contains("tampered"));
+
+    // another algorithm
+    let err = load(&json).to_merkle_tree_restored::<KeyedHasher<true>>().err().unwrap();
+    assert!(err.to_string().contains("\"keyed\""));
+
+    // the recorded configuration is tested on the standard path too
+    let err = load(&json).to_merkle_tree(KeyedHasher::<false>::from_params(8)).err().unwrap();
+    assert!(matches!(err, MerkleTreeSerdeError::HasherParamsMismatch(_)));
+    let err = load(&json).to_merkle_tree_lazy(KeyedHasher::<false>::from_params(8)).err().unwrap();
+    assert!(matches!(err, MerkleTreeSerdeError::HasherParamsMismatch(_)));
+    // without the test the root is wrong
+    let err = load(&json).to_merkle_tree_unchecked(KeyedHasher::<false>::from_params(8)).err().unwrap();
+    assert!(!matches!(err, MerkleTreeSerdeError::HasherParamsMismatch(_)));
+    let json = serde_json::to_string(&tree).unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&json).unwrap()["hasher"]["params"], 7);
+    let tree_de: Tree<false> = serde_json::from_str(&json).unwrap();
+    assert!(tree.eq_full(&tree_de));
+    assert_eq!(tree_de.hasher().key, 7);
+    let err = serde_json::from_str::<Tree<true>>(&json).err().unwrap();
+    assert!(err.to_string().contains("\"keyed\""));
+
+    // configuration is optional, but strict loading requires it
+    let json = serde_json::to_string(&tree.serializable()).unwrap();
+    assert!(serde_json::from_str::<serde_json::Value>(&json).unwrap()["hasher"].is_null());
+    assert!(load(&json).to_merkle_tree(KeyedHasher::<false>::from_params(7)).is_ok());
+    // without configuration the default hasher is used
+    assert!(serde_json::from_str::<Tree<false>>(&json).is_err());
+    let err = load(&json).to_merkle_tree_checked(KeyedHasher::<false>::from_params(7)).err().unwrap();
+    assert!(matches!(err, MerkleTreeSerdeError::MissingHasher));
+    let json = serde_json::to_string(&tree.serializable_full().with_hasher(tree.hasher())).unwrap();
+    assert!(tree.eq_full(&load(&json).to_merkle_tree_restored::<KeyedHasher<false>>().unwrap()));
+
+    // recorded configuration doesn't prevent plain loading
+    let unsecure = MerkleTree::<u64, _, 3>::new_from_leafs(UnsecureHasher::new(), 1..=20);
+    let json = serde_json::to_string(&unsecure.serializable_with_hasher()).unwrap();
+    assert!(unsecure.eq_full(&serde_json::from_str(&json).unwrap()));
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn envelope_test() {
+    use crate::{MtEnvelope, MtEnvelopeError, MtHasherId, MtProof, MtSerde, ProofError};
+    type Hasher = UnsecureHasher;
+
+    struct OtherHasher;
+    impl MtHasherId for OtherHasher {
+        const ALGORITHM: &'static str = "other";
+    }
+
+    let vec: Vec<u64> = (1..=23).collect();
+    let tree = MerkleTree::<_, _, 3>::new_from_data(Hasher::new(), vec.clone());
+    let mut hasher = Hasher::new();
+    let id = LeafId::new(7);
+    let leaf = Hasher::hash_data_static(vec[7]);
+
+    let envelope = tree.proof_envelope(id);
+    assert_eq!(envelope, MtEnvelope::from_proof::<Hasher>(tree.proof_owned(id)).unwrap());
+    assert_eq!(envelope.algorithm(), "unsecure-std-default");
+    assert_eq!((envelope.arity(), envelope.leaf_index(), envelope.tree_size()), (3, Some(id), vec.len()));
+    let json = serde_json::to_value(&envelope).unwrap();
+
+    // dispatch by header
+    let header: MtEnvelope<serde::de::IgnoredAny> = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(header.version(), MtEnvelope::<()>::VERSION);
+    assert_eq!(header.algorithm(), Hasher::ALGORITHM);
+    assert_eq!(header.arity(), 3);
+    assert_eq!(header.padding(), "duplicate-last");
+
+    let envelope: MtEnvelope<MtProof<u64, 3>> = serde_json::from_value(json.clone()).unwrap();
+    assert!(envelope.verify(tree.root_ref(), leaf, &mut hasher));
+    assert_eq!(envelope.clone().open::<Hasher>(), Ok(tree.proof_owned(id)));
+    assert_eq!(
+        envelope.verify_detailed(tree.root_ref(), leaf + 1, &mut hasher), 
+        Err(MtEnvelopeError::Proof(ProofError::WrongNode(0)))
+    );
+
+    // mismatched parameters
+    assert_eq!(
+        envelope.clone().open::<OtherHasher>(),
+        Err(MtEnvelopeError::AlgorithmMismatch { expected: "other".to_string(), actual: Hasher::ALGORITHM.to_string() })
+    );
+    let envelope: MtEnvelope<MtProof<u64, 2>> = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(envelope.open::<Hasher>(), Err(MtEnvelopeError::ArityMismatch { expected: 2, actual: 3 }));
+
+    let tampered = |field: &str, value: serde_json::Value|{
+        let mut json = json.clone();
+        json[field] = value;
+        serde_json::from_value::<MtEnvelope<MtProof<u64, 3>>>(json).unwrap()
+    };
+    let envelope = tampered("version", 2.into());
+    assert_eq!(
+        envelope.verify_detailed(tree.root_ref(), leaf, &mut hasher), 
+        Err(MtEnvelopeError::UnsupportedVersion { supported: 1, actual: 2 })
+    );
+    let envelope = tampered("padding", "zeros".into());
+    assert_eq!(
+        envelope.open::<Hasher>(), 
+        Err(MtEnvelopeError::PaddingMismatch { expected: "duplicate-last".to_string(), actual: "zeros".to_string() })
+    );
+    let envelope = tampered("tree_size", 24.into());
+    assert_eq!(envelope.open::<Hasher>(), Err(MtEnvelopeError::TreeSizeMismatch { header: 24, body: 23 }));
+    let envelope = tampered("leaf_index", 8.into());
+    assert_eq!(envelope.open::<Hasher>(), Err(MtEnvelopeError::LeafIndexMismatch { header: Some(8), body: Some(7) }));
+
+    // tree envelope
+    let envelope = MtEnvelope::from_tree(&tree);
+    assert_eq!((envelope.leaf_index(), envelope.tree_size()), (None, vec.len()));
+    let json = serde_json::to_string(&envelope).unwrap();
+    let envelope: MtEnvelope<MtSerde<u64, 3>> = serde_json::from_str(&json).unwrap();
+    let tree_de = envelope.open::<Hasher>().unwrap().to_merkle_tree(Hasher::new()).unwrap();
+    assert!(tree.eq_full(&tree_de));
+    let envelope: MtEnvelope<MtSerde<u64, 3>> = serde_json::from_str(&json).unwrap();
+    assert!(matches!(envelope.open::<OtherHasher>(), Err(MtEnvelopeError::AlgorithmMismatch { .. })));
+}
+
 #[cfg(feature = "serde_json")]
 #[test]
 fn serde_test() {
@@ -875,13 +3079,13 @@ fn serde_test() {
             let proof_deser: crate::MtProof<u64, ARITY> = serde_json::from_str(&proof_ser).unwrap();
             
             let mut hasher = Hasher::new();
-            assert!(proof.verify_data(vec[7], &mut hasher));
-            assert!(!proof.verify_data(vec[8], &mut hasher));
-            assert!(!proof.verify_data(vec[5], &mut hasher));
+            assert!(proof.verify_data_with_root(x_tree.root_ref(), vec[7], &mut hasher));
+            assert!(!proof.verify_data_with_root(x_tree.root_ref(), vec[8], &mut hasher));
+            assert!(!proof.verify_data_with_root(x_tree.root_ref(), vec[5], &mut hasher));
             
-            assert!(proof_deser.verify_data(vec[7], &mut hasher));
-            assert!(!proof_deser.verify_data(vec[8], &mut hasher));
-            assert!(!proof_deser.verify_data(vec[5], &mut hasher));
+            assert!(proof_deser.verify_data_with_root(x_tree.root_ref(), vec[7], &mut hasher));
+            assert!(!proof_deser.verify_data_with_root(x_tree.root_ref(), vec[8], &mut hasher));
+            assert!(!proof_deser.verify_data_with_root(x_tree.root_ref(), vec[5], &mut hasher));
         }
     }
 
@@ -921,6 +3125,17 @@ fn pop_test() {
             assert_eq!(removed_hash, Hasher::hash_data_static(vec[to]));
         }
         assert!(tree.is_empty());
+
+        // push after batched push & pop
+        let mut tree = Tree::<N>::new_from_data(Hasher::new(), vec.clone());
+        tree.pop();
+        for (to, data) in vec.iter().copied().enumerate().skip(vec.len().saturating_sub(1)) {
+            tree.push_data(data);
+            tree.push_data(data + 1);
+            let mut tree_must = Tree::<N>::new_from_data(Hasher::new(), vec[0..=to].iter().cloned());
+            tree_must.push_data(data + 1);
+            assert!(tree.eq_full(&tree_must), "{tree:?}\n=?=\n{tree_must:?}");
+        }
     }
     
     let vecs = vec![
@@ -943,6 +3158,41 @@ fn pop_test() {
     }
 }
 
+#[test]
+fn push_after_batched_test() {
+    type Hasher = UnsecureHasher;
+    type Tree<const ARITY: usize> = MerkleTree<u64, Hasher, ARITY>;
+
+    // `push` must grow the tree that was built by other operations
+    fn test<const N: usize>(leafs: &[u64]) {
+        for split in 0..leafs.len() {
+            let mut batched = Tree::<N>::new_minimal(Hasher::new());
+            batched.push_batched(leafs[..split].iter().copied());
+            let mut popped = Tree::<N>::new_from_leafs(Hasher::new(), leafs.iter().copied());
+            let mut merged = Tree::<N>::new_minimal(Hasher::new());
+            merged.merge([Tree::<N>::new_from_leafs(Hasher::new(), leafs[..split].iter().copied())]);
+            while popped.leaf_count() > split {
+                popped.pop();
+            }
+
+            for &leaf in &leafs[split..] {
+                batched.push(leaf);
+                popped.push(leaf);
+                merged.push(leaf);
+            }
+            let tree_must = Tree::<N>::new_from_leafs(Hasher::new(), leafs.iter().copied());
+            assert!(batched.eq_full(&tree_must), "{batched:?}\n=?=\n{tree_must:?}");
+            assert!(popped.eq_full(&tree_must), "{popped:?}\n=?=\n{tree_must:?}");
+            assert!(merged.eq_full(&tree_must), "{merged:?}\n=?=\n{tree_must:?}");
+        }
+    }
+
+    let leafs: Vec<u64> = (1u64..=40).collect();
+    test::<2>(&leafs);
+    test::<3>(&leafs);
+    test::<5>(&leafs);
+}
+
 #[test]
 fn swap_remove_test() {
     type Hasher = UnsecureHasher; // AddHasher;